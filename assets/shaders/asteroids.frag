@@ -0,0 +1,23 @@
+#version 330 core
+
+#include "lighting.glsl"
+
+uniform vec3 CameraPos;
+uniform vec3 SunPos;
+uniform vec3 LightColor;
+uniform vec3 SurfaceColor;
+
+in VS_OUTPUT {
+    vec3 Position;
+    vec3 Normal;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    vec3 to_camera = CameraPos - IN.Position;
+    vec3 to_light = SunPos - IN.Position;
+
+    Color = vec4(directional_light(SurfaceColor, IN.Normal, to_light, to_camera, LightColor), 1.0);
+}
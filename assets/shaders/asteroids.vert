@@ -0,0 +1,24 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec3 Normal;
+layout (location = 2) in vec2 Uv;
+// one model matrix per instance, taking locations 3 to 6
+layout (location = 3) in mat4 Model;
+
+uniform mat4 ViewProjection;
+
+out VS_OUTPUT {
+    vec3 Position;
+    vec3 Normal;
+} OUT;
+
+void main()
+{
+    vec4 world_position = Model * vec4(Position, 1.0);
+
+    gl_Position = ViewProjection * world_position;
+    OUT.Position = world_position.xyz;
+    // instances only translate and scale uniformly
+    OUT.Normal = mat3(Model) * Normal;
+}
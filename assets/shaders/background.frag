@@ -0,0 +1,17 @@
+#version 330 core
+
+uniform vec3 PoleColor;
+uniform vec3 EclipticColor;
+
+in VS_OUTPUT {
+    vec3 Direction;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    // sine of ecliptic latitude, world Z is ecliptic north
+    float latitude = abs(normalize(IN.Direction).z);
+    Color = vec4(mix(EclipticColor, PoleColor, latitude), 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+uniform mat4 InverseViewProjection;
+
+layout (location = 0) in vec2 Position;
+
+out VS_OUTPUT {
+    vec3 Direction;
+} OUT;
+
+void main()
+{
+    gl_Position = vec4(Position, 0.0, 1.0);
+    vec4 far = InverseViewProjection * vec4(Position, 1.0, 1.0);
+    OUT.Direction = far.xyz / far.w;
+}
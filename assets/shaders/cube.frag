@@ -0,0 +1,25 @@
+#version 330 core
+
+#include "lighting.glsl"
+
+uniform vec3 CameraPos;
+uniform vec3 LightDir;
+uniform vec3 LightColor;
+
+in VS_OUTPUT {
+    vec3 Position;
+    vec4 Color;
+    vec3 Normal;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    vec3 to_camera = CameraPos - IN.Position;
+
+    Color = vec4(
+        directional_light(IN.Color.rgb, IN.Normal, LightDir, to_camera, LightColor),
+        IN.Color.a
+    );
+}
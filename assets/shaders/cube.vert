@@ -0,0 +1,23 @@
+#version 330 core
+
+#include "srgb.glsl"
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec4 Color;
+layout (location = 2) in vec3 Normal;
+
+uniform mat4 ViewProjection;
+
+out VS_OUTPUT {
+    vec3 Position;
+    vec4 Color;
+    vec3 Normal;
+} OUT;
+
+void main()
+{
+    gl_Position = ViewProjection * vec4(Position, 1.0);
+    OUT.Position = Position;
+    OUT.Color = vec4(srgb_to_linear(Color.rgb), Color.a);
+    OUT.Normal = Normal;
+}
@@ -0,0 +1,17 @@
+#version 330 core
+
+uniform vec3 HaloColor;
+
+in VS_OUTPUT {
+    vec2 Corner;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    float r = length(IN.Corner);
+    float glow = pow(max(1.0 - r, 0.0), 2.5);
+
+    Color = vec4(HaloColor, glow);
+}
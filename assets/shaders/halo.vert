@@ -0,0 +1,21 @@
+#version 330 core
+
+layout (location = 0) in vec2 Corner;
+
+uniform mat4 ViewProjection;
+uniform vec3 Center;
+uniform vec3 Right;
+uniform vec3 Up;
+uniform float Radius;
+
+out VS_OUTPUT {
+    vec2 Corner;
+} OUT;
+
+void main()
+{
+    vec3 position = Center + (Right * Corner.x + Up * Corner.y) * Radius;
+
+    gl_Position = ViewProjection * vec4(position, 1.0);
+    OUT.Corner = Corner;
+}
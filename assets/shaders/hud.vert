@@ -0,0 +1,18 @@
+#version 330 core
+
+#include "srgb.glsl"
+
+layout (location = 0) in vec2 Position;
+layout (location = 1) in vec4 Color;
+
+uniform mat4 Projection;
+
+out VS_OUTPUT {
+    vec4 Color;
+} OUT;
+
+void main()
+{
+    gl_Position = Projection * vec4(Position, 0.0, 1.0);
+    OUT.Color = vec4(srgb_to_linear(Color.rgb), Color.a);
+}
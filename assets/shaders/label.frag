@@ -0,0 +1,14 @@
+#version 330 core
+
+uniform sampler2D Font;
+
+in VS_OUTPUT {
+    vec2 Uv;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    Color = texture(Font, IN.Uv);
+}
@@ -0,0 +1,19 @@
+// Lighting helpers shared by mesh shaders.
+
+const float AMBIENT = 0.08;
+const float SPECULAR = 0.25;
+const float SHININESS = 32.0;
+
+// Lambert diffuse with a small Blinn-Phong highlight, from a directional light.
+// `to_light` and `to_camera` point away from the surface.
+vec3 directional_light(vec3 albedo, vec3 normal, vec3 to_light, vec3 to_camera, vec3 light_color)
+{
+    vec3 n = normalize(normal);
+    vec3 l = normalize(to_light);
+    float diffuse = max(dot(n, l), 0.0);
+
+    vec3 h = normalize(l + normalize(to_camera));
+    float specular = diffuse > 0.0 ? pow(max(dot(n, h), 0.0), SHININESS) * SPECULAR : 0.0;
+
+    return albedo * (AMBIENT + diffuse * light_color) + specular * light_color;
+}
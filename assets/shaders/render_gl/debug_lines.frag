@@ -8,5 +8,5 @@ out vec4 Color;
 
 void main()
 {
-    Color = IN.Color, 1.0f;
+    Color = IN.Color;
 }
@@ -1,9 +1,13 @@
 #version 330 core
 
+#include "../srgb.glsl"
+
 layout (location = 0) in vec3 Position;
 layout (location = 1) in vec4 Color;
 
 uniform mat4 ViewProjection;
+// offset of translated lines, zero for the others
+uniform vec3 Translation;
 
 out VS_OUTPUT {
     vec4 Color;
@@ -11,6 +15,6 @@ out VS_OUTPUT {
 
 void main()
 {
-    gl_Position = ViewProjection * vec4(Position, 1.0);
-    OUT.Color = Color;
+    gl_Position = ViewProjection * vec4(Position + Translation, 1.0);
+    OUT.Color = vec4(srgb_to_linear(Color.rgb), Color.a);
 }
@@ -0,0 +1,20 @@
+#version 330 core
+
+uniform vec3 RingColor;
+
+in VS_OUTPUT {
+    vec2 Uv;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    float r = IN.Uv.x;
+
+    // concentric bands, fading out towards both edges
+    float bands = 0.6 + 0.4 * sin(r * 60.0) * sin(r * 17.0);
+    float edge = smoothstep(0.0, 0.08, r) * smoothstep(1.0, 0.85, r);
+
+    Color = vec4(RingColor * bands, 0.7 * bands * edge);
+}
@@ -0,0 +1,14 @@
+#version 330 core
+
+uniform samplerCube Skybox;
+
+in VS_OUTPUT {
+    vec3 Direction;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    Color = texture(Skybox, IN.Direction);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+
+uniform mat4 View;
+uniform mat4 Projection;
+
+out VS_OUTPUT {
+    vec3 Direction;
+} OUT;
+
+void main()
+{
+    gl_Position = Projection * View * vec4(Position, 1.0);
+    OUT.Direction = Position;
+}
@@ -0,0 +1,33 @@
+#version 330 core
+
+#include "lighting.glsl"
+
+uniform vec3 CameraPos;
+uniform vec3 LightDir;
+uniform vec3 LightColor;
+uniform vec3 SurfaceColor;
+uniform bool Emissive;
+uniform bool Textured;
+uniform sampler2D SurfaceTexture;
+
+in VS_OUTPUT {
+    vec3 Position;
+    vec3 Normal;
+    vec2 Uv;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    vec3 albedo = Textured ? texture(SurfaceTexture, IN.Uv).rgb : SurfaceColor;
+
+    if (Emissive) {
+        Color = vec4(albedo, 1.0);
+        return;
+    }
+
+    vec3 to_camera = CameraPos - IN.Position;
+
+    Color = vec4(directional_light(albedo, IN.Normal, LightDir, to_camera, LightColor), 1.0);
+}
@@ -0,0 +1,24 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec3 Normal;
+layout (location = 2) in vec2 Uv;
+
+uniform mat4 ViewProjection;
+uniform mat4 Model;
+
+out VS_OUTPUT {
+    vec3 Position;
+    vec3 Normal;
+    vec2 Uv;
+} OUT;
+
+void main()
+{
+    vec4 world_position = Model * vec4(Position, 1.0);
+
+    gl_Position = ViewProjection * world_position;
+    OUT.Position = world_position.xyz;
+    OUT.Normal = transpose(inverse(mat3(Model))) * Normal;
+    OUT.Uv = Uv;
+}
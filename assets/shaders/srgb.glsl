@@ -0,0 +1,9 @@
+// Conversion of colors given in sRGB, matching render_gl::srgb on the CPU.
+
+// Linear intensity of sRGB encoded color channels; the framebuffer encodes it back on write.
+vec3 srgb_to_linear(vec3 color)
+{
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, vec3(lessThanEqual(color, vec3(0.04045))));
+}
@@ -0,0 +1,15 @@
+#version 330 core
+
+in VS_OUTPUT {
+    float Brightness;
+} IN;
+
+out vec4 Color;
+
+void main()
+{
+    float r = length(gl_PointCoord * 2.0 - 1.0);
+    float glow = IN.Brightness * (1.0 - smoothstep(0.5, 1.0, r));
+
+    Color = vec4(vec3(glow), 1.0);
+}
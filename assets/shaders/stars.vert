@@ -0,0 +1,19 @@
+#version 330 core
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in float Size;
+
+uniform mat4 View;
+uniform mat4 Projection;
+
+out VS_OUTPUT {
+    float Brightness;
+} OUT;
+
+void main()
+{
+    gl_Position = Projection * View * vec4(Position, 1.0);
+    gl_PointSize = Size;
+    // points at the smallest size are dimmed instead of shrunk further
+    OUT.Brightness = min(Size / 2.0, 1.0);
+}
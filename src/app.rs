@@ -0,0 +1,1512 @@
+use crate::resources::Resources;
+use crate::{
+    aspects, asteroids, background, bench, camera, cli, comet, cube, debug, frame_limit,
+    gl_context, grid, halo, hud, keybindings, labels, orbit, planets, render_gl, retrograde, rings,
+    screenshot, settings, sim_clock, sky_circles, skybox, sphere, stars, texture_loader, trail,
+    window_icon, zodiac,
+};
+use failure::err_msg;
+use floating_duration::TimeAsFloat;
+use nalgebra as na;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Window with OpenGL context and loaded scene, ready to run the main loop.
+pub struct App {
+    options: cli::Options,
+    settings: settings::Settings,
+    res: Resources,
+    scene: Scene,
+    gl: gl::Gl,
+    // GL objects delete themselves on drop, so the context must be dropped after them
+    gl_context: sdl2::video::GLContext,
+    window: sdl2::video::Window,
+    _video_subsystem: sdl2::VideoSubsystem,
+    sdl: sdl2::Sdl,
+}
+
+impl App {
+    /// Initialize SDL, create window and OpenGL context for `options`, then load the scene.
+    pub fn new(options: cli::Options) -> Result<App, failure::Error> {
+        let settings = settings::Settings::from_options(&options);
+        let res = Resources::from_relative_exe_path(Path::new("assets"))?;
+        let sdl = sdl2::init().map_err(err_msg)?;
+        let video_subsystem = sdl.video().map_err(err_msg)?;
+
+        let (mut window, gl_context, (gl_major, gl_minor)) =
+            gl_context::create_window(&video_subsystem, options.msaa_samples, (4, 1), || {
+                let mut window_builder =
+                    video_subsystem.window(&settings.title, options.width, options.height);
+                window_builder.opengl().resizable().allow_highdpi();
+                if options.fullscreen {
+                    window_builder.fullscreen_desktop();
+                }
+                Ok(window_builder.build()?)
+            })?;
+        window_icon::set(&mut window, &res, "icon.png");
+        let gl = gl::Gl::load_with(|s| {
+            video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void
+        });
+        let msaa_samples = gl_context::negotiated_samples(&gl);
+        info!(
+            "Using OpenGL {}.{} core context, {}x MSAA",
+            gl_major, gl_minor, msaa_samples
+        );
+        if msaa_samples > 0 {
+            unsafe {
+                gl.Enable(gl::MULTISAMPLE);
+            }
+        }
+        let swap_interval = if settings.vsync {
+            sdl2::video::SwapInterval::VSync
+        } else {
+            sdl2::video::SwapInterval::Immediate
+        };
+        if let Err(e) = video_subsystem.gl_set_swap_interval(swap_interval) {
+            warn!("Failed to set swap interval: {}", e);
+        }
+        if gl_context::negotiated_srgb(&gl) {
+            unsafe {
+                gl.Enable(gl::FRAMEBUFFER_SRGB);
+            }
+        } else {
+            warn!("No sRGB framebuffer available, colors will look too dark");
+        }
+        let scene = Scene::load(&options, &res, &gl)?;
+
+        Ok(App {
+            options,
+            settings,
+            res,
+            scene,
+            gl,
+            gl_context,
+            window,
+            _video_subsystem: video_subsystem,
+            sdl,
+        })
+    }
+
+    /// Run the main loop until the window is closed.
+    pub fn run(self) -> Result<(), failure::Error> {
+        let App {
+            options,
+            mut settings,
+            res,
+            scene,
+            gl,
+            gl_context: _gl_context,
+            mut window,
+            _video_subsystem,
+            sdl,
+        } = self;
+
+        // fullscreen window may not have the requested size, and on high-DPI displays
+        // the drawable has more pixels than the window has points
+        let (drawable_width, drawable_height) = window.drawable_size();
+        let mut viewport =
+            render_gl::Viewport::for_window(drawable_width as i32, drawable_height as i32);
+        let color_buffer = render_gl::ColorBuffer::new();
+        let Scene {
+            mut debug_lines,
+            mut cube,
+            mut background,
+            mut skybox,
+            mut stars,
+            mut sphere,
+            mut halo,
+            mut asteroids,
+            mut hud,
+            mut labels,
+            key_bindings,
+            planets,
+            planet_orbits,
+            mut planet_rings,
+        } = scene;
+        // maps are decoded in the background, bodies keep their color until uploaded
+        let mut texture_loader = texture_loader::TextureLoader::new(&res)?;
+        for (i, planet) in planets.iter().enumerate() {
+            if let Some(ref name) = planet.texture {
+                texture_loader.request(i, name);
+            }
+        }
+        let mut planet_textures = planets
+            .iter()
+            .map(|_| None)
+            .collect::<Vec<Option<render_gl::Texture>>>();
+        // paths around the Sun only change with the scene, moon paths move with their parent
+        let orbit_paths_for = |debug_lines: &render_gl::DebugLines, scene| {
+            debug_lines.in_group("orbits", |debug_lines| {
+                planet_orbits
+                    .iter()
+                    .filter_map(|orbit| orbit.as_ref())
+                    .filter(|orbit| orbit.parent.is_none())
+                    .map(|orbit| {
+                        let segments = orbit.path_segments(128);
+                        orbit_path(debug_lines, orbit, segments, &scene)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        let mut orbit_paths_scene = orbit::SceneTransform {
+            origin: na::Point3::origin(),
+            distance_scale: orbit::DistanceScale::Linear,
+        };
+        let mut orbit_paths = orbit_paths_for(&debug_lines, orbit_paths_scene);
+        let moon_orbits = planet_orbits
+            .iter()
+            .filter_map(|orbit| orbit.as_ref())
+            .filter(|orbit| orbit.parent.is_some())
+            .collect::<Vec<_>>();
+        let moon_orbit_paths = debug_lines.in_group("orbits", |debug_lines| {
+            moon_orbits
+                .iter()
+                .map(|orbit| local_orbit_path(debug_lines, orbit, orbit.path_segments(128)))
+                .collect::<Vec<_>>()
+        });
+        // spin axes move with the bodies, so they are rebuilt every frame while shown
+        let mut planet_axes = Vec::new();
+        let mut comet_tails = Vec::new();
+        let earth_orbit = planets
+            .iter()
+            .position(|planet| planet.name == "Earth")
+            .and_then(|i| planet_orbits[i].as_ref());
+        let mut trails = debug_lines.in_group("trails", |debug_lines| {
+            planet_orbits
+                .iter()
+                .zip(planets.iter())
+                .map(|(orbit, planet)| {
+                    orbit.as_ref().map(|orbit| {
+                        trail::Trail::new(
+                            debug_lines,
+                            planet.trail_length.unwrap_or(settings.trail_length),
+                            orbit.period / 256.0,
+                            na::Vector4::new(0.9, 0.9, 1.0, 0.8),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let _zodiac_wheel = debug_lines.in_group("zodiac", |debug_lines| {
+            zodiac::ZodiacWheel::new(debug_lines, 350.0, &na::Vector3::z())
+        });
+        let _sky_circles = debug_lines.in_group("circles", |debug_lines| {
+            sky_circles::SkyCircles::new(debug_lines, 300.0)
+        });
+        let _reference_grid = debug_lines.in_group("grid", |debug_lines| {
+            grid::ReferenceGrid::new(debug_lines, 320.0, 10.0)
+        });
+        debug_lines.set_group_visible("grid", false);
+        debug_lines.set_group_visible("axes", false);
+        debug_lines.set_group_visible("orbits", settings.show_orbits);
+        debug_lines.set_group_visible("trails", settings.show_orbits);
+        let circles_width = debug_lines.set_group_line_width("circles", 2.0);
+        info!("Sky circle line width {}", circles_width);
+
+        let (viewport_width, viewport_height) = viewport.size();
+        let mut camera = camera::TargetCamera::new(
+            viewport_width as f32 / viewport_height as f32,
+            settings.fov,
+            0.01,
+            1000.0,
+            3.14 / 4.0,
+            2.0,
+        );
+        camera.movement.set_base_speed(settings.movement_speed);
+        camera.set_rotation_sensitivity(settings.rotation_sensitivity);
+        let bookmarks_path = camera::bookmarks::default_path();
+        let mut bookmarks = bookmarks_path
+            .as_ref()
+            .map(|path| {
+                camera::bookmarks::Bookmarks::load(path).unwrap_or_else(|e| {
+                    warn!("Ignoring bookmarks: {}", debug::failure_to_string(e.into()));
+                    camera::bookmarks::Bookmarks::default()
+                })
+            })
+            .unwrap_or_default();
+        let camera_target_marker = debug_lines.marker(camera.target, 0.25);
+        // markers dropped by the user, M adds one at the camera target and Delete removes the last
+        let mut user_markers: Vec<render_gl::PointMarker> = Vec::new();
+
+        // set up shared state for window
+
+        viewport.set_used(&gl);
+        color_buffer.set_clear_color(&gl, background.pole_color);
+
+        // main loop
+        let mut time = Instant::now();
+        let mut side_cam = false;
+        let mut take_screenshot = false;
+        let mut focused = true;
+        let mut mouse_grab = camera::MouseGrab::default();
+        // last cursor position in window points, none while outside the window
+        let mut hover_cursor = None;
+        let mut focus_index = 0;
+        let mut title_elapsed = 0.0;
+        let mut title_frames = 0;
+        let mut benchmark = options
+            .bench_frames
+            .map(|frames| bench::Benchmark::new(frames as usize));
+        let mut sim_clock = sim_clock::SimClock::new(settings.time_scale);
+        if let Some((year, month, day)) = options.start_date {
+            sim_clock.set_date(year, month, day)?;
+        }
+        if let Some(hours) = options.step_hours {
+            sim_clock.set_step_seconds(f64::from(hours) * 3600.0);
+        }
+
+        let mut event_pump = sdl.event_pump().map_err(err_msg)?;
+        'main: loop {
+            for event in event_pump.poll_iter() {
+                if let Some(has_focus) = window_focus_change(&event) {
+                    focused = has_focus;
+                    if !has_focus {
+                        // key releases are not delivered while unfocused
+                        camera.movement.release_all();
+                    }
+                }
+                handle_mouse_grab(&mut mouse_grab, &sdl.mouse(), &window, &event);
+                if let Some(cursor) = cursor_change(&event) {
+                    hover_cursor = cursor;
+                }
+
+                match event {
+                    sdl2::event::Event::Quit { .. } => break 'main,
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::C),
+                        ..
+                    } => {
+                        side_cam = !side_cam;
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Tab),
+                        keymod,
+                        ..
+                    } if !planets.is_empty() => {
+                        let backwards = keymod.intersects(
+                            sdl2::keyboard::Mod::LSHIFTMOD | sdl2::keyboard::Mod::RSHIFTMOD,
+                        );
+                        focus_index = planets::cycle_index(focus_index, planets.len(), !backwards);
+                        let sim_days = sim_clock.days_since_j2000();
+                        let scene = scene_transform(&settings, earth_orbit, sim_days);
+                        camera.focus_on(
+                            body_position(planet_orbits[focus_index].as_ref(), sim_days, &scene),
+                            1.0,
+                        );
+                        if camera.follow().is_some() {
+                            camera.set_follow(Some(focus_index));
+                        }
+                        info!("Focus: {}", planets[focus_index].name);
+                    }
+                    sdl2::event::Event::MouseButtonDown {
+                        mouse_btn: sdl2::mouse::MouseButton::Left,
+                        x,
+                        y,
+                        ..
+                    } => {
+                        let scale = render_gl::scale_factor(window.size(), window.drawable_size());
+                        let (origin, direction) = camera.screen_ray(
+                            na::Vector2::new(x as f32, y as f32) * scale,
+                            viewport.size(),
+                        );
+                        let sim_days = sim_clock.days_since_j2000();
+                        let scene = scene_transform(&settings, earth_orbit, sim_days);
+                        let bodies = body_spheres(
+                            &planets,
+                            &planet_orbits,
+                            sim_days,
+                            &scene,
+                            settings.planet_scale,
+                        );
+                        let hit = camera::picking::nearest_body(&origin, &direction, &bodies);
+
+                        if let Some((i, _)) = hit {
+                            focus_index = i;
+                            camera.focus_on(bodies[i].center, 1.0);
+                            if camera.follow().is_some() {
+                                camera.set_follow(Some(i));
+                            }
+                            info!("Focus: {}", planets[i].name);
+                        }
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::H),
+                        ..
+                    } => {
+                        let sim_days = sim_clock.days_since_j2000();
+                        let old_scene = scene_transform(&settings, earth_orbit, sim_days);
+                        settings.frame = settings.frame.toggle();
+                        let new_scene = scene_transform(&settings, earth_orbit, sim_days);
+                        // the new origin moves to where the old one was shown
+                        camera.shift(&-na::convert::<_, na::Vector3<f32>>(
+                            old_scene.apply(&new_scene.origin).coords,
+                        ));
+                        // last tracked position is in the old frame
+                        camera.set_follow(camera.follow());
+                        for trail in trails.iter_mut().filter_map(Option::as_mut) {
+                            trail.clear();
+                        }
+                        info!("Reference frame: {:?}", settings.frame);
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::J),
+                        ..
+                    } => {
+                        settings.distance_scale = settings.distance_scale.toggle();
+                        // positions in the old scale no longer match
+                        camera.set_follow(camera.follow());
+                        for trail in trails.iter_mut().filter_map(Option::as_mut) {
+                            trail.clear();
+                        }
+                        info!("Distance scale: {:?}", settings.distance_scale);
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::LeftBracket),
+                        ..
+                    } => {
+                        settings.planet_scale =
+                            planets::step_scale_factor(settings.planet_scale, false);
+                        info!("Planet scale: {:.2}x", settings.planet_scale);
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::RightBracket),
+                        ..
+                    } => {
+                        settings.planet_scale =
+                            planets::step_scale_factor(settings.planet_scale, true);
+                        info!("Planet scale: {:.2}x", settings.planet_scale);
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::G),
+                        ..
+                    } => settings.wireframe = !settings.wireframe,
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::L),
+                        ..
+                    } => toggle_group(&mut debug_lines, "grid"),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::K),
+                        ..
+                    } => {
+                        settings.show_orbits = !settings.show_orbits;
+                        debug_lines.set_group_visible("orbits", settings.show_orbits);
+                        debug_lines.set_group_visible("trails", settings.show_orbits);
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Z),
+                        ..
+                    } => toggle_group(&mut debug_lines, "zodiac"),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::X),
+                        ..
+                    } => toggle_group(&mut debug_lines, "axes"),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::V),
+                        ..
+                    } => settings.show_reticle = !settings.show_reticle,
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::T),
+                        ..
+                    } if !planets.is_empty() => {
+                        if camera.follow().is_some() {
+                            camera.set_follow(None);
+                            info!("Follow: off");
+                        } else {
+                            camera.set_follow(Some(focus_index));
+                            info!("Follow: {}", planets[focus_index].name);
+                        }
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Y),
+                        ..
+                    } => toggle_group(&mut debug_lines, "circles"),
+                    // with Shift these are > and <, which step the paused simulation
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(scancode @ sdl2::keyboard::Scancode::Period),
+                        keymod,
+                        ..
+                    }
+                    | sdl2::event::Event::KeyDown {
+                        scancode: Some(scancode @ sdl2::keyboard::Scancode::Comma),
+                        keymod,
+                        ..
+                    } if keymod.intersects(
+                        sdl2::keyboard::Mod::LSHIFTMOD | sdl2::keyboard::Mod::RSHIFTMOD,
+                    ) =>
+                    {
+                        if sim_clock.step(scancode == sdl2::keyboard::Scancode::Period) {
+                            info!("Date: {}", sim_clock.date());
+                        }
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Period),
+                        ..
+                    } => sim_clock.speed_up(),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Comma),
+                        ..
+                    } => sim_clock.slow_down(),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::P),
+                        ..
+                    } => sim_clock.toggle_pause(),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::M),
+                        ..
+                    } => user_markers.push(debug_lines.colored_marker(
+                        camera.target,
+                        na::Vector4::new(1.0, 0.6, 0.1, 1.0),
+                        0.5,
+                    )),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Delete),
+                        ..
+                    } => {
+                        if let Some(marker) = user_markers.pop() {
+                            marker.remove();
+                        }
+                    }
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::Backspace),
+                        ..
+                    } => sim_clock.reverse(),
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::F12),
+                        ..
+                    } => take_screenshot = true,
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::F2),
+                        ..
+                    } => settings.show_aspect_grid = !settings.show_aspect_grid,
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(sdl2::keyboard::Scancode::F5),
+                        ..
+                    } => {
+                        let reloads = vec![
+                            debug_lines.reload_shaders(&res),
+                            cube.reload_shaders(&res),
+                            background.reload_shaders(&res),
+                            skybox
+                                .as_mut()
+                                .map_or(Ok(()), |skybox| skybox.reload_shaders(&res)),
+                            sphere.reload_shaders(&res),
+                            labels.reload_shaders(&res),
+                            halo.reload_shaders(&res),
+                            asteroids.reload_shaders(&res),
+                            hud.reload_shaders(&res),
+                        ];
+                        let reloads = reloads
+                            .into_iter()
+                            .chain(stars.iter_mut().map(|stars| stars.reload_shaders(&res)))
+                            .chain(
+                                planet_rings
+                                    .iter_mut()
+                                    .filter_map(Option::as_mut)
+                                    .map(|rings| rings.reload_shaders(&res)),
+                            );
+                        for e in reloads.filter_map(Result::err) {
+                            error!("{}", debug::failure_to_string(e));
+                        }
+                    }
+                    sdl2::event::Event::Window {
+                        win_event: sdl2::event::WindowEvent::Resized(..),
+                        ..
+                    } => {
+                        // event size is in points, the viewport needs pixels
+                        let (w, h) = window.drawable_size();
+                        viewport.update_size(w as i32, h as i32);
+                        viewport.set_used(&gl);
+                        camera.update_aspect(w as f32 / h.max(1) as f32);
+                    }
+                    // Ctrl+1..9 stores a bookmark, 1..9 travels to it
+                    sdl2::event::Event::KeyDown {
+                        scancode: Some(scancode),
+                        keymod,
+                        repeat: false,
+                        ..
+                    } => {
+                        if let Some(slot) = bookmark_slot(scancode) {
+                            let store = keymod.intersects(
+                                sdl2::keyboard::Mod::LCTRLMOD | sdl2::keyboard::Mod::RCTRLMOD,
+                            );
+                            if store {
+                                bookmarks.set(slot, camera.save_state());
+                                info!("Stored bookmark {}", slot + 1);
+                                if let Some(ref path) = bookmarks_path {
+                                    if let Err(e) = bookmarks.save(path) {
+                                        error!("{}", debug::failure_to_string(e.into()));
+                                    }
+                                }
+                            } else if let Some(state) = bookmarks.get(slot) {
+                                camera.travel_to_state(state, 1.0);
+                                info!("Bookmark {}", slot + 1);
+                            }
+                        } else {
+                            handle_camera_event(&mut camera, &key_bindings, &event);
+                        }
+                    }
+                    e => handle_camera_event(&mut camera, &key_bindings, &e),
+                }
+            }
+            // idle while in the background, without counting the time spent there; benchmarks
+            // keep rendering
+            if !focused && benchmark.is_none() {
+                std::thread::sleep(Duration::from_millis(16));
+                time = Instant::now();
+                continue;
+            }
+
+            let frame_time = time.elapsed().as_fractional_secs();
+            time = Instant::now();
+            if let Some(ref mut benchmark) = benchmark {
+                if benchmark.record(frame_time) {
+                    if let Some(stats) = benchmark.stats() {
+                        println!("{}", stats);
+                    }
+                    break 'main;
+                }
+            }
+            let delta = frame_limit::clamp_delta(frame_time, frame_limit::MAX_FRAME_DELTA_SECS);
+            sim_clock.advance(delta);
+
+            // frame rate is measured from the real frame time
+            title_elapsed += frame_time;
+            title_frames += 1;
+            if title_elapsed >= 0.5 {
+                let mut title = format!("{} - {}", settings.title, sim_clock.date());
+                if sim_clock.is_paused() {
+                    title.push_str(" (paused)");
+                }
+                if sim_clock.is_reversed() {
+                    title.push_str(" (reversed)");
+                }
+                let sim_days = sim_clock.days_since_j2000();
+                let bodies = body_spheres(
+                    &planets,
+                    &planet_orbits,
+                    sim_days,
+                    &scene_transform(&settings, earth_orbit, sim_days),
+                    settings.planet_scale,
+                );
+                if let Some(i) = camera::picking::body_under_crosshair(&camera, &bodies) {
+                    title.push_str(&format!(" - looking at {}", planets[i].name));
+                }
+                if settings.show_fps {
+                    title.push_str(&format!(
+                        " - {:.0} fps, {:.2} ms",
+                        title_frames as f64 / title_elapsed,
+                        title_elapsed * 1000.0 / title_frames as f64
+                    ));
+                }
+                window.set_title(&title)?;
+                title_elapsed = 0.0;
+                title_frames = 0;
+            }
+            if let Some(body) = camera.follow() {
+                let sim_days = sim_clock.days_since_j2000();
+                let scene = scene_transform(&settings, earth_orbit, sim_days);
+                camera.track(body_position(
+                    planet_orbits[body].as_ref(),
+                    sim_days,
+                    &scene,
+                ));
+            }
+            // the target is brought to the focused body, so zoom stops short of its surface
+            camera.set_target_radius(
+                planets
+                    .get(focus_index)
+                    .map_or(0.0, |planet| planet.rendered_radius(settings.planet_scale)),
+            );
+            if camera.update(delta as f32) {
+                camera_target_marker.update_position(camera.target);
+            }
+
+            // GL objects are created here, on the thread owning the context
+            for decoded in texture_loader.ready() {
+                let texture = decoded
+                    .image
+                    .map_err(failure::Error::from)
+                    .and_then(|image| {
+                        render_gl::Texture::from_srgb_image(&gl, &image)
+                            .map_err(failure::Error::from)
+                    });
+                match texture {
+                    Ok(texture) => {
+                        info!("Loaded texture {}", decoded.name);
+                        planet_textures[decoded.key] = Some(texture);
+                    }
+                    Err(e) => warn!(
+                        "Drawing {} without texture {}: {}",
+                        planets[decoded.key].name,
+                        decoded.name,
+                        debug::failure_to_string(e)
+                    ),
+                }
+            }
+
+            let vp_matrix = camera.get_vp_matrix();
+            let light_dir = na::Vector3::new(0.5, -0.3, 1.0).normalize();
+            unsafe {
+                gl.Enable(gl::CULL_FACE);
+                gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                gl.Enable(gl::DEPTH_TEST);
+            }
+
+            color_buffer.clear(&gl);
+            background.render(
+                &gl,
+                &camera.get_view_rotation_matrix(),
+                &camera.projection_matrix(),
+            );
+            match stars {
+                Some(ref stars) => stars.render(
+                    &gl,
+                    &camera.get_view_rotation_matrix(),
+                    &camera.projection_matrix(),
+                ),
+                None => {
+                    if let Some(ref skybox) = skybox {
+                        skybox.render(
+                            &gl,
+                            &camera.get_view_rotation_matrix(),
+                            &camera.projection_matrix(),
+                        );
+                    }
+                }
+            }
+
+            if settings.wireframe {
+                unsafe {
+                    gl.PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                }
+            }
+
+            let frustum_planes = camera.frustum_planes();
+
+            if is_visible(
+                &frustum_planes,
+                cube.bounding_sphere(),
+                &na::Vector3::zeros(),
+                1.0,
+            ) {
+                cube.render(
+                    &gl,
+                    &vp_matrix,
+                    &na::Matrix4::identity(),
+                    &camera.project_pos().coords,
+                    &light_dir,
+                );
+            }
+
+            let sim_days = sim_clock.days_since_j2000();
+            let scene = scene_transform(&settings, earth_orbit, sim_days);
+            let (paths_scene, paths_offset) = orbit_paths_placement(&scene);
+            if paths_scene != orbit_paths_scene {
+                // release old lines before adding new ones
+                drop(orbit_paths);
+                orbit_paths = orbit_paths_for(&debug_lines, paths_scene);
+                orbit_paths_scene = paths_scene;
+            }
+            for path in &orbit_paths {
+                path.set_translation(paths_offset);
+            }
+            for (path, orbit) in moon_orbit_paths.iter().zip(moon_orbits.iter()) {
+                let parent = orbit.parent.as_ref().map(|parent| parent.as_ref());
+                path.set_translation(body_position(parent, sim_days, &scene).coords);
+            }
+            comet_tails.clear();
+            comet_tails = debug_lines.in_group("comets", |debug_lines| {
+                planets
+                    .iter()
+                    .zip(planet_orbits.iter())
+                    .filter_map(|(planet, orbit)| Some((planet.comet.as_ref()?, orbit.as_ref()?)))
+                    .filter_map(|(comet, orbit)| {
+                        comet_tail(debug_lines, comet, orbit, &scene, sim_days)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            planet_axes.clear();
+            if debug_lines.is_group_visible("axes") {
+                planet_axes = debug_lines.in_group("axes", |debug_lines| {
+                    planets
+                        .iter()
+                        .zip(planet_orbits.iter())
+                        .map(|(planet, orbit)| {
+                            let (south, north) = planet.axis_endpoints(
+                                &body_position(orbit.as_ref(), sim_days, &scene),
+                                planet.rendered_radius(settings.planet_scale) * 1.5,
+                                sim_days,
+                            );
+                            debug_lines
+                                .start_polyline(south.coords, na::Vector4::new(0.3, 0.5, 1.0, 1.0))
+                                .with_point(north.coords, na::Vector4::new(1.0, 0.3, 0.3, 1.0))
+                                .finish()
+                        })
+                        .collect()
+                });
+            }
+            for (trail, orbit) in trails.iter_mut().zip(planet_orbits.iter()) {
+                if let Some(trail) = trail {
+                    trail.update(sim_days, body_position(orbit.as_ref(), sim_days, &scene));
+                }
+            }
+
+            let sun_position = body_position(None, sim_days, &scene);
+            for ((planet, orbit), texture) in planets
+                .iter()
+                .zip(planet_orbits.iter())
+                .zip(planet_textures.iter())
+            {
+                let position = body_position(orbit.as_ref(), sim_days, &scene).coords;
+                if !is_visible(
+                    &frustum_planes,
+                    sphere.bounding_sphere(),
+                    &position,
+                    planet.rendered_radius(settings.planet_scale),
+                ) {
+                    continue;
+                }
+
+                let model = planet.model_matrix(
+                    &position,
+                    planet.rendered_radius(settings.planet_scale),
+                    sim_days,
+                );
+                let light_dir = (sun_position.coords - position)
+                    .try_normalize(0.0001)
+                    .unwrap_or(light_dir);
+
+                sphere.render(
+                    &gl,
+                    &camera,
+                    viewport.size().1 as f32,
+                    &model,
+                    &light_dir,
+                    &sphere::Surface {
+                        color: planet.color(),
+                        texture: texture.as_ref(),
+                        emissive: planet.emissive,
+                    },
+                );
+            }
+
+            asteroids.update(sim_days, &scene, settings.planet_scale);
+            asteroids.render(
+                &gl,
+                &vp_matrix,
+                &camera.project_pos().coords,
+                &sun_position.coords,
+            );
+
+            // transparent rings go last, from the farthest to the nearest
+            let camera_pos = camera.project_pos();
+            let mut ringed = planets
+                .iter()
+                .zip(planet_orbits.iter())
+                .zip(planet_rings.iter())
+                .filter_map(|((planet, orbit), rings)| {
+                    rings.as_ref().map(|rings| {
+                        let position = body_position(orbit.as_ref(), sim_days, &scene);
+                        (
+                            rings,
+                            planet,
+                            planet.rendered_radius(settings.planet_scale),
+                            position,
+                            na::distance(&position, &camera_pos),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            ringed.sort_by(|a, b| b.4.partial_cmp(&a.4).unwrap_or(std::cmp::Ordering::Equal));
+            for (rings, planet, radius, position, _) in ringed {
+                if !is_visible(
+                    &frustum_planes,
+                    rings.bounding_sphere(),
+                    &position.coords,
+                    radius,
+                ) {
+                    continue;
+                }
+
+                // rings lie in the equatorial plane
+                let model = planet.model_matrix(&position.coords, radius, sim_days);
+                rings.render(&gl, &vp_matrix, &model);
+            }
+
+            for (planet, orbit) in planets.iter().zip(planet_orbits.iter()) {
+                if planet.emissive {
+                    let position = body_position(orbit.as_ref(), sim_days, &scene);
+                    halo.render(
+                        &gl,
+                        &camera,
+                        &position,
+                        planet.rendered_radius(settings.planet_scale),
+                        &planet.color(),
+                    );
+                }
+            }
+
+            // debug lines and the rest of the frame are always filled
+            if settings.wireframe {
+                unsafe {
+                    gl.PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+                }
+            }
+
+            // astrological retrograde is relative to Earth, so mark it as "Rx" after the name
+            let planet_names = planets
+                .iter()
+                .zip(planet_orbits.iter())
+                .map(|(planet, orbit)| match (orbit, earth_orbit) {
+                    (Some(orbit), Some(earth_orbit))
+                        if !std::ptr::eq(orbit, earth_orbit)
+                            && retrograde::is_retrograde_at(orbit, earth_orbit, sim_days) =>
+                    {
+                        format!("{} Rx", planet.name)
+                    }
+                    _ => planet.name.clone(),
+                })
+                .collect::<Vec<_>>();
+            let planet_labels = planets
+                .iter()
+                .zip(planet_orbits.iter())
+                .zip(planet_names.iter())
+                .map(|((planet, orbit), name)| labels::Label {
+                    position: body_position(orbit.as_ref(), sim_days, &scene)
+                        + camera.up() * planet.rendered_radius(settings.planet_scale) * 1.2,
+                    text: name,
+                })
+                .collect::<Vec<_>>();
+            labels.render(&gl, &planet_labels, &camera);
+
+            // transits as seen from Earth, so Earth itself is left out
+            if settings.show_aspect_grid {
+                if let Some(earth_orbit) = earth_orbit {
+                    let earth = earth_orbit.position_at(sim_days);
+                    let (names, longitudes): (Vec<&str>, Vec<f64>) = planets
+                        .iter()
+                        .zip(planet_orbits.iter())
+                        .filter(|(planet, _)| planet.comet.is_none())
+                        .filter(|(_, orbit)| match orbit {
+                            Some(orbit) => !std::ptr::eq(orbit, earth_orbit),
+                            None => true,
+                        })
+                        .map(|(planet, orbit)| {
+                            let position =
+                                orbit.as_ref().map_or_else(na::Point3::origin, |orbit| {
+                                    orbit.position_at(sim_days)
+                                });
+                            (
+                                planet.name.as_str(),
+                                retrograde::geocentric_longitude(&position, &earth),
+                            )
+                        })
+                        .unzip();
+                    let grid = aspects::aspect_grid(&longitudes, aspects::DEFAULT_ORB);
+                    let lines = aspects::grid_lines(&names, &grid);
+                    hud.draw_panel(&labels, na::Point2::new(hud::MARGIN, hud::MARGIN), &lines);
+                }
+            }
+
+            let field_of_view = match camera.angular_field() {
+                Some((horizontal, vertical)) => {
+                    format!("FoV {:.1} x {:.1} deg", horizontal, vertical)
+                }
+                None => "FoV orthographic".to_string(),
+            };
+            hud.draw_text(
+                na::Point2::new(
+                    hud::MARGIN,
+                    viewport.size().1 as f32 - hud::MARGIN - hud::GLYPH_HEIGHT,
+                ),
+                &field_of_view,
+            );
+            if let Some(planet) = planets.get(focus_index) {
+                hud.draw_text(
+                    na::Point2::new(
+                        hud::MARGIN,
+                        viewport.size().1 as f32 - hud::MARGIN - 2.0 * hud::GLYPH_HEIGHT,
+                    ),
+                    &format!("Focus: {}", planet.name),
+                );
+            }
+
+            if settings.show_reticle {
+                let scale = render_gl::scale_factor(window.size(), window.drawable_size());
+                hud.draw_reticle(viewport.size(), scale);
+            }
+
+            // the cursor is hidden while the mouse is grabbed
+            if let (Some((x, y)), false) = (hover_cursor, mouse_grab.is_grabbed()) {
+                let scale = render_gl::scale_factor(window.size(), window.drawable_size());
+                let cursor = na::Vector2::new(x as f32, y as f32) * scale;
+                let bodies = body_spheres(
+                    &planets,
+                    &planet_orbits,
+                    sim_days,
+                    &scene,
+                    settings.planet_scale,
+                );
+                let hovered = camera::picking::body_at_screen_point(
+                    &camera,
+                    cursor,
+                    viewport.size(),
+                    &bodies,
+                );
+                if let Some(i) = hovered {
+                    let lines = body_tooltip(&planets[i], planet_orbits[i].as_ref(), sim_days);
+                    let position = hud::tooltip_position(
+                        cursor.into(),
+                        hud::panel_size(&labels, &lines),
+                        viewport.size(),
+                    );
+                    hud.draw_panel(&labels, position, &lines);
+                }
+            }
+
+            debug_lines.render(&gl, &color_buffer, &vp_matrix);
+            hud.render(&gl, &labels, viewport.size());
+
+            // read back buffer before it is swapped out
+            if take_screenshot {
+                take_screenshot = false;
+                match screenshot::capture(&gl, &viewport, Path::new("screenshots")) {
+                    Ok(path) => info!("Saved screenshot {}", path.display()),
+                    Err(e) => error!("{}", debug::failure_to_string(e.into())),
+                }
+            }
+
+            debug::check_gl_error(&gl, "frame");
+            window.gl_swap_window();
+
+            if let (false, Some(max_fps)) = (settings.vsync, settings.max_fps) {
+                std::thread::sleep(frame_limit::sleep_duration(max_fps, time.elapsed()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// GL resources and bodies of the scene, which do not depend on the window.
+pub struct Scene {
+    pub debug_lines: render_gl::DebugLines,
+    pub cube: cube::Cube,
+    pub background: background::Background,
+    pub skybox: Option<skybox::Skybox>,
+    pub stars: Option<stars::Stars>,
+    pub sphere: sphere::Sphere,
+    pub halo: halo::Halo,
+    pub asteroids: asteroids::Asteroids,
+    pub hud: hud::Hud,
+    pub labels: labels::Labels,
+    pub key_bindings: keybindings::KeyBindings,
+    pub planets: Vec<planets::PlanetDef>,
+    pub planet_orbits: Vec<Option<orbit::KeplerOrbit>>,
+    pub planet_rings: Vec<Option<rings::Rings>>,
+}
+
+impl Scene {
+    /// Load shaders, meshes and bodies for `options` using the current GL context.
+    ///
+    /// Nothing here needs a window, so the scene can be set up against any loaded `gl`.
+    pub fn load(
+        options: &cli::Options,
+        res: &Resources,
+        gl: &gl::Gl,
+    ) -> Result<Scene, failure::Error> {
+        let debug_lines = render_gl::DebugLines::new(gl, res)?;
+        let cube = cube::Cube::new(res, gl, &debug_lines)?;
+        let mut background = background::Background::new(res, gl)?;
+        background.pole_color = render_gl::srgb::color_to_linear(&options.background_pole.into());
+        background.ecliptic_color =
+            render_gl::srgb::color_to_linear(&options.background_ecliptic.into());
+        let skybox = skybox::Skybox::new(res, gl, "skybox/")?;
+        // the skybox texture is only drawn without a star catalog
+        let stars = match options.star_catalog {
+            Some(ref name) => match stars::load(res, name) {
+                Ok(catalog) => Some(stars::Stars::new(res, gl, &catalog)?),
+                Err(e) => {
+                    error!("{}", debug::failure_to_string(e.into()));
+                    None
+                }
+            },
+            None => None,
+        };
+        let sphere = sphere::Sphere::new(res, gl, 32, 16)?;
+        let halo = halo::Halo::new(res, gl)?;
+        let asteroids =
+            asteroids::Asteroids::new(res, gl, asteroids::belt_orbits(options.asteroid_count))?;
+        let hud = hud::Hud::new(res, gl)?;
+        let labels = labels::Labels::new(
+            res,
+            gl,
+            "fonts/mono.png",
+            labels::GlyphAtlas {
+                columns: 16,
+                rows: 6,
+                glyph_aspect: 2.0 / 3.0,
+            },
+        )?;
+
+        let key_bindings = keybindings::load(res, "keybindings.ron").unwrap_or_else(|e| {
+            warn!(
+                "Using default key bindings: {}",
+                debug::failure_to_string(e.into())
+            );
+            keybindings::KeyBindings::default()
+        });
+
+        let planets = planets::load(res, "planets.ron")?;
+        info!("Loaded {} bodies", planets.len());
+        let planet_orbits = planets::kepler_orbits(&planets);
+        let planet_rings = planets
+            .iter()
+            .map(|planet| {
+                planet
+                    .rings
+                    .as_ref()
+                    .map(|rings| rings::Rings::new(res, gl, rings.inner_radius, rings.outer_radius))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Scene {
+            debug_lines,
+            cube,
+            background,
+            skybox,
+            stars,
+            sphere,
+            halo,
+            asteroids,
+            hud,
+            labels,
+            key_bindings,
+            planets,
+            planet_orbits,
+            planet_rings,
+        })
+    }
+}
+
+fn toggle_group(debug_lines: &mut render_gl::DebugLines, name: &str) {
+    let visible = debug_lines.is_group_visible(name);
+    debug_lines.set_group_visible(name, !visible);
+}
+
+/// New hover cursor position in window points if the mouse moved, none when it left the window.
+fn cursor_change(event: &sdl2::event::Event) -> Option<Option<(i32, i32)>> {
+    use sdl2::event::{Event, WindowEvent};
+
+    match *event {
+        Event::MouseMotion { x, y, .. } => Some(Some((x, y))),
+        Event::Window {
+            win_event: WindowEvent::Leave,
+            ..
+        } => Some(None),
+        _ => None,
+    }
+}
+
+/// Bookmark slot of a number key, counted from 0 for key 1.
+fn bookmark_slot(scancode: sdl2::keyboard::Scancode) -> Option<usize> {
+    use sdl2::keyboard::Scancode;
+
+    let slot = match scancode {
+        Scancode::Num1 => 0,
+        Scancode::Num2 => 1,
+        Scancode::Num3 => 2,
+        Scancode::Num4 => 3,
+        Scancode::Num5 => 4,
+        Scancode::Num6 => 5,
+        Scancode::Num7 => 6,
+        Scancode::Num8 => 7,
+        Scancode::Num9 => 8,
+        _ => return None,
+    };
+
+    Some(slot).filter(|&slot| slot < camera::bookmarks::SLOTS)
+}
+
+/// New focus state if event is window gaining or losing keyboard focus.
+fn window_focus_change(event: &sdl2::event::Event) -> Option<bool> {
+    use sdl2::event::{Event, WindowEvent};
+
+    match *event {
+        Event::Window {
+            win_event: WindowEvent::FocusGained,
+            ..
+        } => Some(true),
+        Event::Window {
+            win_event: WindowEvent::FocusLost,
+            ..
+        } => Some(false),
+        _ => None,
+    }
+}
+
+/// Test model-space bounding sphere, placed at `position` and uniformly scaled, against frustum.
+fn is_visible(
+    planes: &[na::Vector4<f32>; 6],
+    bounding_sphere: (na::Point3<f32>, f32),
+    position: &na::Vector3<f32>,
+    scale: f32,
+) -> bool {
+    let (center, radius) = bounding_sphere;
+
+    camera::frustum::sphere_in_frustum(
+        planes,
+        &na::Point3::from(position + center.coords * scale),
+        radius * scale,
+    )
+}
+
+/// Position of a body at simulation time, bodies without orbit stay at origin.
+fn body_position(
+    orbit: Option<&orbit::KeplerOrbit>,
+    sim_days: f64,
+    scene: &orbit::SceneTransform,
+) -> na::Point3<f32> {
+    let position = orbit
+        .map(|orbit| scene.place(orbit, sim_days))
+        .unwrap_or_else(|| scene.apply(&na::Point3::origin()));
+
+    na::convert(position)
+}
+
+/// Tooltip lines for a body: name, distance from the Sun and heliocentric ecliptic longitude.
+fn body_tooltip(
+    planet: &planets::PlanetDef,
+    orbit: Option<&orbit::KeplerOrbit>,
+    sim_days: f64,
+) -> Vec<String> {
+    let mut lines = vec![planet.name.clone()];
+
+    // the Sun has no distance from itself, nor a longitude
+    if let Some(orbit) = orbit {
+        let position = orbit.position_at(sim_days);
+        lines.push(format!(
+            "{:.3} AU from Sun",
+            position.coords.norm() / planets::SCENE_UNITS_PER_AU
+        ));
+        lines.push(format!(
+            "Longitude {:.1} deg",
+            retrograde::ecliptic_longitude(&position.coords)
+        ));
+    }
+
+    lines
+}
+
+/// Pickable bounding spheres of all bodies, with ids indexing `planets`.
+fn body_spheres(
+    planets: &[planets::PlanetDef],
+    planet_orbits: &[Option<orbit::KeplerOrbit>],
+    sim_days: f64,
+    scene: &orbit::SceneTransform,
+    planet_scale_factor: f32,
+) -> Vec<camera::picking::BodySphere> {
+    planets
+        .iter()
+        .zip(planet_orbits.iter())
+        .enumerate()
+        .map(|(id, (planet, orbit))| camera::picking::BodySphere {
+            id,
+            center: body_position(orbit.as_ref(), sim_days, scene),
+            radius: planet.rendered_radius(planet_scale_factor),
+        })
+        .collect()
+}
+
+/// Scene transform for the reference frame and distance scale in `settings`, with the origin
+/// at the Sun or at Earth.
+fn scene_transform(
+    settings: &settings::Settings,
+    earth_orbit: Option<&orbit::KeplerOrbit>,
+    sim_days: f64,
+) -> orbit::SceneTransform {
+    let origin = match (settings.frame, earth_orbit) {
+        (orbit::ReferenceFrame::Geocentric, Some(earth_orbit)) => earth_orbit.position_at(sim_days),
+        _ => na::Point3::origin(),
+    };
+
+    orbit::SceneTransform {
+        origin,
+        distance_scale: settings.distance_scale,
+    }
+}
+
+/// Scene transform to build orbit paths with, and the translation moving them into `scene`.
+///
+/// Linear paths are built around the Sun once and moved with the origin. Compressed distances
+/// do not survive a translation, so logarithmic paths are built around the origin itself.
+fn orbit_paths_placement(
+    scene: &orbit::SceneTransform,
+) -> (orbit::SceneTransform, na::Vector3<f32>) {
+    match scene.distance_scale {
+        orbit::DistanceScale::Linear => (
+            orbit::SceneTransform {
+                origin: na::Point3::origin(),
+                ..*scene
+            },
+            na::convert(-scene.origin.coords),
+        ),
+        orbit::DistanceScale::Logarithmic => (*scene, na::zero()),
+    }
+}
+
+/// Closed polyline following the orbit of a body around the Sun over one period, placed
+/// by `scene`.
+fn orbit_path(
+    debug_lines: &render_gl::DebugLines,
+    orbit: &orbit::KeplerOrbit,
+    segments: usize,
+    scene: &orbit::SceneTransform,
+) -> render_gl::Polyline {
+    closed_orbit_path(
+        debug_lines,
+        orbit
+            .sample_path(segments.max(3), 0.0)
+            .into_iter()
+            .map(|point| na::convert(scene.apply(&point).coords)),
+    )
+}
+
+/// Closed polyline following the orbit of a moon over one period, relative to its parent;
+/// translate it to the parent's scene position to place it.
+fn local_orbit_path(
+    debug_lines: &render_gl::DebugLines,
+    orbit: &orbit::KeplerOrbit,
+    segments: usize,
+) -> render_gl::Polyline {
+    closed_orbit_path(
+        debug_lines,
+        orbit
+            .sample_local_path(segments.max(3))
+            .into_iter()
+            .map(na::convert),
+    )
+}
+
+fn closed_orbit_path<I: Iterator<Item = na::Vector3<f32>>>(
+    debug_lines: &render_gl::DebugLines,
+    mut points: I,
+) -> render_gl::Polyline {
+    let color = na::Vector4::new(0.5, 0.5, 0.6, 0.5);
+    let first = points.next().unwrap_or_else(na::zero);
+
+    points
+        .fold(
+            debug_lines.start_polyline(first, color),
+            |builder, point| builder.with_point(point, color),
+        )
+        .close_and_finish()
+}
+
+/// Tail of a comet pointing away from the Sun, fading towards its end; none far from the Sun.
+fn comet_tail(
+    debug_lines: &render_gl::DebugLines,
+    comet: &planets::CometDef,
+    orbit: &orbit::KeplerOrbit,
+    scene: &orbit::SceneTransform,
+    sim_days: f64,
+) -> Option<render_gl::Polyline> {
+    let position = orbit.position_at(sim_days);
+    let sun = na::Point3::origin();
+    let distance_au = position.coords.norm() / planets::SCENE_UNITS_PER_AU;
+    let length = comet::tail_length(comet.tail_length, distance_au) * planets::SCENE_UNITS_PER_AU;
+    let points = comet::tail_points(&position, &sun, length, 16);
+    let color = |i: usize| {
+        let fade = 1.0 - i as f32 / (points.len() - 1) as f32;
+        na::Vector4::new(0.8, 0.9, 1.0, 0.8 * fade)
+    };
+    let scene_point =
+        |point: &na::Point3<f64>| -> na::Vector3<f32> { na::convert(scene.apply(point).coords) };
+
+    let (first, rest) = points.split_first()?;
+    Some(
+        rest.iter()
+            .enumerate()
+            .fold(
+                debug_lines.start_polyline(scene_point(first), color(0)),
+                |builder, (i, point)| builder.with_point(scene_point(point), color(i + 1)),
+            )
+            .finish(),
+    )
+}
+
+/// Movement flag that stays set while the key for `action` is held down.
+fn held_flag(
+    movement: &mut camera::WasdMovement,
+    action: keybindings::Action,
+) -> Option<&mut bool> {
+    use crate::keybindings::Action;
+
+    match action {
+        Action::Faster => Some(&mut movement.faster),
+        Action::Left => Some(&mut movement.left),
+        Action::Forward => Some(&mut movement.forward),
+        Action::Backward => Some(&mut movement.backward),
+        Action::Right => Some(&mut movement.right),
+        Action::Up => Some(&mut movement.up),
+        Action::Down => Some(&mut movement.down),
+        Action::RotateLeft => Some(&mut movement.rotate_left),
+        Action::RotateRight => Some(&mut movement.rotate_right),
+        Action::RotateUp => Some(&mut movement.rotate_up),
+        Action::RotateDown => Some(&mut movement.rotate_down),
+        Action::RollLeft => Some(&mut movement.roll_left),
+        Action::RollRight => Some(&mut movement.roll_right),
+        _ => None,
+    }
+}
+
+/// Turn relative mouse mode on while the rotate button is held, and off when it is released
+/// or the window loses focus.
+fn handle_mouse_grab(
+    mouse_grab: &mut camera::MouseGrab,
+    mouse: &sdl2::mouse::MouseUtil,
+    window: &sdl2::video::Window,
+    e: &sdl2::event::Event,
+) {
+    use sdl2::event::{Event, WindowEvent};
+    use sdl2::mouse::MouseButton;
+
+    match *e {
+        Event::MouseButtonDown {
+            mouse_btn: MouseButton::Right,
+            x,
+            y,
+            ..
+        } if mouse_grab.grab(x, y) => mouse.set_relative_mouse_mode(true),
+        Event::MouseButtonUp {
+            mouse_btn: MouseButton::Right,
+            ..
+        }
+        | Event::Window {
+            win_event: WindowEvent::FocusLost,
+            ..
+        } => {
+            if let Some((x, y)) = mouse_grab.release() {
+                mouse.set_relative_mouse_mode(false);
+                // some platforms leave the hidden cursor wherever the drag moved it
+                mouse.warp_mouse_in_window(window, x, y);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn handle_camera_event(
+    camera: &mut camera::TargetCamera,
+    key_bindings: &keybindings::KeyBindings,
+    e: &sdl2::event::Event,
+) {
+    use crate::keybindings::Action;
+    use sdl2::event::Event;
+
+    match *e {
+        Event::MouseWheel { y, .. } => {
+            camera.zoom(y as f32);
+        }
+        Event::KeyDown {
+            scancode: Some(scancode),
+            ..
+        } => {
+            if let Some(action) = key_bindings.action(scancode) {
+                if let Some(flag) = held_flag(&mut camera.movement, action) {
+                    *flag = true;
+                } else {
+                    match action {
+                        Action::Reset => camera.reset(),
+                        Action::InvertY => camera.invert_y = !camera.invert_y,
+                        Action::NorthUp => camera.north_up = !camera.north_up,
+                        Action::Orthographic => camera.toggle_orthographic(),
+                        Action::ToggleMode => camera.toggle_mode(),
+                        _ => (),
+                    }
+                }
+            }
+        }
+        Event::KeyUp {
+            scancode: Some(scancode),
+            ..
+        } => {
+            if let Some(flag) = key_bindings
+                .action(scancode)
+                .and_then(|action| held_flag(&mut camera.movement, action))
+            {
+                *flag = false;
+            }
+        }
+        Event::MouseMotion {
+            xrel,
+            yrel,
+            mousestate,
+            ..
+        } => {
+            if mousestate.right() {
+                camera.rotate(&na::Vector2::new(xrel as f32, -yrel as f32));
+            }
+            if mousestate.middle() {
+                camera.pan(na::Vector2::new(xrel as f32, -yrel as f32));
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn scene_loads_without_a_window() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let options = cli::Options::default();
+
+        let scene = Scene::load(&options, &res, &gl).unwrap();
+        assert!(scene.planets.iter().any(|planet| planet.name == "Earth"));
+        assert_eq!(scene.planet_orbits.len(), scene.planets.len());
+        assert_eq!(scene.planet_rings.len(), scene.planets.len());
+        assert!(scene.planet_rings.iter().any(Option::is_some));
+        assert!(scene.stars.is_some());
+        assert_eq!(
+            scene.background.ecliptic_color,
+            render_gl::srgb::color_to_linear(&background::DEFAULT_ECLIPTIC_COLOR.into())
+        );
+    }
+
+    #[test]
+    fn linear_orbit_paths_are_translated_to_the_origin() {
+        let scene = orbit::SceneTransform {
+            origin: na::Point3::new(10.0, -4.0, 1.0),
+            distance_scale: orbit::DistanceScale::Linear,
+        };
+        let (paths_scene, offset) = orbit_paths_placement(&scene);
+        assert_eq!(paths_scene.origin, na::Point3::origin());
+
+        let heliocentric = na::Point3::new(3.0, 2.0, -1.0);
+        let placed = na::convert::<_, na::Point3<f32>>(paths_scene.apply(&heliocentric)) + offset;
+        let expected = na::convert::<_, na::Point3<f32>>(scene.apply(&heliocentric));
+        assert!((placed - expected).norm() < 1e-5);
+
+        // paths stay the same while only the origin moves
+        let moved = orbit::SceneTransform {
+            origin: na::Point3::new(-2.0, 7.0, 0.0),
+            ..scene
+        };
+        assert_eq!(orbit_paths_placement(&moved).0, paths_scene);
+
+        let logarithmic = orbit::SceneTransform {
+            distance_scale: orbit::DistanceScale::Logarithmic,
+            ..scene
+        };
+        assert_eq!(
+            orbit_paths_placement(&logarithmic),
+            (logarithmic, na::Vector3::zeros())
+        );
+    }
+}
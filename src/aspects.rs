@@ -0,0 +1,179 @@
+/// Orb used for the on-screen aspect grid, in degrees.
+pub const DEFAULT_ORB: f64 = 8.0;
+
+/// Angular relationship between two bodies along the ecliptic.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AspectKind {
+    Conjunction,
+    Sextile,
+    Square,
+    Trine,
+    Opposition,
+}
+
+impl AspectKind {
+    pub const ALL: [AspectKind; 5] = [
+        AspectKind::Conjunction,
+        AspectKind::Sextile,
+        AspectKind::Square,
+        AspectKind::Trine,
+        AspectKind::Opposition,
+    ];
+
+    /// Ideal separation in degrees.
+    pub fn angle(self) -> f64 {
+        match self {
+            AspectKind::Conjunction => 0.0,
+            AspectKind::Sextile => 60.0,
+            AspectKind::Square => 90.0,
+            AspectKind::Trine => 120.0,
+            AspectKind::Opposition => 180.0,
+        }
+    }
+
+    /// Single character shown in the aspect grid, since the label font is ASCII only.
+    pub fn symbol(self) -> char {
+        match self {
+            AspectKind::Conjunction => 'C',
+            AspectKind::Sextile => 'X',
+            AspectKind::Square => 'Q',
+            AspectKind::Trine => 'T',
+            AspectKind::Opposition => 'O',
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aspect {
+    pub kind: AspectKind,
+    /// Difference between actual separation and the ideal angle, in degrees.
+    pub deviation: f64,
+}
+
+/// Shortest angular distance between two ecliptic longitudes in degrees, in range [0, 180].
+pub fn separation(lon_a: f64, lon_b: f64) -> f64 {
+    let diff = (lon_a - lon_b).rem_euclid(360.0);
+    if diff > 180.0 {
+        360.0 - diff
+    } else {
+        diff
+    }
+}
+
+/// Find aspect between two ecliptic longitudes (degrees) within `orb` degrees of the ideal angle.
+///
+/// If several aspects are within the orb, the closest one wins.
+pub fn aspect_between(lon_a: f64, lon_b: f64, orb: f64) -> Option<Aspect> {
+    let separation = separation(lon_a, lon_b);
+
+    AspectKind::ALL
+        .iter()
+        .map(|&kind| Aspect {
+            kind,
+            deviation: separation - kind.angle(),
+        })
+        .filter(|aspect| aspect.deviation.abs() <= orb)
+        .min_by(|a, b| {
+            a.deviation
+                .abs()
+                .partial_cmp(&b.deviation.abs())
+                .unwrap_or(::std::cmp::Ordering::Equal)
+        })
+}
+
+/// Aspect for each pair of ecliptic longitudes (degrees), indexed `[row][column]`.
+///
+/// The grid is symmetric and the diagonal is always `None`.
+pub fn aspect_grid(longitudes: &[f64], orb: f64) -> Vec<Vec<Option<AspectKind>>> {
+    longitudes
+        .iter()
+        .enumerate()
+        .map(|(row, &lon_a)| {
+            longitudes
+                .iter()
+                .enumerate()
+                .map(|(column, &lon_b)| {
+                    if row == column {
+                        None
+                    } else {
+                        aspect_between(lon_a, lon_b, orb).map(|aspect| aspect.kind)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Lay out an aspect grid as text lines, with the first two letters of `names` as headers.
+///
+/// Pairs without an aspect show "-", the diagonal is left blank.
+pub fn grid_lines(names: &[&str], grid: &[Vec<Option<AspectKind>>]) -> Vec<String> {
+    let abbreviation = |name: &str| format!("{:<2}", name.chars().take(2).collect::<String>());
+
+    let header = names.iter().fold(String::from("  "), |line, name| {
+        line + " " + &abbreviation(name)
+    });
+
+    let rows = names
+        .iter()
+        .zip(grid.iter())
+        .enumerate()
+        .map(|(row, (name, cells))| {
+            cells
+                .iter()
+                .enumerate()
+                .fold(abbreviation(name), |line, (column, cell)| {
+                    let symbol = match cell {
+                        _ if row == column => ' ',
+                        Some(kind) => kind.symbol(),
+                        None => '-',
+                    };
+                    format!("{} {} ", line, symbol)
+                })
+        });
+
+    std::iter::once(header).chain(rows).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_each_aspect_within_orb() {
+        for &kind in AspectKind::ALL.iter() {
+            let aspect = aspect_between(10.0, 10.0 + kind.angle() + 2.0, 5.0).unwrap();
+            assert_eq!(aspect.kind, kind);
+            assert!((aspect.deviation.abs() - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn near_miss_outside_orb_is_none() {
+        assert_eq!(aspect_between(0.0, 96.0, 5.0), None);
+    }
+
+    #[test]
+    fn separation_wraps_around() {
+        assert!((separation(350.0, 10.0) - 20.0).abs() < 1e-9);
+        let aspect = aspect_between(350.0, 10.0, 25.0).unwrap();
+        assert_eq!(aspect.kind, AspectKind::Conjunction);
+        assert!((aspect.deviation - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grid_lines_align_symbols_under_headers() {
+        let grid = aspect_grid(&[0.0, 90.0, 180.0], 5.0);
+        let lines = grid_lines(&["Sun", "Moon", "Mars"], &grid);
+        assert_eq!(
+            lines,
+            ["   Su Mo Ma", "Su    Q  O ", "Mo Q     Q ", "Ma O  Q    "]
+        );
+
+        let grid = vec![vec![None, None], vec![None, None]];
+        assert_eq!(
+            grid_lines(&["Venus", "J"], &grid),
+            ["   Ve J ", "Ve    - ", "J  -    "]
+        );
+    }
+}
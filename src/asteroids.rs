@@ -0,0 +1,224 @@
+use crate::orbit::{KeplerOrbit, SceneTransform};
+use crate::planets::SCENE_UNITS_PER_AU;
+use crate::render_gl::{self, buffer};
+use crate::resources::Resources;
+use crate::sphere;
+use nalgebra as na;
+use std::f64::consts::PI;
+
+/// Asteroids in the belt when not set on the command line.
+pub const DEFAULT_COUNT: usize = 1000;
+
+/// Range of belt semi-major axes in astronomical units.
+const INNER_AU: f64 = 2.2;
+const OUTER_AU: f64 = 3.3;
+
+/// Largest eccentricity and inclination (in degrees) of belt orbits.
+const MAX_ECCENTRICITY: f64 = 0.2;
+const MAX_INCLINATION: f64 = 10.0;
+
+/// Rendered radius of one asteroid in scene units, before the planet scale factor.
+const RADIUS: f32 = 0.02;
+
+/// Seed of the belt layout, so the belt looks the same on every start.
+const SEED: u64 = 0x5eed_a57e;
+
+/// Sectors and stacks of the shared sphere mesh, small bodies need little detail.
+const MESH_SECTORS: u32 = 8;
+const MESH_STACKS: u32 = 4;
+
+/// Pseudo-random numbers in range [0, 1), the same sequence for the same seed.
+struct Sequence(u64);
+
+impl Sequence {
+    fn next(&mut self) -> f64 {
+        // xorshift64*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+}
+
+/// Orbits of `count` belt asteroids between Mars and Jupiter.
+///
+/// Periods follow Kepler's third law, so inner asteroids overtake outer ones.
+pub fn belt_orbits(count: usize) -> Vec<KeplerOrbit> {
+    let mut sequence = Sequence(SEED);
+
+    (0..count)
+        .map(|_| {
+            let semi_major_au = sequence.range(INNER_AU, OUTER_AU);
+            KeplerOrbit {
+                semi_major: semi_major_au * SCENE_UNITS_PER_AU,
+                eccentricity: sequence.range(0.0, MAX_ECCENTRICITY),
+                inclination: sequence.range(0.0, MAX_INCLINATION).to_radians(),
+                lon_ascending: sequence.range(0.0, 2.0 * PI),
+                arg_periapsis: sequence.range(0.0, 2.0 * PI),
+                mean_anomaly_epoch: sequence.range(0.0, 2.0 * PI),
+                period: 365.256 * semi_major_au.powf(1.5),
+                parent: None,
+                display_scale: 1.0,
+            }
+        })
+        .collect()
+}
+
+/// Asteroid belt drawn as one sphere mesh instanced at every asteroid position.
+pub struct Asteroids {
+    program: render_gl::Program,
+    program_view_projection_location: i32,
+    camera_pos_location: i32,
+    sun_pos_location: i32,
+    light_color_location: i32,
+    surface_color_location: i32,
+    /// Color of the sunlight.
+    pub light_color: na::Vector3<f32>,
+    /// Albedo of every asteroid, in linear space.
+    pub color: na::Vector3<f32>,
+    orbits: Vec<KeplerOrbit>,
+    instances: render_gl::InstanceBuffer,
+    _vbo: buffer::ArrayBuffer,
+    _ebo: buffer::ElementArrayBuffer,
+    index_count: i32,
+    vao: buffer::VertexArray,
+}
+
+impl Asteroids {
+    pub fn new(
+        res: &Resources,
+        gl: &gl::Gl,
+        orbits: Vec<KeplerOrbit>,
+    ) -> Result<Asteroids, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/asteroids")?;
+        let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
+        let camera_pos_location = program.get_uniform_location("CameraPos")?;
+        let sun_pos_location = program.get_uniform_location("SunPos")?;
+        let light_color_location = program.get_uniform_location("LightColor")?;
+        let surface_color_location = program.get_uniform_location("SurfaceColor")?;
+
+        let (vbo_data, ebo_data) = sphere::generate_mesh(MESH_SECTORS, MESH_STACKS);
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let ebo = buffer::ElementArrayBuffer::new(gl);
+        ebo.bind();
+        ebo.static_draw_data::<u32>(&ebo_data);
+        ebo.unbind();
+
+        let instances = render_gl::InstanceBuffer::new(gl);
+
+        // set up vertex array object
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        ebo.bind();
+        sphere::Vertex::vertex_attrib_pointers(gl);
+        instances.attach();
+        vao.unbind();
+
+        ebo.unbind(); // do not unbind ebo until we finish with vao
+
+        Ok(Asteroids {
+            program,
+            program_view_projection_location,
+            camera_pos_location,
+            sun_pos_location,
+            light_color_location,
+            surface_color_location,
+            light_color: na::Vector3::new(1.0, 1.0, 1.0),
+            color: render_gl::srgb::color_to_linear(&na::Vector3::new(0.55, 0.5, 0.45)),
+            orbits,
+            instances,
+            _vbo: vbo,
+            _ebo: ebo,
+            index_count: ebo_data.len() as i32,
+            vao,
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (
+            program_view_projection_location,
+            camera_pos_location,
+            sun_pos_location,
+            light_color_location,
+            surface_color_location,
+        ) = self.program.reload(res, |program| {
+            Ok((
+                program.get_uniform_location("ViewProjection")?,
+                program.get_uniform_location("CameraPos")?,
+                program.get_uniform_location("SunPos")?,
+                program.get_uniform_location("LightColor")?,
+                program.get_uniform_location("SurfaceColor")?,
+            ))
+        })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.camera_pos_location = camera_pos_location;
+        self.sun_pos_location = sun_pos_location;
+        self.light_color_location = light_color_location;
+        self.surface_color_location = surface_color_location;
+        Ok(())
+    }
+
+    /// Move every asteroid along its orbit to simulation time, placed by `scene`.
+    pub fn update(&mut self, sim_days: f64, scene: &SceneTransform, scale_factor: f32) {
+        let radius = RADIUS * scale_factor;
+        let models = self
+            .orbits
+            .iter()
+            .map(|orbit| {
+                let position: na::Point3<f32> =
+                    na::convert(scene.apply(&orbit.position_at(sim_days)));
+                na::Matrix4::new_translation(&position.coords) * na::Matrix4::new_scaling(radius)
+            })
+            .collect::<Vec<_>>();
+
+        self.instances.update(&models);
+    }
+
+    /// Render all asteroids with one draw call, lit by the Sun at `sun_pos`.
+    pub fn render(
+        &self,
+        gl: &gl::Gl,
+        vp_matrix: &na::Matrix4<f32>,
+        camera_pos: &na::Vector3<f32>,
+        sun_pos: &na::Vector3<f32>,
+    ) {
+        if self.instances.count() == 0 {
+            return;
+        }
+
+        self.program.set_used();
+        self.program
+            .set_uniform_matrix4fv(self.program_view_projection_location, vp_matrix);
+        self.program
+            .set_uniform_3f(self.camera_pos_location, camera_pos);
+        self.program.set_uniform_3f(self.sun_pos_location, sun_pos);
+        self.program
+            .set_uniform_3f(self.light_color_location, &self.light_color);
+        self.program
+            .set_uniform_3f(self.surface_color_location, &self.color);
+        self.vao.bind();
+
+        unsafe {
+            gl.DrawElementsInstanced(
+                gl::TRIANGLES,                                // mode
+                self.index_count,                             // index vertex count
+                gl::UNSIGNED_INT,                             // index type
+                ::std::ptr::null(), // pointer to indices (ebo from the vao)
+                self.instances.count() as gl::types::GLsizei, // instance count
+            );
+        }
+    }
+}
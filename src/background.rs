@@ -0,0 +1,189 @@
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+
+/// Default color towards the ecliptic poles, almost black.
+pub const DEFAULT_POLE_COLOR: [f32; 3] = [0.0, 0.0, 0.02];
+
+/// Default color along the ecliptic, a slightly lighter dark blue.
+pub const DEFAULT_ECLIPTIC_COLOR: [f32; 3] = [0.03, 0.04, 0.1];
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32,
+}
+
+/// Two triangles covering the whole screen, in normalized device coordinates.
+pub fn fullscreen_quad() -> [(f32, f32); 6] {
+    [
+        (-1.0, -1.0),
+        (1.0, -1.0),
+        (1.0, 1.0),
+        (-1.0, -1.0),
+        (1.0, 1.0),
+        (-1.0, 1.0),
+    ]
+}
+
+/// Matrix taking normalized device coordinates on the far plane back to view directions.
+///
+/// The `view` matrix should only contain camera rotation, as for the skybox.
+pub fn inverse_view_projection(
+    view: &na::Matrix4<f32>,
+    projection: &na::Matrix4<f32>,
+) -> na::Matrix4<f32> {
+    (projection * view)
+        .try_inverse()
+        .unwrap_or_else(na::Matrix4::identity)
+}
+
+/// Color gradient by ecliptic latitude drawn behind the scene, lighter near the ecliptic.
+pub struct Background {
+    program: render_gl::Program,
+    inverse_view_projection_location: i32,
+    pole_color_location: i32,
+    ecliptic_color_location: i32,
+    pub pole_color: na::Vector3<f32>,
+    pub ecliptic_color: na::Vector3<f32>,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Background {
+    pub fn new(res: &Resources, gl: &gl::Gl) -> Result<Background, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/background")?;
+        let inverse_view_projection_location =
+            program.get_uniform_location("InverseViewProjection")?;
+        let pole_color_location = program.get_uniform_location("PoleColor")?;
+        let ecliptic_color_location = program.get_uniform_location("EclipticColor")?;
+
+        let vbo_data: Vec<Vertex> = fullscreen_quad()
+            .iter()
+            .map(|&pos| Vertex { pos: pos.into() })
+            .collect();
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Background {
+            program,
+            inverse_view_projection_location,
+            pole_color_location,
+            ecliptic_color_location,
+            pole_color: DEFAULT_POLE_COLOR.into(),
+            ecliptic_color: DEFAULT_ECLIPTIC_COLOR.into(),
+            _vbo: vbo,
+            vao,
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (inverse_view_projection_location, pole_color_location, ecliptic_color_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("InverseViewProjection")?,
+                    program.get_uniform_location("PoleColor")?,
+                    program.get_uniform_location("EclipticColor")?,
+                ))
+            })?;
+        self.inverse_view_projection_location = inverse_view_projection_location;
+        self.pole_color_location = pole_color_location;
+        self.ecliptic_color_location = ecliptic_color_location;
+        Ok(())
+    }
+
+    /// Render gradient; should be done first, because it ignores and does not write depth.
+    ///
+    /// The `view` matrix should only contain camera rotation, so the gradient follows the
+    /// ecliptic as the camera turns.
+    pub fn render(&self, gl: &gl::Gl, view: &na::Matrix4<f32>, projection: &na::Matrix4<f32>) {
+        self.program.set_used();
+        self.program.set_uniform_matrix4fv(
+            self.inverse_view_projection_location,
+            &inverse_view_projection(view, projection),
+        );
+        self.program
+            .set_uniform_3f(self.pole_color_location, &self.pole_color);
+        self.program
+            .set_uniform_3f(self.ecliptic_color_location, &self.ecliptic_color);
+        self.vao.bind();
+
+        unsafe {
+            let depth_test_enabled = gl.IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            let mut depth_mask: gl::types::GLboolean = gl::TRUE;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+            gl.Disable(gl::DEPTH_TEST);
+            gl.DepthMask(gl::FALSE);
+
+            gl.DrawArrays(
+                gl::TRIANGLES, // mode
+                0,             // starting index in the enabled arrays
+                6,             // number of indices to be rendered
+            );
+
+            gl.DepthMask(depth_mask);
+            if depth_test_enabled {
+                gl.Enable(gl::DEPTH_TEST);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn direction(inverse: &na::Matrix4<f32>, x: f32, y: f32) -> na::Vector3<f32> {
+        let far = inverse * na::Vector4::new(x, y, 1.0, 1.0);
+        (far.xyz() / far.w).normalize()
+    }
+
+    #[test]
+    fn fullscreen_quad_covers_normalized_device_coordinates() {
+        let quad = fullscreen_quad();
+        assert!(quad.iter().all(|&(x, y)| x.abs() == 1.0 && y.abs() == 1.0));
+
+        let area: f32 = quad
+            .chunks(3)
+            .map(|t| {
+                ((t[1].0 - t[0].0) * (t[2].1 - t[0].1) - (t[2].0 - t[0].0) * (t[1].1 - t[0].1))
+                    / 2.0
+            })
+            .sum();
+        // both triangles counter-clockwise, together the full -1..1 square
+        assert!((area - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gradient_direction_follows_ecliptic_latitude() {
+        let projection = na::Perspective3::new(1.0, PI / 2.0, 0.1, 100.0).into_inner();
+
+        // looking along the ecliptic towards +Y, with ecliptic north up
+        let level = na::Rotation3::from_axis_angle(&na::Vector3::x_axis(), PI / 2.0)
+            .inverse()
+            .to_homogeneous();
+        let inverse = inverse_view_projection(&level, &projection);
+        let center = direction(&inverse, 0.0, 0.0);
+        assert!((center - na::Vector3::y()).norm() < 1e-4);
+        assert!(direction(&inverse, 0.0, 1.0).z > 0.5);
+        assert!(direction(&inverse, 0.0, -1.0).z < -0.5);
+
+        // looking straight down at the ecliptic pole
+        let inverse = inverse_view_projection(&na::Matrix4::identity(), &projection);
+        assert!((direction(&inverse, 0.0, 0.0).z + 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+
+/// Summary of frame times, in milliseconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameStats {
+    pub frames: usize,
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} frames: min {:.2} ms, avg {:.2} ms, max {:.2} ms, p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms",
+            self.frames, self.min, self.avg, self.max, self.p50, self.p95, self.p99
+        )
+    }
+}
+
+/// Nearest-rank percentile `p` (0 to 100) of ascending `sorted` values.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.max(1) - 1])
+}
+
+/// Statistics of frame times given in seconds, none without frames.
+pub fn frame_stats(frame_times: &[f64]) -> Option<FrameStats> {
+    let mut sorted = frame_times
+        .iter()
+        .map(|seconds| seconds * 1000.0)
+        .collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    Some(FrameStats {
+        frames: sorted.len(),
+        min: *sorted.first()?,
+        avg: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        max: *sorted.last()?,
+        p50: percentile(&sorted, 50.0)?,
+        p95: percentile(&sorted, 95.0)?,
+        p99: percentile(&sorted, 99.0)?,
+    })
+}
+
+/// Frame times collected until a set number of frames is reached.
+pub struct Benchmark {
+    frames: usize,
+    frame_times: Vec<f64>,
+    started: bool,
+}
+
+impl Benchmark {
+    pub fn new(frames: usize) -> Benchmark {
+        Benchmark {
+            frames,
+            frame_times: Vec::with_capacity(frames),
+            started: false,
+        }
+    }
+
+    /// Add the time of one frame in seconds, returns true once all frames are recorded.
+    ///
+    /// The first time is skipped, it measures setup before the first frame was rendered.
+    pub fn record(&mut self, frame_time: f64) -> bool {
+        if !self.started {
+            self.started = true;
+        } else if self.frame_times.len() < self.frames {
+            self.frame_times.push(frame_time);
+        }
+        self.is_done()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame_times.len() >= self.frames
+    }
+
+    /// Statistics of the frames recorded so far.
+    pub fn stats(&self) -> Option<FrameStats> {
+        frame_stats(&self.frame_times)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_use_nearest_rank_percentiles() {
+        // 1 to 100 ms, shuffled
+        let frame_times: Vec<_> = (0..100)
+            .map(|i| ((i * 37) % 100 + 1) as f64 / 1000.0)
+            .collect();
+        let stats = frame_stats(&frame_times).unwrap();
+
+        assert_eq!(stats.frames, 100);
+        assert!((stats.min - 1.0).abs() < 1e-9);
+        assert!((stats.max - 100.0).abs() < 1e-9);
+        assert!((stats.avg - 50.5).abs() < 1e-9);
+        assert!((stats.p50 - 50.0).abs() < 1e-9);
+        assert!((stats.p95 - 95.0).abs() < 1e-9);
+        assert!((stats.p99 - 99.0).abs() < 1e-9);
+        assert_eq!(frame_stats(&[]), None);
+    }
+
+    #[test]
+    fn setup_time_before_the_first_frame_is_skipped() {
+        let mut benchmark = Benchmark::new(2);
+        assert!(!benchmark.record(5.0));
+        assert!(!benchmark.record(0.010));
+        assert!(benchmark.record(0.020));
+        assert!(benchmark.record(0.030));
+
+        let stats = benchmark.stats().unwrap();
+        assert_eq!(stats.frames, 2);
+        assert!((stats.max - 20.0).abs() < 1e-9);
+    }
+}
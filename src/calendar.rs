@@ -0,0 +1,182 @@
+use std::fmt;
+
+/// Julian day of the J2000 epoch, 2000-01-01 12:00 TT.
+pub const J2000: f64 = 2_451_545.0;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Invalid date {:04}-{:02}-{:02}", year, month, day)]
+    InvalidDate { year: i32, month: u32, day: u32 },
+}
+
+/// Calendar date and time of day.
+///
+/// Dates from 1582-10-15 on are Gregorian, earlier ones are Julian calendar dates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CalendarDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl CalendarDate {
+    /// Date at midnight, checked to exist in its calendar.
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Result<CalendarDate, Error> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(Error::InvalidDate { year, month, day });
+        }
+
+        Ok(CalendarDate {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        })
+    }
+
+    /// Convert to Julian day number (Meeus, Astronomical Algorithms, chapter 7).
+    pub fn to_julian_day(&self) -> f64 {
+        let (year, month) = if self.month > 2 {
+            (self.year as f64, self.month as f64)
+        } else {
+            (self.year as f64 - 1.0, self.month as f64 + 12.0)
+        };
+
+        let b = if is_gregorian(self.year, self.month, self.day) {
+            let a = (year / 100.0).floor();
+            2.0 - a + (a / 4.0).floor()
+        } else {
+            0.0
+        };
+
+        let day_fraction =
+            (self.hour as f64 * 3600.0 + self.minute as f64 * 60.0 + self.second as f64) / 86_400.0;
+
+        (365.25 * (year + 4716.0)).floor()
+            + (30.6001 * (month + 1.0)).floor()
+            + self.day as f64
+            + day_fraction
+            + b
+            - 1524.5
+    }
+
+    /// Convert Julian day number to calendar date (Meeus, Astronomical Algorithms, chapter 7).
+    pub fn from_julian_day(jd: f64) -> CalendarDate {
+        // julian day starts at noon, round to whole seconds first so 59.9999 s does not show up
+        let seconds_since_midnight_epoch = ((jd + 0.5) * 86_400.0).round();
+        let z = (seconds_since_midnight_epoch / 86_400.0).floor();
+        let seconds_of_day = (seconds_since_midnight_epoch - z * 86_400.0) as u32;
+
+        let a = if z < 2_299_161.0 {
+            z
+        } else {
+            let alpha = ((z - 1_867_216.25) / 36_524.25).floor();
+            z + 1.0 + alpha - (alpha / 4.0).floor()
+        };
+        let b = a + 1524.0;
+        let c = ((b - 122.1) / 365.25).floor();
+        let d = (365.25 * c).floor();
+        let e = ((b - d) / 30.6001).floor();
+
+        let day = (b - d - (30.6001 * e).floor()) as u32;
+        let month = (if e < 14.0 { e - 1.0 } else { e - 13.0 }) as u32;
+        let year = (if month > 2 { c - 4716.0 } else { c - 4715.0 }) as i32;
+
+        CalendarDate {
+            year,
+            month,
+            day,
+            hour: seconds_of_day / 3600,
+            minute: seconds_of_day / 60 % 60,
+            second: seconds_of_day % 60,
+        }
+    }
+}
+
+/// Gregorian calendar starts on 1582-10-15.
+fn is_gregorian(year: i32, month: u32, day: u32) -> bool {
+    (year, month, day) >= (1582, 10, 15)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    if is_gregorian(year, 12, 31) {
+        (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+    } else {
+        year % 4 == 0
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn j2000_is_noon_on_first_of_january_2000() {
+        let date = CalendarDate::from_julian_day(J2000);
+        assert_eq!(
+            date,
+            CalendarDate {
+                year: 2000,
+                month: 1,
+                day: 1,
+                hour: 12,
+                minute: 0,
+                second: 0,
+            }
+        );
+        assert_eq!(date.to_string(), "2000-01-01 12:00");
+    }
+
+    #[test]
+    fn dates_round_trip_through_julian_day() {
+        for &(year, month, day) in &[
+            (2000, 2, 29),
+            (1582, 10, 15),
+            (1582, 10, 4),
+            (1957, 10, 4),
+            (-4712, 1, 1),
+            (2099, 12, 31),
+        ] {
+            let date = CalendarDate::from_ymd(year, month, day).unwrap();
+            assert_eq!(CalendarDate::from_julian_day(date.to_julian_day()), date);
+        }
+
+        // Meeus example 7.a
+        let sputnik = CalendarDate::from_ymd(1957, 10, 4).unwrap();
+        assert!((sputnik.to_julian_day() - 2_436_115.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nonexistent_dates_are_rejected() {
+        assert!(CalendarDate::from_ymd(2001, 2, 29).is_err());
+        assert!(CalendarDate::from_ymd(1900, 2, 29).is_err());
+        assert!(CalendarDate::from_ymd(2000, 13, 1).is_err());
+        assert!(CalendarDate::from_ymd(2000, 4, 31).is_err());
+        // leap year in the Julian calendar
+        assert!(CalendarDate::from_ymd(1500, 2, 29).is_ok());
+    }
+}
@@ -0,0 +1,151 @@
+use super::CameraState;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Number of bookmark slots, recalled with keys 1 to 9.
+pub const SLOTS: usize = 9;
+
+/// File name of the bookmarks in the user configuration directory.
+const FILE_NAME: &str = "bookmarks.ron";
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to read bookmarks {}", path)]
+    Read {
+        path: String,
+        #[cause]
+        inner: io::Error,
+    },
+    #[fail(display = "Failed to parse bookmarks {}", path)]
+    Parse {
+        path: String,
+        #[cause]
+        inner: ron::de::Error,
+    },
+    #[fail(display = "Failed to serialize bookmarks")]
+    Serialize {
+        #[cause]
+        inner: ron::ser::Error,
+    },
+    #[fail(display = "Failed to write bookmarks {}", path)]
+    Write {
+        path: String,
+        #[cause]
+        inner: io::Error,
+    },
+}
+
+/// Camera states stored in numbered slots, persisted between runs.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    slots: [Option<CameraState>; SLOTS],
+}
+
+impl Bookmarks {
+    /// State stored in `slot`, counted from 0; none for an empty or out of range slot.
+    pub fn get(&self, slot: usize) -> Option<&CameraState> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    /// Store `state` in `slot`, counted from 0. Returns false if the slot is out of range.
+    pub fn set(&mut self, slot: usize, state: CameraState) -> bool {
+        match self.slots.get_mut(slot) {
+            Some(stored) => {
+                *stored = Some(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Read bookmarks from `path`; a missing file has no bookmarks yet.
+    pub fn load(path: &Path) -> Result<Bookmarks, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Bookmarks::default()),
+            Err(e) => {
+                return Err(Error::Read {
+                    path: path.display().to_string(),
+                    inner: e,
+                })
+            }
+        };
+
+        ron::de::from_str(&text).map_err(|e| Error::Parse {
+            path: path.display().to_string(),
+            inner: e,
+        })
+    }
+
+    /// Write bookmarks to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| Error::Serialize { inner: e })?;
+        let write_error = |e| Error::Write {
+            path: path.display().to_string(),
+            inner: e,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        fs::write(path, text).map_err(write_error)
+    }
+}
+
+/// Bookmarks file in the user configuration directory, none if it can not be determined.
+///
+/// This is `$XDG_CONFIG_HOME/r3d3`, `~/.config/r3d3` or `%APPDATA%\r3d3`.
+pub fn default_path() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let config_dir = non_empty("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| non_empty("APPDATA").map(PathBuf::from))
+        .or_else(|| non_empty("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("r3d3").join(FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_survive_a_round_trip_through_a_file() {
+        let dir = std::env::temp_dir().join(format!("r3d3-bookmarks-{}", std::process::id()));
+        let path = dir.join("config").join(FILE_NAME);
+
+        assert!(Bookmarks::load(&path).unwrap().get(0).is_none());
+
+        let mut bookmarks = Bookmarks::default();
+        let state = CameraState {
+            target: [1.0, 2.0, 3.0],
+            yaw: 0.5,
+            pitch: 0.25,
+            roll: 0.125,
+            distance: 4.0,
+            aspect: 1.5,
+        };
+        assert!(bookmarks.set(2, state));
+        assert!(!bookmarks.set(SLOTS, bookmarks.get(2).unwrap().clone()));
+        bookmarks.save(&path).unwrap();
+
+        let loaded = Bookmarks::load(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        let stored = loaded.get(2).unwrap();
+        assert_eq!(stored.target, [1.0, 2.0, 3.0]);
+        assert_eq!(
+            (
+                stored.yaw,
+                stored.pitch,
+                stored.roll,
+                stored.distance,
+                stored.aspect
+            ),
+            (0.5, 0.25, 0.125, 4.0, 1.5)
+        );
+        assert!(loaded.get(0).is_none());
+    }
+}
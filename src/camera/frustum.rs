@@ -0,0 +1,65 @@
+use nalgebra as na;
+
+/// Extract normalized clip planes (left, right, bottom, top, near, far) from a
+/// view-projection matrix, using the Gribb-Hartmann method.
+///
+/// Each plane is `(a, b, c, d)` where a point is inside if `a*x + b*y + c*z + d >= 0`.
+pub fn planes_from_matrix(m: &na::Matrix4<f32>) -> [na::Vector4<f32>; 6] {
+    let row = |i: usize| -> na::Vector4<f32> { m.row(i).transpose() };
+
+    let mut planes = [
+        row(3) + row(0),
+        row(3) - row(0),
+        row(3) + row(1),
+        row(3) - row(1),
+        row(3) + row(2),
+        row(3) - row(2),
+    ];
+
+    for plane in planes.iter_mut() {
+        let len = plane.xyz().norm();
+        if len > 0.0 {
+            *plane /= len;
+        }
+    }
+
+    planes
+}
+
+/// Test if a sphere is at least partially inside all planes.
+pub fn sphere_in_frustum(
+    planes: &[na::Vector4<f32>; 6],
+    center: &na::Point3<f32>,
+    radius: f32,
+) -> bool {
+    planes
+        .iter()
+        .all(|p| p.xyz().dot(&center.coords) + p.w >= -radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::TargetCamera;
+
+    #[test]
+    fn points_behind_camera_are_culled() {
+        let camera = TargetCamera::new(1.0, 1.0, 0.1, 100.0, 0.0, 5.0);
+        let planes = planes_from_matrix(&camera.get_vp_matrix());
+        let eye = camera.project_pos();
+        let ahead = (camera.target - eye).normalize();
+
+        let in_front = eye + ahead * 5.0;
+        assert!(planes
+            .iter()
+            .all(|p| p.xyz().dot(&in_front.coords) + p.w >= 0.0));
+        assert!(sphere_in_frustum(&planes, &in_front, 0.0));
+
+        let behind = eye - ahead * 5.0;
+        assert!(planes[4].xyz().dot(&behind.coords) + planes[4].w < 0.0);
+        assert!(!sphere_in_frustum(&planes, &behind, 0.0));
+
+        let beyond_far = eye + ahead * 200.0;
+        assert!(planes[5].xyz().dot(&beyond_far.coords) + planes[5].w < 0.0);
+    }
+}
@@ -0,0 +1,54 @@
+/// Relative mouse mode while rotating, so drags are not stopped by the screen edge.
+///
+/// Relative mode hides the cursor; once released, it goes back to where the drag started.
+#[derive(Default)]
+pub struct MouseGrab {
+    /// Cursor position in the window when the grab started.
+    grabbed_at: Option<(i32, i32)>,
+}
+
+impl MouseGrab {
+    pub fn is_grabbed(&self) -> bool {
+        self.grabbed_at.is_some()
+    }
+
+    /// Rotate button went down with the cursor at `x`, `y`.
+    ///
+    /// Returns true if relative mode should be turned on.
+    pub fn grab(&mut self, x: i32, y: i32) -> bool {
+        if self.is_grabbed() {
+            return false;
+        }
+
+        self.grabbed_at = Some((x, y));
+        true
+    }
+
+    /// Rotate button went up, or the window lost focus.
+    ///
+    /// Returns the position to put the cursor back to if relative mode should be turned off.
+    pub fn release(&mut self) -> Option<(i32, i32)> {
+        self.grabbed_at.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_warps_back_to_where_the_grab_started() {
+        let mut grab = MouseGrab::default();
+        assert!(!grab.is_grabbed());
+        assert_eq!(grab.release(), None);
+
+        assert!(grab.grab(120, 45));
+        // a second button press while grabbed keeps the first position
+        assert!(!grab.grab(300, 300));
+        assert!(grab.is_grabbed());
+
+        assert_eq!(grab.release(), Some((120, 45)));
+        assert!(!grab.is_grabbed());
+        assert_eq!(grab.release(), None);
+    }
+}
@@ -1,5 +1,17 @@
 mod target_camera;
-pub use self::target_camera::TargetCamera;
+pub use self::target_camera::{
+    angular_field, projected_size, BodyId, CameraMode, Error, TargetCamera,
+};
 
 mod movement;
-pub use self::movement::WasdMovement;
+pub use self::movement::{WasdMovement, DEFAULT_BASE_SPEED as DEFAULT_MOVEMENT_SPEED};
+
+mod grab;
+pub use self::grab::MouseGrab;
+
+mod state;
+pub use self::state::CameraState;
+
+pub mod bookmarks;
+pub mod frustum;
+pub mod picking;
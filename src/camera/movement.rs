@@ -1,5 +1,8 @@
 use nalgebra as na;
 
+/// Movement speed in scene units per second, without the faster modifier.
+pub const DEFAULT_BASE_SPEED: f32 = 25.0;
+
 pub struct WasdMovement {
     pub left: bool,
     pub forward: bool,
@@ -8,6 +11,14 @@ pub struct WasdMovement {
     pub up: bool,
     pub down: bool,
     pub faster: bool,
+    pub rotate_left: bool,
+    pub rotate_right: bool,
+    pub rotate_up: bool,
+    pub rotate_down: bool,
+    pub roll_left: bool,
+    pub roll_right: bool,
+    base_speed: f32,
+    faster_factor: f32,
 }
 
 impl WasdMovement {
@@ -20,6 +31,37 @@ impl WasdMovement {
             up: false,
             down: false,
             faster: false,
+            rotate_left: false,
+            rotate_right: false,
+            rotate_up: false,
+            rotate_down: false,
+            roll_left: false,
+            roll_right: false,
+            base_speed: DEFAULT_BASE_SPEED,
+            faster_factor: 3.0,
+        }
+    }
+
+    /// Movement speed multiplier, before scaling by camera distance.
+    pub fn base_speed(&self) -> f32 {
+        self.base_speed
+    }
+
+    pub fn set_base_speed(&mut self, base_speed: f32) {
+        self.base_speed = base_speed.max(0.0);
+    }
+
+    /// Multiplier applied to base speed while `faster` is held.
+    pub fn set_faster_factor(&mut self, faster_factor: f32) {
+        self.faster_factor = faster_factor.max(0.0);
+    }
+
+    /// Current speed multiplier, taking `faster` into account.
+    pub fn speed(&self) -> f32 {
+        if self.faster {
+            self.base_speed * self.faster_factor
+        } else {
+            self.base_speed
         }
     }
 
@@ -56,4 +98,74 @@ impl WasdMovement {
 
         na::Vector3::new(x, y, z)
     }
+
+    /// Getting rotation vector would result in non-zero rotation.
+    pub fn has_rotation(&self) -> bool {
+        self.rotate_left || self.rotate_right || self.rotate_up || self.rotate_down
+    }
+
+    /// Get rotation intent. The X is yaw to the left, the Y is pitch upwards.
+    pub fn get_rotation_vector(&self) -> na::Vector2<f32> {
+        let mut x = 0.0;
+        if self.rotate_left {
+            x += 1.0;
+        }
+        if self.rotate_right {
+            x -= 1.0;
+        }
+
+        let mut y = 0.0;
+        if self.rotate_up {
+            y += 1.0;
+        }
+        if self.rotate_down {
+            y -= 1.0;
+        }
+
+        na::Vector2::new(x, y)
+    }
+
+    /// Getting roll would result in non-zero roll.
+    pub fn has_roll(&self) -> bool {
+        self.roll_left != self.roll_right
+    }
+
+    /// Get roll intent, positive to the left.
+    pub fn get_roll(&self) -> f32 {
+        match (self.roll_left, self.roll_right) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Release every held movement, rotation and roll key, keeping the speed settings.
+    pub fn release_all(&mut self) {
+        *self = WasdMovement {
+            base_speed: self.base_speed,
+            faster_factor: self.faster_factor,
+            ..WasdMovement::new()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_all_clears_held_keys_but_keeps_speed() {
+        let mut movement = WasdMovement::new();
+        movement.set_base_speed(7.0);
+        movement.forward = true;
+        movement.rotate_left = true;
+        movement.roll_right = true;
+        movement.faster = true;
+
+        movement.release_all();
+        assert!(!movement.has_movement());
+        assert!(!movement.has_rotation());
+        assert!(!movement.has_roll());
+        assert!((movement.speed() - 7.0).abs() < 1e-5);
+    }
 }
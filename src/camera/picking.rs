@@ -0,0 +1,183 @@
+use super::{BodyId, TargetCamera};
+use nalgebra as na;
+
+/// Bounding sphere of a body that can be picked.
+#[derive(Copy, Clone, Debug)]
+pub struct BodySphere {
+    pub id: BodyId,
+    pub center: na::Point3<f32>,
+    pub radius: f32,
+}
+
+/// Distance along normalized ray to the nearest intersection with a sphere.
+///
+/// Returns `None` if the ray misses the sphere or the sphere is behind the ray origin.
+pub fn ray_sphere_intersection(
+    origin: &na::Point3<f32>,
+    direction: &na::Vector3<f32>,
+    center: &na::Point3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let to_center = center - origin;
+    let projection = to_center.dot(direction);
+    let distance_squared = to_center.norm_squared() - projection * projection;
+    let radius_squared = radius * radius;
+
+    if distance_squared > radius_squared {
+        return None;
+    }
+
+    let half_chord = (radius_squared - distance_squared).sqrt();
+    let near = projection - half_chord;
+    let far = projection + half_chord;
+
+    if near >= 0.0 {
+        Some(near)
+    } else if far >= 0.0 {
+        // origin is inside the sphere
+        Some(far)
+    } else {
+        None
+    }
+}
+
+/// Nearest body hit by a normalized ray, with the distance along the ray.
+pub fn nearest_body(
+    origin: &na::Point3<f32>,
+    direction: &na::Vector3<f32>,
+    bodies: &[BodySphere],
+) -> Option<(BodyId, f32)> {
+    bodies
+        .iter()
+        .filter_map(|body| {
+            ray_sphere_intersection(origin, direction, &body.center, body.radius)
+                .map(|distance| (body.id, distance))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Nearest body on the ray through `screen_point`, in pixels from the top left corner of a
+/// `viewport` sized in pixels.
+///
+/// Bodies beyond the far clip plane are not visible, so they are never returned.
+pub fn body_at_screen_point(
+    camera: &TargetCamera,
+    screen_point: na::Vector2<f32>,
+    viewport: (i32, i32),
+    bodies: &[BodySphere],
+) -> Option<BodyId> {
+    let (origin, direction) = camera.screen_ray(screen_point, viewport);
+
+    nearest_body(&origin, &direction, bodies)
+        .filter(|&(_, distance)| distance <= camera.far_clip())
+        .map(|(id, _)| id)
+}
+
+/// Nearest visible body on the ray through the center of the view.
+pub fn body_under_crosshair(camera: &TargetCamera, bodies: &[BodySphere]) -> Option<BodyId> {
+    // any viewport works, only the center point matters
+    body_at_screen_point(camera, na::Vector2::new(0.5, 0.5), (1, 1), bodies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sphere(id: BodyId, x: f32, radius: f32) -> BodySphere {
+        BodySphere {
+            id,
+            center: na::Point3::new(x, 0.0, 0.0),
+            radius,
+        }
+    }
+
+    #[test]
+    fn nearest_of_several_hit_bodies_is_picked() {
+        let origin = na::Point3::origin();
+        let direction = na::Vector3::x();
+        let bodies = [
+            sphere(0, 10.0, 1.0),
+            sphere(1, 5.0, 1.0),
+            sphere(2, -3.0, 1.0),
+        ];
+
+        let (id, distance) = nearest_body(&origin, &direction, &bodies).unwrap();
+        assert_eq!(id, 1);
+        assert!((distance - 4.0).abs() < 1e-5);
+
+        // a body behind the origin is never hit
+        assert_eq!(nearest_body(&origin, &direction, &bodies[2..]), None);
+        // missing every body
+        assert_eq!(nearest_body(&origin, &na::Vector3::y(), &bodies[..2]), None);
+    }
+
+    #[test]
+    fn ray_from_inside_a_sphere_hits_its_far_side() {
+        let distance = ray_sphere_intersection(
+            &na::Point3::origin(),
+            &na::Vector3::x(),
+            &na::Point3::origin(),
+            2.0,
+        );
+        assert_eq!(distance, Some(2.0));
+    }
+
+    #[test]
+    fn crosshair_picks_the_body_at_the_target() {
+        let camera = TargetCamera::new(1.0, PI / 2.0, 0.01, 1000.0, 0.0, 5.0);
+        let at_target = BodySphere {
+            id: 7,
+            center: camera.target,
+            radius: 0.5,
+        };
+        let aside = BodySphere {
+            id: 8,
+            center: camera.target + camera.right() * 3.0,
+            radius: 0.5,
+        };
+
+        assert_eq!(body_under_crosshair(&camera, &[aside, at_target]), Some(7));
+        assert_eq!(body_under_crosshair(&camera, &[aside]), None);
+    }
+
+    #[test]
+    fn hovered_pixel_resolves_the_body_drawn_there() {
+        let camera = TargetCamera::new(1.0, PI / 2.0, 0.01, 1000.0, 0.0, 5.0);
+        let viewport = (600, 600);
+        let bodies = [
+            BodySphere {
+                id: 1,
+                center: camera.target,
+                radius: 0.5,
+            },
+            BodySphere {
+                id: 2,
+                center: camera.target + camera.right() * 2.0,
+                radius: 0.5,
+            },
+        ];
+        // window pixel where the second body is drawn
+        let ndc = camera.get_vp_matrix().transform_point(&bodies[1].center);
+        let pixel = na::Vector2::new(
+            (ndc.x + 1.0) * 0.5 * viewport.0 as f32,
+            (1.0 - ndc.y) * 0.5 * viewport.1 as f32,
+        );
+        assert!(pixel.x > 300.0 && (pixel.y - 300.0).abs() < 1e-3);
+
+        assert_eq!(
+            body_at_screen_point(&camera, pixel, viewport, &bodies),
+            Some(2)
+        );
+        let center = na::Vector2::new(300.0, 300.0);
+        assert_eq!(
+            body_at_screen_point(&camera, center, viewport, &bodies),
+            Some(1)
+        );
+        let corner = na::Vector2::new(10.0, 10.0);
+        assert_eq!(
+            body_at_screen_point(&camera, corner, viewport, &bodies),
+            None
+        );
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Snapshot of everything needed to restore a `TargetCamera` view.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraState {
+    pub target: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Roll around the view direction; missing in states saved before roll existed.
+    #[serde(default)]
+    pub roll: f32,
+    pub distance: f32,
+    pub aspect: f32,
+}
@@ -1,13 +1,106 @@
-use super::WasdMovement;
+use super::{frustum, CameraState, WasdMovement};
 use nalgebra as na;
+use std::f32::consts::PI;
+
+/// Keep pitch this far away from the poles so the view never flips over.
+const PITCH_EPSILON: f32 = 0.01;
+
+/// Yaw and pitch rate while rotating with keys, in radians per second.
+const KEY_ROTATION_SPEED: f32 = PI / 2.0;
+
+/// Closest distance from the surface of the body at the target, and farthest distance from the
+/// target the camera can zoom to.
+const MIN_ZOOM_DISTANCE: f32 = 0.5;
+const MAX_ZOOM_DISTANCE: f32 = 900.0;
+
+/// Rate at which zoom velocity decays, per second.
+const ZOOM_DAMPING: f32 = 8.0;
+
+/// Zoom stops once its velocity drops below this, in distance units per second.
+const ZOOM_STOP_VELOCITY: f32 = 0.001;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(
+        display = "Invalid clip planes near {} far {}, expected 0 < near < far",
+        near, far
+    )]
+    InvalidClipPlanes { near: f32, far: f32 },
+}
+
+/// How the camera reacts to movement and rotation input.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CameraMode {
+    /// Orbit around `target`; movement pans the target along the ground plane.
+    Orbit,
+    /// Move the eye freely; rotation turns the view in place.
+    Fly,
+}
+
+/// Approximate diameter in pixels of a sphere of `radius` at `distance` from the eye, for a
+/// perspective projection with vertical field of view `fovy` (radians) on a viewport
+/// `viewport_height` pixels tall.
+///
+/// A sphere around the eye covers the whole view, which is reported as infinite size.
+pub fn projected_size(distance: f32, radius: f32, fovy: f32, viewport_height: f32) -> f32 {
+    if distance <= radius {
+        return f32::INFINITY;
+    }
+
+    radius * viewport_height / (distance * (fovy / 2.0).tan())
+}
+
+/// Horizontal and vertical angular field in degrees of a perspective projection with vertical
+/// field of view `fovy` (radians) and width to height `aspect`.
+pub fn angular_field(fovy: f32, aspect: f32) -> (f32, f32) {
+    let horizontal = 2.0 * ((fovy / 2.0).tan() * aspect).atan();
+
+    (horizontal.to_degrees(), fovy.to_degrees())
+}
+
+/// Index of a body in the scene's body list.
+pub type BodyId = usize;
+
+struct FocusTransition {
+    from: na::Point3<f32>,
+    to: na::Point3<f32>,
+    elapsed: f32,
+    duration: f32,
+}
 
 pub struct TargetCamera {
     pub target: na::Point3<f32>,
+    mode: CameraMode,
+    /// Eye position used in `Fly` mode.
+    eye: na::Point3<f32>,
     distance: f32,
-    pub rotation: na::UnitQuaternion<f32>,
+    /// Radius of the body at the target, which the camera stays outside of.
+    target_radius: f32,
+    /// Change of `distance` per second, decaying over time.
+    zoom_velocity: f32,
+    /// Rotation around world Z axis, in radians.
+    yaw: f32,
+    /// Elevation above the XY plane, in radians.
+    pitch: f32,
+    /// Rotation around the view direction, in radians, positive to the left.
+    roll: f32,
     pub movement: WasdMovement,
+    /// Flight-sim style mouse look: dragging up tilts the view down.
+    pub invert_y: bool,
+    /// Keep view up direction aligned with ecliptic north (world Z axis), so the view never rolls.
+    pub north_up: bool,
+    rotation_sensitivity: f32,
     projection: na::Perspective3<f32>,
+    /// Half of the visible height when using orthographic projection.
+    orthographic_half_height: Option<f32>,
     invalidated: bool,
+    focus: Option<FocusTransition>,
+    /// Followed body, with its position when last tracked.
+    follow: Option<(BodyId, Option<na::Point3<f32>>)>,
+    default_target: na::Point3<f32>,
+    default_distance: f32,
+    default_yaw: f32,
+    default_pitch: f32,
 }
 
 impl TargetCamera {
@@ -21,21 +114,107 @@ impl TargetCamera {
     ) -> TargetCamera {
         TargetCamera {
             target: na::Point3::origin(),
+            mode: CameraMode::Orbit,
+            eye: na::Point3::origin(),
             distance: initial_distance,
-            rotation: na::UnitQuaternion::from_axis_angle(
-                &na::Vector3::x_axis(),
-                ::std::f32::consts::PI / 4.0,
-            ),
+            target_radius: 0.0,
+            zoom_velocity: 0.0,
+            yaw: 0.0,
+            pitch: PI / 4.0,
+            roll: 0.0,
             movement: WasdMovement::new(),
+            invert_y: false,
+            north_up: false,
+            rotation_sensitivity: 1.0,
             projection: na::Perspective3::new(aspect, fov, znear, zfar),
+            orthographic_half_height: None,
             invalidated: true,
+            focus: None,
+            follow: None,
+            default_target: na::Point3::origin(),
+            default_distance: initial_distance,
+            default_yaw: 0.0,
+            default_pitch: PI / 4.0,
         }
     }
 
+    /// Orientation of the camera around its target, built from yaw, pitch and roll.
+    ///
+    /// Roll is ignored while `north_up` keeps the view level.
+    pub fn rotation(&self) -> na::UnitQuaternion<f32> {
+        let roll = if self.north_up { 0.0 } else { self.roll };
+
+        na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), self.yaw)
+            * na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), PI / 2.0 - self.pitch)
+            * na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), roll)
+    }
+
+    /// Roll around the view direction by `radians`, positive to the left.
+    pub fn roll(&mut self, radians: f32) {
+        self.roll = (self.roll + radians).rem_euclid(2.0 * PI);
+        self.invalidated = true;
+    }
+
+    /// Current roll in radians, in range [0, 2 PI).
+    pub fn roll_angle(&self) -> f32 {
+        self.roll
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// Switch camera mode, keeping the current view unchanged.
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+
+        match mode {
+            CameraMode::Fly => {
+                self.eye = self.project_pos();
+                self.focus = None;
+            }
+            CameraMode::Orbit => {
+                self.target = self.eye + self.forward() * self.distance;
+                self.focus = None;
+            }
+        }
+
+        self.mode = mode;
+        self.invalidated = true;
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.set_mode(match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        });
+    }
+
+    /// Camera right direction in world space.
+    pub fn right(&self) -> na::Vector3<f32> {
+        self.rotation() * na::Vector3::x()
+    }
+
+    /// Camera up direction in world space.
+    pub fn up(&self) -> na::Vector3<f32> {
+        self.rotation() * na::Vector3::y()
+    }
+
+    /// Direction the camera is looking at.
+    fn forward(&self) -> na::Vector3<f32> {
+        self.rotation() * -na::Vector3::z()
+    }
+
     /// Calculate position of camera from a view matrix.
     pub fn project_pos(&self) -> na::Point3<f32> {
+        if self.mode == CameraMode::Fly {
+            return self.eye;
+        }
+
         na::Translation3::<f32>::from(self.target.coords)
-            * self.rotation
+            * self.rotation()
             * na::Translation3::<f32>::from(na::Vector3::z() * self.distance)
             * na::Point3::<f32>::origin()
     }
@@ -44,76 +223,417 @@ impl TargetCamera {
         self.projection.set_aspect(aspect);
     }
 
-    fn get_view_matrix(&self) -> na::Matrix4<f32> {
+    /// Distance of the far clip plane from the eye.
+    pub fn far_clip(&self) -> f32 {
+        self.projection.zfar()
+    }
+
+    /// Horizontal and vertical angular field in degrees, none with orthographic projection.
+    pub fn angular_field(&self) -> Option<(f32, f32)> {
+        match self.orthographic_half_height {
+            Some(_) => None,
+            None => Some(angular_field(
+                self.projection.fovy(),
+                self.projection.aspect(),
+            )),
+        }
+    }
+
+    /// Change vertical field of view (in radians), clamped to a sane range.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.projection.set_fovy(fov.clamp(0.1, 3.0));
+    }
+
+    /// Change near and far clip plane distances.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), Error> {
+        if !(near > 0.0 && near < far && far.is_finite()) {
+            return Err(Error::InvalidClipPlanes { near, far });
+        }
+
+        self.projection.set_znear_and_zfar(near, far);
+        Ok(())
+    }
+
+    /// Move target, eye and any focus transition by `offset`, for example when the scene origin
+    /// changes, so the view stays on the same bodies.
+    pub fn shift(&mut self, offset: &na::Vector3<f32>) {
+        self.target += offset;
+        self.eye += offset;
+        if let Some(ref mut focus) = self.focus {
+            focus.from += offset;
+            focus.to += offset;
+        }
+        self.invalidated = true;
+    }
+
+    /// Start or stop riding along with a body. Stopping leaves the camera where it is.
+    ///
+    /// While following, `track` should be called with the body position before each `update`.
+    pub fn set_follow(&mut self, body: Option<BodyId>) {
+        self.follow = body.map(|body| (body, None));
+    }
+
+    pub fn follow(&self) -> Option<BodyId> {
+        self.follow.map(|(body, _)| body)
+    }
+
+    /// Keep the followed body centered, given its current position.
+    ///
+    /// The first call moves to the body smoothly, later calls move the camera along with it.
+    pub fn track(&mut self, position: na::Point3<f32>) {
+        let last = match self.follow {
+            Some((_, ref mut last)) => last.replace(position),
+            None => return,
+        };
+
+        match last {
+            Some(last) => self.shift(&(position - last)),
+            None => self.focus_on(position, 1.0),
+        }
+    }
+
+    /// Bring `point` to the view center, easing over specified duration in seconds.
+    ///
+    /// In `Orbit` mode the target moves to the point. In `Fly` mode the eye moves so that the
+    /// point is `distance` ahead, keeping the look direction. Calling this during another
+    /// transition starts from the current interpolated position.
+    pub fn focus_on(&mut self, point: na::Point3<f32>, duration_secs: f32) {
+        let (from, to) = match self.mode {
+            CameraMode::Orbit => (self.target, point),
+            CameraMode::Fly => (self.eye, point - self.forward() * self.distance),
+        };
+
+        if duration_secs <= 0.0 {
+            self.focus = None;
+            self.move_focused(to);
+            return;
+        }
+
+        self.focus = Some(FocusTransition {
+            from,
+            to,
+            elapsed: 0.0,
+            duration: duration_secs,
+        });
+    }
+
+    /// Place the point a focus transition moves: the target, or the eye in `Fly` mode.
+    fn move_focused(&mut self, position: na::Point3<f32>) {
+        match self.mode {
+            CameraMode::Orbit => self.target = position,
+            CameraMode::Fly => self.eye = position,
+        }
+        self.invalidated = true;
+    }
+
+    /// Restore target, distance and rotation the camera was constructed with.
+    pub fn reset(&mut self) {
+        self.target = self.default_target;
+        self.distance = self.default_distance;
+        self.zoom_velocity = 0.0;
+        self.yaw = self.default_yaw;
+        self.pitch = self.default_pitch;
+        self.roll = 0.0;
+        self.mode = CameraMode::Orbit;
+        self.focus = None;
+        self.follow = None;
+        self.invalidated = true;
+    }
+
+    /// Capture current target, orientation, distance and aspect.
+    pub fn save_state(&self) -> CameraState {
+        let target = match self.mode {
+            CameraMode::Orbit => self.target,
+            CameraMode::Fly => self.eye + self.forward() * self.distance,
+        };
+
+        CameraState {
+            target: [target.x, target.y, target.z],
+            yaw: self.yaw,
+            pitch: self.pitch,
+            roll: self.roll,
+            distance: self.distance,
+            aspect: self.projection.aspect(),
+        }
+    }
+
+    /// Restore a view previously captured with `save_state`.
+    pub fn load_state(&mut self, state: &CameraState) {
+        self.target = na::Point3::new(state.target[0], state.target[1], state.target[2]);
+        self.yaw = state.yaw;
+        self.pitch = state.pitch;
+        self.roll = state.roll;
+        self.distance = state.distance;
+        self.zoom_velocity = 0.0;
+        self.projection.set_aspect(state.aspect);
+        self.mode = CameraMode::Orbit;
+        self.focus = None;
+        self.invalidated = true;
+    }
+
+    /// Turn to the orientation and distance of `state` and move smoothly to its target over
+    /// `duration_secs`, keeping the current aspect ratio.
+    pub fn travel_to_state(&mut self, state: &CameraState, duration_secs: f32) {
+        if self.mode == CameraMode::Fly {
+            // orbit around the current fly target, so the transition starts where we look
+            self.target = self.eye + self.forward() * self.distance;
+            self.mode = CameraMode::Orbit;
+        }
+        self.yaw = state.yaw;
+        self.pitch = state.pitch;
+        self.roll = state.roll;
+        self.distance = state.distance;
+        self.zoom_velocity = 0.0;
+        self.follow = None;
+        self.invalidated = true;
+        self.focus_on(
+            na::Point3::new(state.target[0], state.target[1], state.target[2]),
+            duration_secs,
+        );
+    }
+
+    /// World to camera space transform.
+    pub fn view_matrix(&self) -> na::Matrix4<f32> {
+        if self.north_up {
+            let eye = self.project_pos();
+            return na::Matrix4::look_at_rh(&eye, &(eye + self.forward()), &na::Vector3::z());
+        }
+
+        if self.mode == CameraMode::Fly {
+            return (na::Translation3::<f32>::from(self.eye.coords) * self.rotation())
+                .inverse()
+                .to_homogeneous();
+        }
+
         (na::Translation3::<f32>::from(self.target.coords)
-            * self.rotation
+            * self.rotation()
             * na::Translation3::<f32>::from(na::Vector3::z() * self.distance))
         .inverse()
         .to_homogeneous()
     }
 
-    pub fn get_p_matrix(&self) -> na::Matrix4<f32> {
-        self.projection.into_inner()
+    /// View matrix without translation, for rendering things at infinity.
+    pub fn get_view_rotation_matrix(&self) -> na::Matrix4<f32> {
+        self.rotation().inverse().to_homogeneous()
+    }
+
+    pub fn projection_matrix(&self) -> na::Matrix4<f32> {
+        match self.orthographic_half_height {
+            Some(half_height) => {
+                let half_width = half_height * self.projection.aspect();
+                na::Orthographic3::new(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.projection.znear(),
+                    self.projection.zfar(),
+                )
+                .into_inner()
+            }
+            None => self.projection.into_inner(),
+        }
+    }
+
+    /// Use orthographic projection showing `half_height` units above and below view center.
+    ///
+    /// Near and far planes are kept from the perspective projection.
+    pub fn set_orthographic(&mut self, half_height: f32) {
+        self.orthographic_half_height = Some(half_height.max(0.001));
+    }
+
+    pub fn set_perspective(&mut self) {
+        self.orthographic_half_height = None;
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        self.orthographic_half_height.is_some()
+    }
+
+    /// Switch between perspective and orthographic projection, keeping the target framed alike.
+    pub fn toggle_orthographic(&mut self) {
+        if self.is_orthographic() {
+            self.set_perspective();
+        } else {
+            self.set_orthographic(self.distance * (self.projection.fovy() / 2.0).tan());
+        }
+    }
+
+    /// Approximate diameter in pixels of a sphere, with the current projection.
+    pub fn projected_size(
+        &self,
+        center: &na::Point3<f32>,
+        radius: f32,
+        viewport_height: f32,
+    ) -> f32 {
+        match self.orthographic_half_height {
+            Some(half_height) => radius * viewport_height / half_height,
+            None => projected_size(
+                na::distance(center, &self.project_pos()),
+                radius,
+                self.projection.fovy(),
+                viewport_height,
+            ),
+        }
     }
 
+    /// Combined `projection_matrix() * view_matrix()`.
     pub fn get_vp_matrix(&self) -> na::Matrix4<f32> {
-        self.projection.into_inner() * self.get_view_matrix()
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    /// Ray from the camera through a point in window pixels (origin at top left).
+    ///
+    /// Returns ray origin on the near plane and normalized direction.
+    pub fn screen_ray(
+        &self,
+        screen: na::Vector2<f32>,
+        viewport: (i32, i32),
+    ) -> (na::Point3<f32>, na::Vector3<f32>) {
+        let ndc_x = 2.0 * screen.x / viewport.0.max(1) as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * screen.y / viewport.1.max(1) as f32;
+
+        let inverse_vp = self
+            .get_vp_matrix()
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity);
+        let near = inverse_vp.transform_point(&na::Point3::new(ndc_x, ndc_y, -1.0));
+        let far = inverse_vp.transform_point(&na::Point3::new(ndc_x, ndc_y, 1.0));
+
+        let direction = (far - near)
+            .try_normalize(0.000_001)
+            .unwrap_or_else(|| self.forward());
+
+        (near, direction)
+    }
+
+    /// Normalized clip planes of the current view, for culling.
+    pub fn frustum_planes(&self) -> [na::Vector4<f32>; 6] {
+        frustum::planes_from_matrix(&self.get_vp_matrix())
     }
 
     /// Zoom scene using specified scroll wheel difference.
+    ///
+    /// The wheel adds to zoom velocity, which `update` applies and decays over time, so that
+    /// one notch still moves by about `speed_from_distance` in total.
     pub fn zoom(&mut self, rel: f32) {
-        self.distance -= rel * self.speed_from_distance();
+        self.zoom_velocity -= rel * self.speed_from_distance() * ZOOM_DAMPING;
         self.invalidated = true;
     }
 
+    fn has_zoom(&self) -> bool {
+        self.zoom_velocity != 0.0
+    }
+
+    /// Keep the camera at least `MIN_ZOOM_DISTANCE` outside a body of `radius` at the target,
+    /// moving it out if it is closer already.
+    pub fn set_target_radius(&mut self, radius: f32) {
+        self.target_radius = radius.max(0.0);
+
+        let min_distance = self.min_distance();
+        if self.distance < min_distance {
+            self.distance = min_distance;
+            self.invalidated = true;
+        }
+    }
+
+    fn min_distance(&self) -> f32 {
+        (self.target_radius + MIN_ZOOM_DISTANCE).min(MAX_ZOOM_DISTANCE)
+    }
+
+    /// Move by zoom velocity, clamped to allowed distance, and decay the velocity.
+    fn apply_zoom(&mut self, delta: f32) {
+        let distance = self.distance + self.zoom_velocity * delta;
+        self.distance = distance.clamp(self.min_distance(), MAX_ZOOM_DISTANCE);
+        self.zoom_velocity *= (-ZOOM_DAMPING * delta).exp();
+
+        if self.distance != distance || self.zoom_velocity.abs() < ZOOM_STOP_VELOCITY {
+            self.zoom_velocity = 0.0;
+        }
+    }
+
+    /// Multiplier for mouse movement in `rotate`.
+    pub fn set_rotation_sensitivity(&mut self, sensitivity: f32) {
+        self.rotation_sensitivity = sensitivity.max(0.0);
+    }
+
     /// Rotate camera using relative mouse movement over screen pixels.
     pub fn rotate(&mut self, rel: &na::Vector2<f32>) {
-        let around_x =
-            na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), rel.y as f32 * 0.005);
-        let around_z =
-            na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), -rel.x as f32 * 0.005);
+        let rel = rel * self.rotation_sensitivity;
+        let rel_y = if self.invert_y { -rel.y } else { rel.y };
 
-        self.rotation = around_z * self.rotation * around_x;
+        self.turn(-rel.x * 0.005, -rel_y * 0.005);
+    }
 
+    /// Add angles to yaw and pitch, keeping pitch away from the poles.
+    fn turn(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw).rem_euclid(2.0 * PI);
+        self.pitch =
+            (self.pitch + pitch).clamp(-PI / 2.0 + PITCH_EPSILON, PI / 2.0 - PITCH_EPSILON);
+
+        self.invalidated = true;
+    }
+
+    /// Pan target across the view plane using relative mouse movement over screen pixels.
+    ///
+    /// Positive `screen_delta` is right and up; the scene follows the mouse.
+    pub fn pan(&mut self, screen_delta: na::Vector2<f32>) {
+        let translation =
+            (self.right() * -screen_delta.x + self.up() * -screen_delta.y) * self.distance * 0.002;
+
+        match self.mode {
+            CameraMode::Orbit => self.target += translation,
+            CameraMode::Fly => self.eye += translation,
+        }
+
+        self.focus = None;
         self.invalidated = true;
     }
 
     /// Update camera position for the movement.
     pub fn update(&mut self, delta: f32) -> bool {
-        if !self.movement.has_movement() && !self.invalidated {
+        if !self.movement.has_movement()
+            && !self.movement.has_rotation()
+            && !self.movement.has_roll()
+            && !self.has_zoom()
+            && !self.invalidated
+            && self.focus.is_none()
+        {
             return false;
         }
 
-        if self.movement.has_movement() {
-            let mut mov3 = self.movement.get_vector();
-
-            let camera_pos = self.project_pos();
-            if camera_pos.z < self.target.z {
-                mov3.y = -mov3.y;
-            }
+        if self.movement.has_rotation() {
+            let rotation = self.movement.get_rotation_vector() * KEY_ROTATION_SPEED * delta;
+            self.turn(rotation.x, rotation.y);
+        }
 
-            let mov3_rotated = self.rotation * na::Vector3::new(mov3.x, mov3.y, 0.0);
+        if self.movement.has_roll() {
+            self.roll(self.movement.get_roll() * KEY_ROTATION_SPEED * delta);
+        }
 
-            let xy = na::Vector2::new(mov3_rotated.x, mov3_rotated.y).try_normalize(0.01);
+        if self.has_zoom() {
+            self.apply_zoom(delta);
+        }
 
-            let combined_movement = na::Vector3::new(
-                xy.map(|v| v.x).unwrap_or(0.0),
-                xy.map(|v| v.y).unwrap_or(0.0),
-                mov3.z,
-            )
-            .try_normalize(0.01);
+        if let Some(ref mut focus) = self.focus {
+            focus.elapsed += delta;
+            let t = (focus.elapsed / focus.duration).min(1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            let position = focus.from + (focus.to - focus.from) * eased;
+            if t >= 1.0 {
+                self.focus = None;
+            }
+            self.move_focused(position);
+        }
 
-            if let Some(combined_movement) = combined_movement {
-                let movement_translation = combined_movement
-                    * (if self.movement.faster { 75.0 } else { 25.0 })
-                    * delta
-                    * self.speed_from_distance();
+        if self.movement.has_movement() {
+            // manual movement takes over from any focus transition or followed body
+            self.focus = None;
+            self.follow = None;
 
-                self.target += na::Vector3::new(
-                    movement_translation.x,
-                    movement_translation.y,
-                    movement_translation.z,
-                );
+            match self.mode {
+                CameraMode::Orbit => self.move_target(delta),
+                CameraMode::Fly => self.move_eye(delta),
             }
         }
 
@@ -122,6 +642,50 @@ impl TargetCamera {
         true
     }
 
+    /// Move orbit target along the ground plane.
+    fn move_target(&mut self, delta: f32) {
+        let mut mov3 = self.movement.get_vector();
+
+        let camera_pos = self.project_pos();
+        if camera_pos.z < self.target.z {
+            mov3.y = -mov3.y;
+        }
+
+        let mov3_rotated = self.rotation() * na::Vector3::new(mov3.x, mov3.y, 0.0);
+
+        let xy = na::Vector2::new(mov3_rotated.x, mov3_rotated.y).try_normalize(0.01);
+
+        let combined_movement = na::Vector3::new(
+            xy.map(|v| v.x).unwrap_or(0.0),
+            xy.map(|v| v.y).unwrap_or(0.0),
+            mov3.z,
+        )
+        .try_normalize(0.01);
+
+        if let Some(combined_movement) = combined_movement {
+            let movement_translation =
+                combined_movement * self.movement.speed() * delta * self.speed_from_distance();
+
+            self.target += na::Vector3::new(
+                movement_translation.x,
+                movement_translation.y,
+                movement_translation.z,
+            );
+        }
+    }
+
+    /// Move eye along the view direction, with up and down along world Z axis.
+    fn move_eye(&mut self, delta: f32) {
+        let mov3 = self.movement.get_vector();
+
+        let direction =
+            self.rotation() * na::Vector3::new(mov3.x, 0.0, -mov3.y) + na::Vector3::z() * mov3.z;
+
+        if let Some(direction) = direction.try_normalize(0.01) {
+            self.eye += direction * self.movement.speed() * delta * self.speed_from_distance();
+        }
+    }
+
     pub fn speed_from_distance(&self) -> f32 {
         let min_speed = 0.1;
         let max_speed = 20.0;
@@ -138,3 +702,376 @@ impl TargetCamera {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> TargetCamera {
+        TargetCamera::new(4.0 / 3.0, PI / 2.0, 0.01, 1000.0, 0.0, 2.0)
+    }
+
+    #[test]
+    fn narrower_fov_magnifies_projection() {
+        let mut camera = camera();
+        let wide = camera.get_vp_matrix();
+
+        camera.set_fov(0.5);
+        let narrow = camera.get_vp_matrix();
+
+        // the view is unchanged, so only the projection scale differs
+        assert!(narrow[(0, 0)].abs() > wide[(0, 0)].abs());
+        assert!((narrow[(0, 0)] / wide[(0, 0)] - 1.0 / 0.25f32.tan()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn loaded_state_restores_view() {
+        let mut camera = camera();
+        camera.focus_on(na::Point3::new(1.0, 2.0, 3.0), 0.0);
+        camera.rotate(&na::Vector2::new(40.0, -25.0));
+        camera.roll(0.3);
+        let state = camera.save_state();
+        let saved = camera.get_vp_matrix();
+
+        camera.rotate(&na::Vector2::new(-100.0, 60.0));
+        camera.focus_on(na::Point3::new(-5.0, 0.0, 1.0), 0.0);
+        camera.zoom(3.0);
+        camera.update(1.0);
+        assert_ne!(camera.get_vp_matrix(), saved);
+
+        camera.load_state(&state);
+        assert_eq!(camera.get_vp_matrix(), saved);
+    }
+
+    #[test]
+    fn focus_transition_reaches_destination() {
+        let mut camera = camera();
+        let destination = na::Point3::new(4.0, -2.0, 1.0);
+        camera.focus_on(destination, 1.0);
+
+        for _ in 0..9 {
+            assert!(camera.update(0.1));
+        }
+        assert!(na::distance(&camera.target, &destination) > 0.001);
+
+        // retargeting continues from where the transition got to
+        let midway = camera.target;
+        camera.focus_on(destination, 1.0);
+        assert_eq!(camera.target, midway);
+
+        for _ in 0..10 {
+            camera.update(0.1);
+        }
+        assert!(na::distance(&camera.target, &destination) < 0.0001);
+    }
+
+    #[test]
+    fn reset_restores_initial_view() {
+        let mut camera = camera();
+        let initial = camera.get_vp_matrix();
+
+        camera.rotate(&na::Vector2::new(120.0, 80.0));
+        camera.focus_on(na::Point3::new(3.0, 3.0, 0.0), 0.0);
+        camera.roll(1.0);
+        camera.toggle_mode();
+        camera.update(0.1);
+        assert_ne!(camera.get_vp_matrix(), initial);
+
+        camera.reset();
+        assert!(camera.update(0.0));
+        assert_eq!(camera.get_vp_matrix(), initial);
+    }
+
+    #[test]
+    fn pitch_stays_away_from_poles() {
+        let mut camera = camera();
+
+        camera.rotate(&na::Vector2::new(0.0, -100_000.0));
+        assert!(camera.pitch <= PI / 2.0 - PITCH_EPSILON);
+        assert!(camera.pitch > PI / 2.0 - 2.0 * PITCH_EPSILON);
+
+        camera.rotate(&na::Vector2::new(0.0, 100_000.0));
+        assert!(camera.pitch >= -PI / 2.0 + PITCH_EPSILON);
+    }
+
+    #[test]
+    fn inverted_y_turns_pitch_the_other_way() {
+        let pitch_delta = |invert_y: bool| {
+            let mut camera = camera();
+            camera.invert_y = invert_y;
+            let before = camera.pitch;
+            camera.rotate(&na::Vector2::new(0.0, 20.0));
+            camera.pitch - before
+        };
+
+        let normal = pitch_delta(false);
+        let inverted = pitch_delta(true);
+        assert!(normal != 0.0);
+        assert!((normal + inverted).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fly_focus_moves_eye_and_keeps_direction() {
+        let mut camera = camera();
+        camera.set_mode(CameraMode::Fly);
+        let target = camera.target;
+        let forward = camera.forward();
+        let point = na::Point3::new(10.0, -4.0, 2.0);
+
+        camera.focus_on(point, 0.5);
+        for _ in 0..10 {
+            camera.update(0.1);
+        }
+
+        assert_eq!(camera.target, target);
+        assert_eq!(camera.forward(), forward);
+        assert!(na::distance(&(camera.eye + forward * camera.distance), &point) < 0.0001);
+    }
+
+    #[test]
+    fn pan_moves_target_in_view_plane() {
+        let mut camera = camera();
+        // yaw 0 and pitch 45 degrees look along +Y and down
+        let half = 0.5f32.sqrt();
+        assert!((camera.right() - na::Vector3::x()).norm() < 1e-6);
+        assert!((camera.up() - na::Vector3::new(0.0, half, half)).norm() < 1e-6);
+
+        camera.pan(na::Vector2::new(10.0, 0.0));
+        assert!((camera.target.coords - na::Vector3::new(-0.04, 0.0, 0.0)).norm() < 1e-6);
+
+        let before = camera.target;
+        camera.pan(na::Vector2::new(0.0, -10.0));
+        let moved = camera.target - before;
+        assert!(moved.dot(&camera.forward()).abs() < 1e-6);
+        assert!((moved - camera.up() * 0.04).norm() < 1e-6);
+    }
+
+    #[test]
+    fn center_ray_points_from_eye_to_target() {
+        let mut camera = camera();
+        camera.focus_on(na::Point3::new(1.0, -2.0, 0.5), 0.0);
+        camera.update(0.0);
+
+        let (origin, direction) = camera.screen_ray(na::Vector2::new(400.0, 300.0), (800, 600));
+        let to_target = (camera.target - camera.project_pos()).normalize();
+        assert!((direction - to_target).norm() < 1e-4);
+        // the ray starts on the near plane, in front of the eye
+        assert!((origin - camera.project_pos()).dot(&to_target) > 0.0);
+    }
+
+    #[test]
+    fn faster_movement_scales_displacement() {
+        let displacement = |faster: bool| {
+            let mut camera = camera();
+            camera.movement.set_base_speed(10.0);
+            camera.movement.set_faster_factor(3.0);
+            camera.movement.forward = true;
+            camera.movement.faster = faster;
+            camera.update(0.1);
+            camera.target.coords.norm()
+        };
+
+        let normal = displacement(false);
+        let faster = displacement(true);
+        assert!(normal > 0.0);
+        assert!((faster / normal - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn view_projection_is_product_of_parts() {
+        let mut camera = camera();
+        camera.rotate(&na::Vector2::new(30.0, 15.0));
+        camera.focus_on(na::Point3::new(2.0, 1.0, -1.0), 0.0);
+        camera.update(0.0);
+
+        let product = camera.projection_matrix() * camera.view_matrix();
+        assert!((product - camera.get_vp_matrix()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn north_up_keeps_view_level_despite_roll() {
+        let mut camera = camera();
+        camera.roll(0.7);
+        camera.north_up = true;
+        let view = camera.view_matrix();
+
+        // rows of the view rotation are the camera axes in world space
+        let right = na::Vector3::new(view[(0, 0)], view[(0, 1)], view[(0, 2)]);
+        let up = na::Vector3::new(view[(1, 0)], view[(1, 1)], view[(1, 2)]);
+        assert!(right.z.abs() < 1e-6);
+        assert!(up.z > 0.0);
+        // up is as close to ecliptic north as the pitch allows
+        assert!((up.z - (PI / 4.0).sin()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn orthographic_projection_has_no_perspective_divide() {
+        let mut camera = camera();
+        camera.set_orthographic(10.0);
+        let projection = camera.projection_matrix();
+        assert_eq!(projection.row(3), na::Matrix4::<f32>::identity().row(3));
+
+        camera.set_perspective();
+        assert!(camera.projection_matrix()[(3, 2)] != 0.0);
+    }
+
+    #[test]
+    fn invalid_clip_planes_are_rejected() {
+        let mut camera = camera();
+        let before = camera.projection_matrix();
+        assert!(camera.set_clip_planes(10.0, 1.0).is_err());
+        assert!(camera.set_clip_planes(0.0, 1.0).is_err());
+        assert!(camera.set_clip_planes(1.0, 1.0).is_err());
+        assert_eq!(camera.projection_matrix(), before);
+
+        camera.set_clip_planes(0.5, 5000.0).unwrap();
+        assert!((camera.far_clip() / 5000.0 - 1.0).abs() < 1e-3);
+        assert_ne!(camera.projection_matrix(), before);
+    }
+
+    #[test]
+    fn rotation_sensitivity_scales_mouse_turn() {
+        let yaw_delta = |sensitivity: f32| {
+            let mut camera = camera();
+            camera.set_rotation_sensitivity(sensitivity);
+            let before = camera.yaw;
+            camera.rotate(&na::Vector2::new(-20.0, 0.0));
+            camera.yaw - before
+        };
+
+        assert!((yaw_delta(2.0) - 2.0 * yaw_delta(1.0)).abs() < 1e-5);
+        assert!(yaw_delta(1.0) > 0.0);
+        // negative sensitivity is clamped to zero instead of reversing the mouse
+        assert_eq!(yaw_delta(-1.0), 0.0);
+    }
+
+    #[test]
+    fn arrow_keys_turn_at_key_rotation_speed() {
+        let mut camera = camera();
+        let (yaw, pitch) = (camera.yaw, camera.pitch);
+
+        camera.movement.rotate_left = true;
+        camera.movement.rotate_up = true;
+        assert!(camera.update(0.1));
+        assert!((camera.yaw - (yaw + KEY_ROTATION_SPEED * 0.1)).abs() < 1e-5);
+        assert!((camera.pitch - (pitch + KEY_ROTATION_SPEED * 0.1)).abs() < 1e-5);
+        // rotation keeps the orbit target
+        assert_eq!(camera.target, na::Point3::origin());
+
+        // opposite keys cancel out
+        camera.movement.rotate_right = true;
+        camera.movement.rotate_down = true;
+        let (yaw, pitch) = (camera.yaw, camera.pitch);
+        camera.update(0.1);
+        assert!((camera.yaw - yaw).abs() < 1e-6);
+        assert!((camera.pitch - pitch).abs() < 1e-6);
+    }
+
+    #[test]
+    fn arrow_keys_stop_short_of_the_pole() {
+        let mut camera = camera();
+        camera.movement.rotate_up = true;
+        for _ in 0..100 {
+            camera.update(0.1);
+        }
+        assert!((camera.pitch - (PI / 2.0 - PITCH_EPSILON)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zoom_stops_outside_the_target_body() {
+        let mut camera = camera();
+        camera.set_target_radius(3.0);
+        // already inside the body, so moved out right away
+        assert!((camera.distance - (3.0 + MIN_ZOOM_DISTANCE)).abs() < 1e-5);
+
+        camera.zoom(1000.0);
+        for _ in 0..10 {
+            camera.update(0.1);
+        }
+        assert!((camera.distance - (3.0 + MIN_ZOOM_DISTANCE)).abs() < 1e-5);
+
+        camera.set_target_radius(0.0);
+        camera.zoom(1000.0);
+        for _ in 0..10 {
+            camera.update(0.1);
+        }
+        assert!((camera.distance - MIN_ZOOM_DISTANCE).abs() < 1e-5);
+    }
+
+    #[test]
+    fn followed_body_stays_centered_as_it_moves() {
+        let mut camera = camera();
+        camera.set_follow(Some(3));
+        assert_eq!(camera.follow(), Some(3));
+
+        // the first position eases the target over
+        camera.track(na::Point3::new(10.0, 0.0, 0.0));
+        camera.update(2.0);
+        assert!((camera.target - na::Point3::new(10.0, 0.0, 0.0)).norm() < 1e-5);
+
+        // later positions carry the camera along
+        let eye_offset = camera.project_pos() - camera.target;
+        camera.track(na::Point3::new(12.0, 1.0, 0.0));
+        camera.update(0.01);
+        assert!((camera.target - na::Point3::new(12.0, 1.0, 0.0)).norm() < 1e-5);
+        assert!(((camera.project_pos() - camera.target) - eye_offset).norm() < 1e-5);
+
+        camera.set_follow(None);
+        camera.track(na::Point3::new(50.0, 0.0, 0.0));
+        camera.update(2.0);
+        assert!((camera.target - na::Point3::new(12.0, 1.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn quarter_roll_turns_up_to_the_left() {
+        let mut camera = camera();
+        let (right, forward) = (camera.right(), camera.forward());
+
+        camera.roll(PI / 2.0);
+        assert!((camera.up() + right).norm() < 1e-6);
+        assert!((camera.forward() - forward).norm() < 1e-6);
+
+        camera.roll(-PI / 2.0);
+        assert!((camera.right() - right).norm() < 1e-6);
+        assert!(camera.roll_angle().abs() < 1e-6);
+    }
+
+    #[test]
+    fn travel_from_fly_mode_starts_where_the_camera_looks() {
+        let mut camera = camera();
+        camera.set_mode(CameraMode::Fly);
+        camera.yaw += 0.5;
+        let looked_at = camera.eye + camera.forward() * camera.distance;
+
+        let state = CameraState {
+            target: [10.0, 0.0, 0.0],
+            yaw: -1.0,
+            pitch: 0.2,
+            roll: 0.0,
+            distance: 7.0,
+            aspect: 1.0,
+        };
+        camera.travel_to_state(&state, 1.0);
+        assert_eq!(camera.mode(), CameraMode::Orbit);
+        assert!((camera.target - looked_at).norm() < 1e-5);
+
+        camera.update(1.0);
+        assert!((camera.target - na::Point3::new(10.0, 0.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn angular_field_widens_with_aspect() {
+        let (horizontal, vertical) = angular_field(PI / 2.0, 1.0);
+        assert!((horizontal - 90.0).abs() < 1e-4 && (vertical - 90.0).abs() < 1e-4);
+
+        // tan(45°) * 16 / 9 gives about 121.3° horizontally
+        let (horizontal, vertical) = angular_field(PI / 2.0, 16.0 / 9.0);
+        assert!((horizontal - 121.28).abs() < 0.01, "{}", horizontal);
+        assert!((vertical - 90.0).abs() < 1e-4);
+
+        let mut camera = camera();
+        assert!(camera.angular_field().is_some());
+        camera.set_orthographic(10.0);
+        assert_eq!(camera.angular_field(), None);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::asteroids;
+use crate::background;
+use crate::calendar::CalendarDate;
+use crate::trail;
+
+pub const USAGE: &str = "Usage: r3d3 [options]
+
+Options:
+    --width <pixels>            Window width
+    --height <pixels>           Window height
+    --fullscreen                Use fullscreen window
+    --title <text>              Window title
+    --fps                       Show frame rate in window title
+    --speed <multiplier>        Camera movement speed
+    --sensitivity <multiplier>  Mouse rotation sensitivity
+    --date <YYYY-MM-DD>         Start simulation at this date
+    --step-hours <hours>        Time moved by Shift+. and Shift+, while paused
+    --ephemeris <YYYY-MM-DD>    Print body positions for this date and exit
+    --msaa <samples>            Anti-aliasing samples per pixel, 0 to disable
+    --vsync <on|off>            Wait for display refresh before showing a frame
+    --max-fps <fps>             Frame rate limit when vsync is off
+    --bench <frames>            Render this many frames without vsync, print timing and exit
+    --stars <catalog|off>       Star catalog resource, off to use the skybox texture
+    --asteroids <count>         Asteroids in the belt, 0 to disable
+    --trail <samples>           Samples in orbit trails of bodies without their own length
+    --background-pole <RRGGBB>  Background color towards the ecliptic poles
+    --background-ecliptic <RRGGBB>
+                                Background color along the ecliptic";
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Missing value for {}", flag)]
+    MissingValue { flag: String },
+    #[fail(display = "Invalid value {} for {}", value, flag)]
+    InvalidValue { flag: String, value: String },
+    #[fail(display = "Unknown argument {}", arg)]
+    UnknownArgument { arg: String },
+}
+
+/// Options passed on the command line.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    /// Window title shown before the simulation date.
+    pub title: Option<String>,
+    /// Show frame rate and frame time in the window title.
+    pub show_fps: bool,
+    /// Camera movement speed multiplier.
+    pub movement_speed: Option<f32>,
+    /// Mouse rotation sensitivity multiplier.
+    pub rotation_sensitivity: Option<f32>,
+    /// Start simulation at this date, as year, month and day.
+    pub start_date: Option<(i32, u32, u32)>,
+    /// Simulation hours moved by one step while paused.
+    pub step_hours: Option<f32>,
+    /// Print ephemeris for this date instead of opening a window.
+    pub ephemeris_date: Option<(i32, u32, u32)>,
+    /// Multisample anti-aliasing samples per pixel, 0 disables it.
+    pub msaa_samples: u8,
+    /// Synchronize buffer swaps with display refresh.
+    pub vsync: bool,
+    /// Frame rate limit used when vsync is off.
+    pub max_fps: Option<u32>,
+    /// Render this many frames as fast as possible, then print frame times and exit.
+    pub bench_frames: Option<u32>,
+    /// Star catalog resource drawn instead of the skybox texture.
+    pub star_catalog: Option<String>,
+    /// Asteroids drawn in the belt between Mars and Jupiter.
+    pub asteroid_count: usize,
+    /// Samples kept in orbit trails, unless set for the body in the planet table.
+    pub trail_length: Option<usize>,
+    /// Background gradient color towards the ecliptic poles.
+    pub background_pole: [f32; 3],
+    /// Background gradient color along the ecliptic.
+    pub background_ecliptic: [f32; 3],
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: 800,
+            height: 600,
+            fullscreen: false,
+            title: None,
+            show_fps: false,
+            movement_speed: None,
+            rotation_sensitivity: None,
+            start_date: None,
+            step_hours: None,
+            ephemeris_date: None,
+            msaa_samples: 4,
+            vsync: true,
+            max_fps: None,
+            bench_frames: None,
+            star_catalog: Some("stars.ron".into()),
+            asteroid_count: asteroids::DEFAULT_COUNT,
+            trail_length: None,
+            background_pole: background::DEFAULT_POLE_COLOR,
+            background_ecliptic: background::DEFAULT_ECLIPTIC_COLOR,
+        }
+    }
+}
+
+impl Options {
+    /// Parse options from arguments, not including the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, Error> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--width" => options.width = parse_size(&arg, args.next())?,
+                "--height" => options.height = parse_size(&arg, args.next())?,
+                "--fullscreen" => options.fullscreen = true,
+                "--title" => match args.next() {
+                    Some(value) => options.title = Some(value),
+                    None => return Err(Error::MissingValue { flag: arg }),
+                },
+                "--fps" => options.show_fps = true,
+                "--date" => options.start_date = Some(parse_date(&arg, args.next())?),
+                "--ephemeris" => options.ephemeris_date = Some(parse_date(&arg, args.next())?),
+                "--msaa" => options.msaa_samples = parse_samples(&arg, args.next())?,
+                "--vsync" => options.vsync = parse_switch(&arg, args.next())?,
+                "--max-fps" => options.max_fps = Some(parse_size(&arg, args.next())?),
+                "--bench" => options.bench_frames = Some(parse_size(&arg, args.next())?),
+                "--stars" => match args.next() {
+                    Some(ref value) if value == "off" => options.star_catalog = None,
+                    Some(value) => options.star_catalog = Some(value),
+                    None => return Err(Error::MissingValue { flag: arg }),
+                },
+                "--asteroids" => options.asteroid_count = parse_count(&arg, args.next())?,
+                "--trail" => options.trail_length = Some(parse_trail_length(&arg, args.next())?),
+                "--background-pole" => options.background_pole = parse_color(&arg, args.next())?,
+                "--background-ecliptic" => {
+                    options.background_ecliptic = parse_color(&arg, args.next())?
+                }
+                "--speed" => options.movement_speed = Some(parse_multiplier(&arg, args.next())?),
+                "--step-hours" => options.step_hours = Some(parse_multiplier(&arg, args.next())?),
+                "--sensitivity" => {
+                    options.rotation_sensitivity = Some(parse_multiplier(&arg, args.next())?)
+                }
+                _ => return Err(Error::UnknownArgument { arg }),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_multiplier(flag: &str, value: Option<String>) -> Result<f32, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.parse::<f32>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+/// Parse hexadecimal `RRGGBB` color, with optional leading `#`.
+fn parse_color(flag: &str, value: Option<String>) -> Result<[f32; 3], Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    let hex = value.strip_prefix('#').unwrap_or(&value);
+    let color = if hex.len() == 6 && hex.is_ascii() {
+        u32::from_str_radix(hex, 16).ok().map(|rgb| {
+            [
+                ((rgb >> 16) & 0xff) as f32 / 255.0,
+                ((rgb >> 8) & 0xff) as f32 / 255.0,
+                (rgb & 0xff) as f32 / 255.0,
+            ]
+        })
+    } else {
+        None
+    };
+
+    color.ok_or_else(|| Error::InvalidValue {
+        flag: flag.into(),
+        value: value.clone(),
+    })
+}
+
+fn parse_date(flag: &str, value: Option<String>) -> Result<(i32, u32, u32), Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    // leading minus is a year before 1 BC, not a separator
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.as_str()),
+    };
+    let parts = rest.split('-').collect::<Vec<_>>();
+
+    let date = match parts.as_slice() {
+        [year, month, day] => match (year.parse::<i32>(), month.parse(), day.parse()) {
+            (Ok(year), Ok(month), Ok(day)) => Some((sign * year, month, day)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match date {
+        Some((year, month, day)) if CalendarDate::from_ymd(year, month, day).is_ok() => {
+            Ok((year, month, day))
+        }
+        _ => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+fn parse_samples(flag: &str, value: Option<String>) -> Result<u8, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.parse::<u8>() {
+        Ok(samples) => Ok(samples),
+        Err(_) => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+fn parse_switch(flag: &str, value: Option<String>) -> Result<bool, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+fn parse_count(flag: &str, value: Option<String>) -> Result<usize, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.parse::<usize>() {
+        Ok(count) => Ok(count),
+        Err(_) => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+/// Parse trail samples, from 1 up to the trail length limit.
+fn parse_trail_length(flag: &str, value: Option<String>) -> Result<usize, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.parse::<usize>() {
+        Ok(length) if (1..=trail::MAX_LENGTH).contains(&length) => Ok(length),
+        _ => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+fn parse_size(flag: &str, value: Option<String>) -> Result<u32, Error> {
+    let value = value.ok_or_else(|| Error::MissingValue { flag: flag.into() })?;
+
+    match value.parse::<u32>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(Error::InvalidValue {
+            flag: flag.into(),
+            value,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, Error> {
+        Options::parse(args.iter().map(|&arg| String::from(arg)))
+    }
+
+    #[test]
+    fn defaults_without_arguments() {
+        let options = parse(&[]).unwrap();
+        assert_eq!((options.width, options.height), (800, 600));
+        assert!(!options.fullscreen);
+    }
+
+    #[test]
+    fn explicit_window_size() {
+        let options = parse(&["--width", "1280", "--height", "720", "--fullscreen"]).unwrap();
+        assert_eq!((options.width, options.height), (1280, 720));
+        assert!(options.fullscreen);
+    }
+
+    #[test]
+    fn bad_integer_is_rejected() {
+        match parse(&["--width", "wide"]) {
+            Err(Error::InvalidValue { flag, value }) => {
+                assert_eq!((&*flag, &*value), ("--width", "wide"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match parse(&["--height"]) {
+            Err(Error::MissingValue { flag }) => assert_eq!(flag, "--height"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn date_accepts_negative_years_and_rejects_invalid_days() {
+        let options = parse(&["--date", "-0500-03-01"]).unwrap();
+        assert_eq!(options.start_date, Some((-500, 3, 1)));
+
+        match parse(&["--date", "2021-02-30"]) {
+            Err(Error::InvalidValue { flag, value }) => {
+                assert_eq!(flag, "--date");
+                assert_eq!(value, "2021-02-30");
+            }
+            _ => panic!("expected invalid value"),
+        }
+    }
+}
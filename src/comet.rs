@@ -0,0 +1,71 @@
+use nalgebra as na;
+
+/// Distance from the Sun in AU beyond which comets have no tail.
+pub const TAIL_CUTOFF_AU: f64 = 5.0;
+
+/// Closest distance used for tail length, so tails stay finite at perihelion.
+const MIN_TAIL_DISTANCE_AU: f64 = 0.3;
+
+/// Direction a comet tail points, away from the Sun; none with the comet at the Sun.
+pub fn tail_direction(comet: &na::Point3<f64>, sun: &na::Point3<f64>) -> Option<na::Vector3<f64>> {
+    (comet - sun).try_normalize(1e-12)
+}
+
+/// Tail length for a comet `distance_au` from the Sun, with `length_at_1au` at 1 AU.
+///
+/// The tail grows inversely with distance, and fades out towards `TAIL_CUTOFF_AU` so it does
+/// not pop in when the comet comes close.
+pub fn tail_length(length_at_1au: f64, distance_au: f64) -> f64 {
+    if distance_au >= TAIL_CUTOFF_AU {
+        return 0.0;
+    }
+
+    let fade = 1.0 - distance_au / TAIL_CUTOFF_AU;
+    length_at_1au / distance_au.max(MIN_TAIL_DISTANCE_AU) * fade
+}
+
+/// `segments + 1` evenly spaced points from the comet to the end of its tail, or none when
+/// there is no tail.
+pub fn tail_points(
+    comet: &na::Point3<f64>,
+    sun: &na::Point3<f64>,
+    length: f64,
+    segments: usize,
+) -> Vec<na::Point3<f64>> {
+    match tail_direction(comet, sun) {
+        Some(direction) if length > 0.0 => (0..=segments.max(1))
+            .map(|i| comet + direction * length * i as f64 / segments.max(1) as f64)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_points_away_from_the_sun() {
+        let sun = na::Point3::new(1.0, 1.0, 0.0);
+        let comet = na::Point3::new(1.0, 4.0, 4.0);
+        let points = tail_points(&comet, &sun, 2.0, 4);
+
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[0], comet);
+        let end = points[4] - comet;
+        assert!((end - na::Vector3::new(0.0, 0.6, 0.8) * 2.0).norm() < 1e-12);
+        assert!(tail_points(&sun, &sun, 2.0, 4).is_empty());
+        assert!(tail_points(&comet, &sun, 0.0, 4).is_empty());
+    }
+
+    #[test]
+    fn tail_shrinks_with_distance_and_vanishes_at_cutoff() {
+        let near = tail_length(0.5, 1.0);
+        assert!((near - 0.5 * (1.0 - 1.0 / TAIL_CUTOFF_AU)).abs() < 1e-12);
+        assert!(tail_length(0.5, 2.0) < near);
+        // finite at perihelion
+        assert!(tail_length(0.5, 0.0) <= 0.5 / MIN_TAIL_DISTANCE_AU);
+        assert_eq!(tail_length(0.5, TAIL_CUTOFF_AU), 0.0);
+        assert!(tail_length(0.5, TAIL_CUTOFF_AU - 0.01) < 0.01);
+    }
+}
@@ -19,9 +19,14 @@ pub struct Cube {
     program: render_gl::Program,
     program_view_projection_location: i32,
     camera_pos_location: i32,
+    light_dir_location: i32,
+    light_color_location: i32,
+    /// Color of the directional light.
+    pub light_color: na::Vector3<f32>,
     _vbo: buffer::ArrayBuffer,
     _ebo: buffer::ElementArrayBuffer,
     index_count: i32,
+    bounding_radius: f32,
     vao: buffer::VertexArray,
     _debug_rays: Vec<render_gl::RayMarker>,
 }
@@ -37,6 +42,8 @@ impl Cube {
         let program = render_gl::Program::from_res(gl, res, "shaders/cube")?;
         let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
         let camera_pos_location = program.get_uniform_location("CameraPos")?;
+        let light_dir_location = program.get_uniform_location("LightDir")?;
+        let light_color_location = program.get_uniform_location("LightColor")?;
 
         let v0 = (-1.0, -1.0, -1.0);
         let v1 = (1.0, -1.0, -1.0);
@@ -267,10 +274,17 @@ impl Cube {
             program,
             program_view_projection_location,
             camera_pos_location,
+            light_dir_location,
+            light_color_location,
+            light_color: na::Vector3::new(1.0, 1.0, 1.0),
             _vbo: vbo,
             _ebo: ebo,
             index_count: ebo_data.len() as i32,
             vao,
+            bounding_radius: vbo_data
+                .iter()
+                .map(|v| na::Vector3::new(v.pos.d0, v.pos.d1, v.pos.d2).norm())
+                .fold(0.0, f32::max),
             _debug_rays: vbo_data
                 .iter()
                 .map(|v| {
@@ -289,12 +303,64 @@ impl Cube {
         })
     }
 
-    pub fn render(&self, gl: &gl::Gl, vp_matrix: &na::Matrix4<f32>, camera_pos: &na::Vector3<f32>) {
+    /// Center and radius of a sphere enclosing the cube, in model space.
+    pub fn bounding_sphere(&self) -> (na::Point3<f32>, f32) {
+        (na::Point3::origin(), self.bounding_radius)
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (
+            program_view_projection_location,
+            camera_pos_location,
+            light_dir_location,
+            light_color_location,
+        ) = self.program.reload(res, |program| {
+            Ok((
+                program.get_uniform_location("ViewProjection")?,
+                program.get_uniform_location("CameraPos")?,
+                program.get_uniform_location("LightDir")?,
+                program.get_uniform_location("LightColor")?,
+            ))
+        })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.camera_pos_location = camera_pos_location;
+        self.light_dir_location = light_dir_location;
+        self.light_color_location = light_color_location;
+        Ok(())
+    }
+
+    /// Render cube transformed by `model` matrix.
+    ///
+    /// The shader receives the combined model-view-projection matrix, and camera position and
+    /// light direction (pointing towards the light) transformed into the model space of the cube.
+    pub fn render(
+        &self,
+        gl: &gl::Gl,
+        vp_matrix: &na::Matrix4<f32>,
+        model: &na::Matrix4<f32>,
+        camera_pos: &na::Vector3<f32>,
+        light_dir: &na::Vector3<f32>,
+    ) {
+        let mvp_matrix = vp_matrix * model;
+        let inverse_model = model.try_inverse().unwrap_or_else(na::Matrix4::identity);
+        let model_camera_pos = inverse_model
+            .transform_point(&na::Point3::from(*camera_pos))
+            .coords;
+        let model_light_dir = inverse_model
+            .transform_vector(light_dir)
+            .try_normalize(0.0001)
+            .unwrap_or(*light_dir);
+
         self.program.set_used();
         self.program
-            .set_uniform_matrix4fv(self.program_view_projection_location, vp_matrix);
+            .set_uniform_matrix4fv(self.program_view_projection_location, &mvp_matrix);
         self.program
-            .set_uniform_3f(self.camera_pos_location, camera_pos);
+            .set_uniform_3f(self.camera_pos_location, &model_camera_pos);
+        self.program
+            .set_uniform_3f(self.light_dir_location, &model_light_dir);
+        self.program
+            .set_uniform_3f(self.light_color_location, &self.light_color);
         self.vao.bind();
 
         unsafe {
@@ -307,3 +373,65 @@ impl Cube {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera;
+    use crate::render_gl::mock_gl;
+    use std::path::Path;
+
+    fn gl_and_cube() -> (gl::Gl, Cube) {
+        let (gl, debug_lines) = mock_gl::test_debug_lines();
+        let cube = Cube::new(&mock_gl::test_resources(), &gl, &debug_lines).unwrap();
+        (gl, cube)
+    }
+
+    #[test]
+    fn render_uploads_model_view_projection() {
+        let (gl, cube) = gl_and_cube();
+
+        let vp = na::Matrix4::new_perspective(1.5, 1.0, 0.1, 100.0)
+            * na::Matrix4::look_at_rh(
+                &na::Point3::new(0.0, -5.0, 2.0),
+                &na::Point3::origin(),
+                &na::Vector3::z(),
+            );
+        let model = na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0));
+        cube.render(
+            &gl,
+            &vp,
+            &model,
+            &na::Vector3::new(0.0, -5.0, 2.0),
+            &na::Vector3::z(),
+        );
+
+        let uploaded = mock_gl::with_state(|s| s.matrices.last().cloned()).unwrap();
+        let expected = vp * model;
+        for (a, b) in uploaded.iter().zip(expected.as_slice()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+        // the translation moves the cube center, so it projects where the translated point does
+        let center = expected.transform_point(&na::Point3::origin());
+        let moved = vp.transform_point(&na::Point3::new(1.0, 2.0, 3.0));
+        assert!((center - moved).norm() < 1e-5);
+    }
+
+    #[test]
+    fn bounding_sphere_reaches_corners() {
+        let (center, radius) = gl_and_cube().1.bounding_sphere();
+        assert_eq!(center, na::Point3::origin());
+        // half of the diagonal of a cube with side 2
+        assert!((radius - 3f32.sqrt()).abs() < 1e-6);
+
+        // a corner just outside the left plane still keeps the cube from being culled
+        let vp = na::Matrix4::new_orthographic(-1.0, 1.0, -1.0, 1.0, -10.0, 10.0);
+        let planes = camera::frustum::planes_from_matrix(&vp);
+        let center = na::Point3::new(-1.0 - radius * 0.9, 0.0, 0.0);
+        assert!(camera::frustum::sphere_in_frustum(&planes, &center, radius));
+        let center = na::Point3::new(-1.0 - radius * 1.1, 0.0, 0.0);
+        assert!(!camera::frustum::sphere_in_frustum(
+            &planes, &center, radius
+        ));
+    }
+}
@@ -1,4 +1,53 @@
 use failure;
+use gl;
+
+/// Stop reading errors after this many, since without a current context `glGetError`
+/// may keep returning one.
+const MAX_GL_ERRORS: usize = 16;
+
+/// Name of an OpenGL error code for log messages.
+pub fn gl_error_name(code: gl::types::GLenum) -> &'static str {
+    match code {
+        gl::INVALID_ENUM => "GL_INVALID_ENUM",
+        gl::INVALID_VALUE => "GL_INVALID_VALUE",
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        _ => "unknown GL error",
+    }
+}
+
+/// Read error codes from `get_error` until it returns `GL_NO_ERROR`.
+pub fn drain_gl_errors<F: FnMut() -> gl::types::GLenum>(
+    mut get_error: F,
+) -> Vec<gl::types::GLenum> {
+    (0..MAX_GL_ERRORS)
+        .map(|_| get_error())
+        .take_while(|&code| code != gl::NO_ERROR)
+        .collect()
+}
+
+/// Log and return OpenGL errors raised since the last check, labelled with `context`.
+///
+/// Reading errors waits for the driver, so release builds skip the check and return nothing.
+pub fn check_gl_error(gl: &gl::Gl, context: &str) -> Vec<gl::types::GLenum> {
+    if !cfg!(debug_assertions) {
+        return Vec::new();
+    }
+
+    let errors = drain_gl_errors(|| unsafe { gl.GetError() });
+    for &code in &errors {
+        error!(
+            "OpenGL error {} (0x{:x}) in {}",
+            gl_error_name(code),
+            code,
+            context
+        );
+    }
+    errors
+}
 
 pub fn failure_to_string(e: failure::Error) -> String {
     use std::fmt::Write;
@@ -30,3 +79,72 @@ pub fn failure_to_string(e: failure::Error) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+    use std::sync::Mutex;
+
+    /// Records messages of all tests, so each test looks for its own.
+    struct CapturingLogger;
+
+    static MESSAGES: Mutex<Vec<(log::Level, String)>> = Mutex::new(Vec::new());
+    static LOGGER: CapturingLogger = CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let message = (record.level(), record.args().to_string());
+            MESSAGES.lock().unwrap().push(message);
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture_logs() {
+        // fails if already installed by another test, which is fine
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+
+    #[test]
+    fn gl_errors_are_logged_with_context() {
+        capture_logs();
+        let gl = mock_gl::load();
+        mock_gl::with_state(|s| s.errors.push_back(gl::INVALID_ENUM));
+
+        assert_eq!(check_gl_error(&gl, "logging test"), [gl::INVALID_ENUM]);
+        let expected = (
+            log::Level::Error,
+            "OpenGL error GL_INVALID_ENUM (0x500) in logging test".to_string(),
+        );
+        assert!(MESSAGES.lock().unwrap().contains(&expected));
+    }
+
+    #[test]
+    fn draining_stops_at_no_error() {
+        let mut sequence = vec![
+            gl::INVALID_VALUE,
+            gl::OUT_OF_MEMORY,
+            gl::NO_ERROR,
+            gl::INVALID_ENUM,
+        ]
+        .into_iter();
+        let errors = drain_gl_errors(|| sequence.next().unwrap());
+
+        let names: Vec<_> = errors.into_iter().map(gl_error_name).collect();
+        assert_eq!(names, ["GL_INVALID_VALUE", "GL_OUT_OF_MEMORY"]);
+        // the error after GL_NO_ERROR is left for the next check
+        assert_eq!(sequence.next(), Some(gl::INVALID_ENUM));
+    }
+
+    #[test]
+    fn draining_gives_up_on_a_stuck_error() {
+        let errors = drain_gl_errors(|| gl::INVALID_OPERATION);
+        assert_eq!(errors.len(), MAX_GL_ERRORS);
+    }
+}
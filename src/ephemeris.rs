@@ -0,0 +1,138 @@
+use crate::calendar::{self, CalendarDate};
+use crate::planets::{self, PlanetDef};
+use crate::resources::Resources;
+use crate::retrograde;
+use nalgebra as na;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Header line of the ephemeris table.
+pub const HEADER: &str = "name,x_au,y_au,z_au,heliocentric_longitude,geocentric_longitude";
+
+/// Position of one body at the ephemeris date.
+#[derive(Clone, Debug, PartialEq)]
+struct Row {
+    name: String,
+    /// Heliocentric ecliptic coordinates in AU.
+    position: na::Point3<f64>,
+    /// Ecliptic longitude seen from the Sun in degrees, none for the Sun itself.
+    heliocentric_longitude: Option<f64>,
+    /// Ecliptic longitude seen from Earth in degrees, none for Earth itself.
+    geocentric_longitude: Option<f64>,
+}
+
+/// Positions of `bodies` at `days_since_j2000`, in the same order.
+fn rows(bodies: &[PlanetDef], days_since_j2000: f64) -> Vec<Row> {
+    let positions = planets::kepler_orbits(bodies)
+        .iter()
+        .map(|orbit| {
+            orbit
+                .as_ref()
+                .map(|orbit| orbit.position_at(days_since_j2000) / planets::SCENE_UNITS_PER_AU)
+                .unwrap_or_else(na::Point3::origin)
+        })
+        .collect::<Vec<_>>();
+    let earth = bodies
+        .iter()
+        .position(|body| body.name == "Earth")
+        .map(|i| positions[i]);
+
+    bodies
+        .iter()
+        .zip(positions.iter())
+        .map(|(body, &position)| {
+            let heliocentric_longitude = Some(position)
+                .filter(|position| position.coords.xy() != na::Vector2::zeros())
+                .map(|position| retrograde::ecliptic_longitude(&position.coords));
+            let geocentric_longitude = earth
+                .filter(|_| body.name != "Earth")
+                .map(|earth| retrograde::geocentric_longitude(&position, &earth));
+
+            Row {
+                name: body.name.clone(),
+                position,
+                heliocentric_longitude,
+                geocentric_longitude,
+            }
+        })
+        .collect()
+}
+
+/// Write rows as comma separated values, with an empty field where a longitude is undefined.
+fn write_rows<W: Write>(out: &mut W, rows: &[Row]) -> io::Result<()> {
+    let longitude = |value: Option<f64>| value.map(|v| format!("{:.4}", v)).unwrap_or_default();
+
+    writeln!(out, "{}", HEADER)?;
+    for row in rows {
+        writeln!(
+            out,
+            "{},{:.6},{:.6},{:.6},{},{}",
+            row.name,
+            row.position.x,
+            row.position.y,
+            row.position.z,
+            longitude(row.heliocentric_longitude),
+            longitude(row.geocentric_longitude)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Print positions of all bodies at midnight of the date to standard output.
+pub fn print(year: i32, month: u32, day: u32) -> Result<(), failure::Error> {
+    let res = Resources::from_relative_exe_path(Path::new("assets"))?;
+    let bodies = planets::load(&res, "planets.ron")?;
+    let days_since_j2000 =
+        CalendarDate::from_ymd(year, month, day)?.to_julian_day() - calendar::J2000;
+
+    let stdout = io::stdout();
+    write_rows(&mut stdout.lock(), &rows(&bodies, days_since_j2000))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    fn bodies() -> Vec<PlanetDef> {
+        let res = mock_gl::test_resources();
+        planets::load(&res, "planets.ron").unwrap()
+    }
+
+    #[test]
+    fn earth_and_sun_at_j2000_match_known_positions() {
+        let rows = rows(&bodies(), 0.0);
+        let row = |name: &str| rows.iter().find(|row| row.name == name).unwrap();
+
+        // Earth is near perihelion, and the Sun is seen at about 280.4 degrees
+        let earth = row("Earth");
+        assert!((earth.position.coords.norm() - 0.9833).abs() < 1e-3);
+        assert!((earth.heliocentric_longitude.unwrap() - 100.4).abs() < 0.5);
+        assert_eq!(earth.geocentric_longitude, None);
+
+        let sun = row("Sun");
+        assert_eq!(sun.position, na::Point3::origin());
+        assert_eq!(sun.heliocentric_longitude, None);
+        assert!((sun.geocentric_longitude.unwrap() - 280.4).abs() < 0.5);
+    }
+
+    #[test]
+    fn rows_are_written_as_csv_with_empty_undefined_fields() {
+        let rows = vec![Row {
+            name: "Sun".into(),
+            position: na::Point3::origin(),
+            heliocentric_longitude: None,
+            geocentric_longitude: Some(280.5),
+        }];
+        let mut out = Vec::new();
+        write_rows(&mut out, &rows).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{}\nSun,0.000000,0.000000,0.000000,,280.5000\n", HEADER)
+        );
+    }
+}
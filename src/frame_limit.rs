@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+/// Longest frame time fed to the simulation and camera, in seconds.
+pub const MAX_FRAME_DELTA_SECS: f64 = 0.1;
+
+/// Limit frame time to `max` seconds, so a stall (window drag, OS suspend) does not make
+/// the camera and simulation jump.
+pub fn clamp_delta(delta: f64, max: f64) -> f64 {
+    delta.min(max)
+}
+
+/// Time to wait after a frame that took `elapsed`, so frames start at most `max_fps` times
+/// per second.
+pub fn sleep_duration(max_fps: u32, elapsed: Duration) -> Duration {
+    if max_fps == 0 {
+        return Duration::from_secs(0);
+    }
+
+    let frame_time = Duration::from_secs(1) / max_fps;
+    frame_time.checked_sub(elapsed).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_fills_the_rest_of_the_frame() {
+        let elapsed = Duration::from_millis(4);
+        assert_eq!(sleep_duration(100, elapsed), Duration::from_millis(6));
+        // too slow already, or no limit
+        assert_eq!(
+            sleep_duration(100, Duration::from_millis(15)),
+            Duration::from_secs(0)
+        );
+        assert_eq!(sleep_duration(0, elapsed), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn long_frames_are_clamped() {
+        assert_eq!(clamp_delta(0.016, MAX_FRAME_DELTA_SECS), 0.016);
+        assert_eq!(clamp_delta(3.0, MAX_FRAME_DELTA_SECS), MAX_FRAME_DELTA_SECS);
+    }
+}
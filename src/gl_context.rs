@@ -0,0 +1,187 @@
+use sdl2::video::{GLContext, Window};
+
+/// Core profile versions to try, from preferred to the minimum our shaders need.
+const CONTEXT_VERSIONS: [(u8, u8); 2] = [(4, 1), (3, 3)];
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(
+        display = "Failed to create OpenGL core context, the graphics driver does not support any of versions {}: {}",
+        versions, message
+    )]
+    NoSupportedVersion { versions: String, message: String },
+}
+
+/// Versions to request in order, starting from `preferred` and falling back to lower ones.
+pub fn version_sequence(preferred: (u8, u8)) -> Vec<(u8, u8)> {
+    CONTEXT_VERSIONS
+        .iter()
+        .cloned()
+        .filter(|&version| version <= preferred)
+        .collect()
+}
+
+/// Create core profile context for the window, trying lower versions if preferred one fails.
+///
+/// Returns the context with the version that succeeded.
+pub fn create(
+    video_subsystem: &sdl2::VideoSubsystem,
+    window: &Window,
+    preferred: (u8, u8),
+) -> Result<(GLContext, (u8, u8)), Error> {
+    let versions = version_sequence(preferred);
+    let gl_attr = video_subsystem.gl_attr();
+    let mut last_error = String::new();
+
+    gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+
+    for &(major, minor) in &versions {
+        gl_attr.set_context_version(major, minor);
+
+        match window.gl_create_context() {
+            Ok(context) => return Ok((context, (major, minor))),
+            Err(message) => {
+                warn!(
+                    "OpenGL {}.{} context not available: {}",
+                    major, minor, message
+                );
+                last_error = message;
+            }
+        }
+    }
+
+    Err(Error::NoSupportedVersion {
+        versions: versions
+            .iter()
+            .map(|(major, minor)| format!("{}.{}", major, minor))
+            .collect::<Vec<_>>()
+            .join(", "),
+        message: last_error,
+    })
+}
+
+/// Sample counts to request in order, falling back to no multisampling.
+pub fn sample_sequence(requested: u8) -> Vec<u8> {
+    if requested == 0 {
+        vec![0]
+    } else {
+        vec![requested, 0]
+    }
+}
+
+/// Create window and its context, requesting MSAA framebuffer with `samples` per pixel.
+///
+/// If window or context can not be created with multisampling, it is retried without it.
+pub fn create_window<F>(
+    video_subsystem: &sdl2::VideoSubsystem,
+    samples: u8,
+    preferred: (u8, u8),
+    build_window: F,
+) -> Result<(Window, GLContext, (u8, u8)), failure::Error>
+where
+    F: Fn() -> Result<Window, failure::Error>,
+{
+    let gl_attr = video_subsystem.gl_attr();
+    let mut last_error = None;
+
+    // colors are computed in linear space and encoded to sRGB on write
+    gl_attr.set_framebuffer_srgb_compatible(true);
+
+    for samples in sample_sequence(samples) {
+        // multisampling is part of the pixel format, so it has to be set before window creation
+        gl_attr.set_multisample_buffers(if samples > 0 { 1 } else { 0 });
+        gl_attr.set_multisample_samples(samples);
+
+        let result = build_window().and_then(|window| {
+            let (context, version) = create(video_subsystem, &window, preferred)?;
+            Ok((window, context, version))
+        });
+
+        match result {
+            Ok(created) => return Ok(created),
+            Err(e) => {
+                if samples > 0 {
+                    warn!(
+                        "Failed to create window with {}x MSAA, retrying without: {}",
+                        samples, e
+                    );
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.expect("expected at least one sample count to be tried"))
+}
+
+/// Default framebuffer of the current context converts linear colors to sRGB when enabled.
+///
+/// Like the sample count, this asks the context for the encoding it actually got.
+pub fn negotiated_srgb(gl: &gl::Gl) -> bool {
+    let mut encoding: gl::types::GLint = 0;
+    unsafe {
+        gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl.GetFramebufferAttachmentParameteriv(
+            gl::FRAMEBUFFER,
+            gl::BACK_LEFT,
+            gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING,
+            &mut encoding,
+        );
+    }
+    encoding == gl::SRGB as gl::types::GLint
+}
+
+/// Samples per pixel of the current context framebuffer, as negotiated with the driver.
+///
+/// The driver may pick a different count than requested, so this asks the context rather
+/// than returning the requested attribute.
+pub fn negotiated_samples(gl: &gl::Gl) -> u8 {
+    let mut samples: gl::types::GLint = 0;
+    unsafe {
+        gl.GetIntegerv(gl::SAMPLES, &mut samples);
+    }
+    samples.clamp(0, i32::from(u8::MAX)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn versions_fall_back_from_preferred() {
+        assert_eq!(version_sequence((4, 1)), [(4, 1), (3, 3)]);
+        assert_eq!(version_sequence((4, 6)), [(4, 1), (3, 3)]);
+        assert_eq!(version_sequence((3, 3)), [(3, 3)]);
+        assert!(version_sequence((3, 0)).is_empty());
+    }
+
+    #[test]
+    fn samples_fall_back_to_none() {
+        assert_eq!(sample_sequence(4), [4, 0]);
+        assert_eq!(sample_sequence(0), [0]);
+    }
+
+    #[test]
+    fn negotiated_samples_come_from_the_context() {
+        let gl = mock_gl::load();
+        mock_gl::with_state(|s| s.integers.insert(gl::SAMPLES, 2));
+        assert_eq!(negotiated_samples(&gl), 2);
+    }
+
+    #[test]
+    fn srgb_encoding_comes_from_the_default_framebuffer() {
+        let gl = mock_gl::load();
+        mock_gl::with_state(|s| {
+            s.integers
+                .insert(gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING, gl::LINEAR as i32)
+        });
+        assert!(!negotiated_srgb(&gl));
+
+        mock_gl::with_state(|s| {
+            s.integers
+                .insert(gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING, gl::SRGB as i32)
+        });
+        assert!(negotiated_srgb(&gl));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::render_gl::{DebugLines, Polyline};
+use nalgebra as na;
+
+/// Grid line on the ecliptic plane.
+pub struct GridLine {
+    pub from: na::Point3<f32>,
+    pub to: na::Point3<f32>,
+    pub color: na::Vector4<f32>,
+}
+
+/// Lines spaced evenly over a square of `extent` units in each direction from origin.
+///
+/// The line along the X axis is red and the line along the Y axis is green.
+pub fn grid_lines(extent: f32, spacing: f32) -> Vec<GridLine> {
+    if !(extent > 0.0 && spacing > 0.0) {
+        return Vec::new();
+    }
+
+    let count = (extent / spacing).floor() as i32;
+    let line_color = na::Vector4::new(0.5, 0.5, 0.5, 0.3);
+    let mut lines = Vec::with_capacity((count as usize * 2 + 1) * 2);
+
+    for i in -count..=count {
+        let offset = i as f32 * spacing;
+
+        // parallel to Y axis
+        lines.push(GridLine {
+            from: na::Point3::new(offset, -extent, 0.0),
+            to: na::Point3::new(offset, extent, 0.0),
+            color: if i == 0 {
+                na::Vector4::new(0.2, 0.9, 0.2, 0.8)
+            } else {
+                line_color
+            },
+        });
+
+        // parallel to X axis
+        lines.push(GridLine {
+            from: na::Point3::new(-extent, offset, 0.0),
+            to: na::Point3::new(extent, offset, 0.0),
+            color: if i == 0 {
+                na::Vector4::new(0.9, 0.2, 0.2, 0.8)
+            } else {
+                line_color
+            },
+        });
+    }
+
+    lines
+}
+
+/// Reference grid on the ecliptic plane; lines are removed when dropped.
+pub struct ReferenceGrid {
+    _lines: Vec<Polyline>,
+}
+
+impl ReferenceGrid {
+    pub fn new(debug_lines: &DebugLines, extent: f32, spacing: f32) -> ReferenceGrid {
+        ReferenceGrid {
+            _lines: grid_lines(extent, spacing)
+                .into_iter()
+                .map(|line| debug_lines.colored_line(line.from, line.to, line.color))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn grid_has_two_lines_per_step_including_axes() {
+        let lines = grid_lines(10.0, 2.5);
+        // 4 steps on each side of origin plus the axis, in both directions
+        assert_eq!(lines.len(), 9 * 2);
+
+        let axes: Vec<_> = lines
+            .iter()
+            .filter(|line| line.color != na::Vector4::new(0.5, 0.5, 0.5, 0.3))
+            .collect();
+        assert_eq!(axes.len(), 2);
+        assert_eq!(axes[0].from, na::Point3::new(0.0, -10.0, 0.0));
+        assert_eq!(axes[1].from, na::Point3::new(-10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn degenerate_grid_has_no_lines() {
+        assert!(grid_lines(0.0, 1.0).is_empty());
+        assert!(grid_lines(10.0, 0.0).is_empty());
+        assert!(grid_lines(10.0, -1.0).is_empty());
+    }
+
+    #[test]
+    fn reference_grid_adds_one_debug_line_per_grid_line() {
+        let debug_lines = mock_gl::test_debug_lines().1;
+
+        let grid = ReferenceGrid::new(&debug_lines, 10.0, 2.5);
+        assert_eq!(debug_lines.active_count(), 18);
+        drop(grid);
+        assert_eq!(debug_lines.active_count(), 0);
+    }
+}
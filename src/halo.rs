@@ -0,0 +1,235 @@
+use crate::camera::TargetCamera;
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+
+/// Halo radius in multiples of the body radius, when seen from nearby.
+const HALO_RADIUS_FACTOR: f32 = 4.0;
+
+/// Smallest angular radius of the halo in radians, so the body stays visible from far away.
+const MIN_ANGULAR_RADIUS: f32 = 0.02;
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    corner: data::f32_f32,
+}
+
+/// Halo radius in world units, for a body of `radius` seen from `distance`.
+///
+/// The halo is a few times the body radius, but never smaller than `MIN_ANGULAR_RADIUS`
+/// as seen from the camera.
+pub fn halo_radius(radius: f32, distance: f32) -> f32 {
+    let near_radius = radius * HALO_RADIUS_FACTOR;
+    let far_radius = distance.max(0.0) * MIN_ANGULAR_RADIUS.tan();
+
+    near_radius.max(far_radius)
+}
+
+/// Camera-facing glow quad, blended additively.
+pub struct Halo {
+    program: render_gl::Program,
+    program_view_projection_location: i32,
+    center_location: i32,
+    right_location: i32,
+    up_location: i32,
+    radius_location: i32,
+    color_location: i32,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Halo {
+    pub fn new(res: &Resources, gl: &gl::Gl) -> Result<Halo, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/halo")?;
+        let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
+        let center_location = program.get_uniform_location("Center")?;
+        let right_location = program.get_uniform_location("Right")?;
+        let up_location = program.get_uniform_location("Up")?;
+        let radius_location = program.get_uniform_location("Radius")?;
+        let color_location = program.get_uniform_location("HaloColor")?;
+
+        let vbo_data: Vec<Vertex> = [
+            (-1.0, -1.0),
+            (1.0, -1.0),
+            (1.0, 1.0),
+            (-1.0, -1.0),
+            (1.0, 1.0),
+            (-1.0, 1.0),
+        ]
+        .iter()
+        .map(|&corner| Vertex {
+            corner: corner.into(),
+        })
+        .collect();
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Halo {
+            program,
+            program_view_projection_location,
+            center_location,
+            right_location,
+            up_location,
+            radius_location,
+            color_location,
+            _vbo: vbo,
+            vao,
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (
+            program_view_projection_location,
+            center_location,
+            right_location,
+            up_location,
+            radius_location,
+            color_location,
+        ) = self.program.reload(res, |program| {
+            Ok((
+                program.get_uniform_location("ViewProjection")?,
+                program.get_uniform_location("Center")?,
+                program.get_uniform_location("Right")?,
+                program.get_uniform_location("Up")?,
+                program.get_uniform_location("Radius")?,
+                program.get_uniform_location("HaloColor")?,
+            ))
+        })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.center_location = center_location;
+        self.right_location = right_location;
+        self.up_location = up_location;
+        self.radius_location = radius_location;
+        self.color_location = color_location;
+        Ok(())
+    }
+
+    /// Render halo around a body, after opaque geometry. Blend and depth write state is restored.
+    pub fn render(
+        &self,
+        gl: &gl::Gl,
+        camera: &TargetCamera,
+        center: &na::Point3<f32>,
+        radius: f32,
+        color: &na::Vector3<f32>,
+    ) {
+        let halo_radius = halo_radius(radius, na::distance(center, &camera.project_pos()));
+
+        self.program.set_used();
+        self.program.set_uniform_matrix4fv(
+            self.program_view_projection_location,
+            &camera.get_vp_matrix(),
+        );
+        self.program
+            .set_uniform_3f(self.center_location, &center.coords);
+        self.program
+            .set_uniform_3f(self.right_location, &camera.right());
+        self.program.set_uniform_3f(self.up_location, &camera.up());
+        self.program
+            .set_uniform_1f(self.radius_location, halo_radius);
+        self.program.set_uniform_3f(self.color_location, color);
+        self.vao.bind();
+
+        unsafe {
+            let cull_face_enabled = gl.IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+            let mut blend_func: [gl::types::GLint; 4] = [0; 4];
+            let blend_params = [
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ];
+            for (value, &name) in blend_func.iter_mut().zip(blend_params.iter()) {
+                gl.GetIntegerv(name, value);
+            }
+            let mut depth_mask: gl::types::GLboolean = gl::TRUE;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+
+            gl.Disable(gl::CULL_FACE);
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::SRC_ALPHA, gl::ONE);
+            gl.DepthMask(gl::FALSE);
+
+            gl.DrawArrays(
+                gl::TRIANGLES, // mode
+                0,             // starting index in the enabled arrays
+                6,             // number of indices to be rendered
+            );
+
+            gl.DepthMask(depth_mask);
+            gl.BlendFuncSeparate(
+                blend_func[0] as gl::types::GLenum,
+                blend_func[1] as gl::types::GLenum,
+                blend_func[2] as gl::types::GLenum,
+                blend_func[3] as gl::types::GLenum,
+            );
+            if !blend_enabled {
+                gl.Disable(gl::BLEND);
+            }
+            if cull_face_enabled {
+                gl.Enable(gl::CULL_FACE);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn halo_is_a_few_radii_nearby_and_keeps_angular_size_far_away() {
+        assert!((halo_radius(1.0, 10.0) - HALO_RADIUS_FACTOR).abs() < 1e-5);
+
+        let far = halo_radius(1.0, 10_000.0);
+        assert!((far / 10_000.0 - MIN_ANGULAR_RADIUS.tan()).abs() < 1e-6);
+        assert!(halo_radius(1.0, 20_000.0) > far);
+    }
+
+    #[test]
+    fn render_restores_depth_mask_and_blend_func() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let halo = Halo::new(&res, &gl).unwrap();
+        let camera = TargetCamera::new(1.0, PI / 2.0, 0.01, 1000.0, 0.0, 2.0);
+
+        unsafe {
+            gl.DepthMask(gl::FALSE);
+            gl.BlendFuncSeparate(gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        halo.render(
+            &gl,
+            &camera,
+            &na::Point3::origin(),
+            1.0,
+            &na::Vector3::new(1.0, 1.0, 1.0),
+        );
+
+        mock_gl::with_state(|state| {
+            assert!(!state.depth_mask);
+            assert_eq!(
+                state.blend_func,
+                [gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA]
+            );
+            assert!(!state.enabled.contains(&gl::BLEND));
+            assert_eq!(state.draws, vec![6]);
+        });
+    }
+}
@@ -0,0 +1,286 @@
+use crate::debug;
+use crate::labels::Labels;
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+
+/// Height of HUD text glyphs, in pixels.
+pub const GLYPH_HEIGHT: f32 = 16.0;
+
+/// Distance of HUD elements from the window edges, in pixels.
+pub const MARGIN: f32 = 8.0;
+
+/// Background color of text panels.
+const PANEL_COLOR: (f32, f32, f32, f32) = (0.0, 0.0, 0.0, 0.5);
+
+/// Length of each reticle arm from the center and thickness of the arms, in window points.
+const RETICLE_ARM: f32 = 8.0;
+const RETICLE_THICKNESS: f32 = 1.0;
+
+const RETICLE_COLOR: (f32, f32, f32, f32) = (1.0, 1.0, 1.0, 0.8);
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32,
+    #[location = "1"]
+    color: data::f32_f32_f32_f32,
+}
+
+/// Orthographic projection for a `viewport` sized in pixels, with the origin at the top left
+/// corner and Y going down like window coordinates.
+pub fn pixel_projection(viewport: (i32, i32)) -> na::Matrix4<f32> {
+    na::Matrix4::new_orthographic(
+        0.0,
+        viewport.0.max(1) as f32,
+        viewport.1.max(1) as f32,
+        0.0,
+        -1.0,
+        1.0,
+    )
+}
+
+/// Center of a `viewport` sized in pixels.
+pub fn viewport_center(viewport: (i32, i32)) -> na::Point2<f32> {
+    na::Point2::new(viewport.0 as f32 / 2.0, viewport.1 as f32 / 2.0)
+}
+
+/// Horizontal and vertical bar of a reticle centered in the `viewport`, each as its top left
+/// corner and size in pixels.
+///
+/// `scale` is the number of drawable pixels per window point, so the reticle keeps its size
+/// on high-DPI displays.
+pub fn reticle_bars(viewport: (i32, i32), scale: f32) -> [(na::Point2<f32>, na::Vector2<f32>); 2] {
+    let center = viewport_center(viewport);
+    let arm = RETICLE_ARM * scale;
+    let thickness = (RETICLE_THICKNESS * scale).max(1.0);
+    let bar = |half_size: na::Vector2<f32>| (center - half_size, half_size * 2.0);
+
+    [
+        bar(na::Vector2::new(arm, thickness / 2.0)),
+        bar(na::Vector2::new(thickness / 2.0, arm)),
+    ]
+}
+
+/// Size in pixels of the text in a panel of `lines`, not including its padding.
+pub fn panel_size(labels: &Labels, lines: &[String]) -> na::Vector2<f32> {
+    let width = lines
+        .iter()
+        .map(|line| labels.screen_text_width(line, GLYPH_HEIGHT))
+        .fold(0.0, f32::max);
+
+    na::Vector2::new(width, lines.len() as f32 * GLYPH_HEIGHT)
+}
+
+/// Top left corner of a tooltip of `size` pixels next to the `cursor`.
+///
+/// The tooltip goes below and to the right of the cursor, and flips to the other side where
+/// it would leave the viewport.
+pub fn tooltip_position(
+    cursor: na::Point2<f32>,
+    size: na::Vector2<f32>,
+    viewport: (i32, i32),
+) -> na::Point2<f32> {
+    let offset = GLYPH_HEIGHT;
+    let place = |cursor: f32, size: f32, viewport: i32| {
+        if cursor + offset + size + MARGIN <= viewport as f32 {
+            cursor + offset
+        } else {
+            (cursor - offset - size).max(MARGIN)
+        }
+    };
+
+    na::Point2::new(
+        place(cursor.x, size.x, viewport.0),
+        place(cursor.y, size.y, viewport.1),
+    )
+}
+
+/// Screen space layer drawn over the scene, in pixel coordinates.
+///
+/// Rectangles and text are queued during the frame and drawn by `render`, rectangles first.
+pub struct Hud {
+    program: render_gl::Program,
+    program_projection_location: i32,
+    vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+    rects: Vec<Vertex>,
+    texts: Vec<(na::Point2<f32>, String)>,
+}
+
+impl Hud {
+    pub fn new(res: &Resources, gl: &gl::Gl) -> Result<Hud, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/hud")?;
+        let program_projection_location = program.get_uniform_location("Projection")?;
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Hud {
+            program,
+            program_projection_location,
+            vbo,
+            vao,
+            rects: Vec::new(),
+            texts: Vec::new(),
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        self.program_projection_location = self
+            .program
+            .reload(res, |program| program.get_uniform_location("Projection"))?;
+        Ok(())
+    }
+
+    /// Queue a filled rectangle with top left corner at `min`, blended with `color` alpha.
+    pub fn draw_rect(
+        &mut self,
+        min: na::Point2<f32>,
+        size: na::Vector2<f32>,
+        color: na::Vector4<f32>,
+    ) {
+        let max = min + size;
+        let corner = |x: f32, y: f32| Vertex {
+            pos: (x, y).into(),
+            color: (color.x, color.y, color.z, color.w).into(),
+        };
+
+        self.rects.extend_from_slice(&[
+            corner(min.x, min.y),
+            corner(max.x, min.y),
+            corner(max.x, max.y),
+            corner(min.x, min.y),
+            corner(max.x, max.y),
+            corner(min.x, max.y),
+        ]);
+    }
+
+    /// Queue a line of text with its top left corner at `position`, `GLYPH_HEIGHT` pixels tall.
+    pub fn draw_text(&mut self, position: na::Point2<f32>, text: &str) {
+        self.texts.push((position, text.into()));
+    }
+
+    /// Queue `lines` of text over a translucent background, with the text's top left corner
+    /// at `top_left` and the background padded by half the margin.
+    pub fn draw_panel(&mut self, labels: &Labels, top_left: na::Point2<f32>, lines: &[String]) {
+        let (r, g, b, a) = PANEL_COLOR;
+        self.draw_rect(
+            top_left - na::Vector2::repeat(MARGIN / 2.0),
+            panel_size(labels, lines) + na::Vector2::repeat(MARGIN),
+            na::Vector4::new(r, g, b, a),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(top_left + na::Vector2::y() * GLYPH_HEIGHT * i as f32, line);
+        }
+    }
+
+    /// Queue a crosshair at the center of the `viewport`, `scale` pixels per window point.
+    pub fn draw_reticle(&mut self, viewport: (i32, i32), scale: f32) {
+        let (r, g, b, a) = RETICLE_COLOR;
+        for &(min, size) in reticle_bars(viewport, scale).iter() {
+            self.draw_rect(min, size, na::Vector4::new(r, g, b, a));
+        }
+    }
+
+    /// Draw everything queued this frame over the scene, then clear the queue.
+    ///
+    /// Should be done last; depth test and blend state are restored.
+    pub fn render(&mut self, gl: &gl::Gl, labels: &Labels, viewport: (i32, i32)) {
+        let projection = pixel_projection(viewport);
+
+        if !self.rects.is_empty() {
+            self.vbo.bind();
+            self.vbo.dynamic_draw_data(&self.rects);
+            self.vbo.unbind();
+
+            self.program.set_used();
+            self.program
+                .set_uniform_matrix4fv(self.program_projection_location, &projection);
+            self.vao.bind();
+
+            unsafe {
+                let depth_test_enabled = gl.IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+                let cull_face_enabled = gl.IsEnabled(gl::CULL_FACE) == gl::TRUE;
+                let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+                gl.Disable(gl::DEPTH_TEST);
+                gl.Disable(gl::CULL_FACE);
+                gl.Enable(gl::BLEND);
+                gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+                gl.DrawArrays(
+                    gl::TRIANGLES,                          // mode
+                    0,                                      // starting index in the enabled arrays
+                    self.rects.len() as gl::types::GLsizei, // number of indices to be rendered
+                );
+
+                if !blend_enabled {
+                    gl.Disable(gl::BLEND);
+                }
+                if cull_face_enabled {
+                    gl.Enable(gl::CULL_FACE);
+                }
+                if depth_test_enabled {
+                    gl.Enable(gl::DEPTH_TEST);
+                }
+            }
+        }
+
+        labels.render_screen(gl, &self.texts, GLYPH_HEIGHT, &projection);
+        debug::check_gl_error(gl, "HUD");
+
+        self.rects.clear();
+        self.texts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_ndc(projection: &na::Matrix4<f32>, x: f32, y: f32) -> na::Point3<f32> {
+        projection.transform_point(&na::Point3::new(x, y, 0.0))
+    }
+
+    #[test]
+    fn pixel_corners_map_to_ndc_corners_with_y_down() {
+        let projection = pixel_projection((800, 600));
+
+        let top_left = to_ndc(&projection, 0.0, 0.0);
+        let bottom_right = to_ndc(&projection, 800.0, 600.0);
+        let center = to_ndc(&projection, 400.0, 300.0);
+        assert!((top_left - na::Point3::new(-1.0, 1.0, 0.0)).norm() < 1e-6);
+        assert!((bottom_right - na::Point3::new(1.0, -1.0, 0.0)).norm() < 1e-6);
+        assert!(center.coords.norm() < 1e-6);
+    }
+
+    #[test]
+    fn empty_viewport_still_gives_a_finite_projection() {
+        let projection = pixel_projection((0, 0));
+        assert!(projection.iter().all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn reticle_is_centered_at_ndc_origin() {
+        let viewport = (1024, 768);
+        let projection = pixel_projection(viewport);
+
+        for &scale in &[1.0, 2.0] {
+            for &(top_left, size) in &reticle_bars(viewport, scale) {
+                let middle = top_left + size / 2.0;
+                assert!(to_ndc(&projection, middle.x, middle.y).coords.norm() < 1e-6);
+            }
+        }
+        let [horizontal, vertical] = reticle_bars(viewport, 2.0);
+        assert_eq!(horizontal.1.x, RETICLE_ARM * 4.0);
+        assert_eq!(vertical.1.y, RETICLE_ARM * 4.0);
+    }
+}
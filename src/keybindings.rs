@@ -0,0 +1,229 @@
+use crate::resources::{self, Resources};
+use sdl2::keyboard::Scancode;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to load resource {}", name)]
+    ResourceLoad {
+        name: String,
+        #[cause]
+        inner: resources::Error,
+    },
+    #[fail(display = "Failed to parse {}", name)]
+    Parse {
+        name: String,
+        #[cause]
+        inner: ron::de::Error,
+    },
+    #[fail(display = "Unknown key {} for {:?} in {}", key, action, name)]
+    UnknownKey {
+        name: String,
+        action: Action,
+        key: String,
+    },
+    #[fail(
+        display = "Key {} for {:?} in {} is already used by the application",
+        key, action, name
+    )]
+    ReservedKey {
+        name: String,
+        action: Action,
+        key: String,
+    },
+}
+
+/// Camera control that can be bound to keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    Faster,
+    Left,
+    Forward,
+    Backward,
+    Right,
+    Up,
+    Down,
+    RotateLeft,
+    RotateRight,
+    RotateUp,
+    RotateDown,
+    RollLeft,
+    RollRight,
+    Reset,
+    InvertY,
+    NorthUp,
+    Orthographic,
+    ToggleMode,
+}
+
+/// Bindings used for actions not mentioned in the bindings file.
+const DEFAULT_BINDINGS: [(Action, &[Scancode]); 18] = [
+    (Action::Faster, &[Scancode::LShift, Scancode::RShift]),
+    (Action::Left, &[Scancode::A]),
+    (Action::Forward, &[Scancode::W]),
+    (Action::Backward, &[Scancode::S]),
+    (Action::Right, &[Scancode::D]),
+    (Action::Up, &[Scancode::Space]),
+    (Action::Down, &[Scancode::LCtrl]),
+    (Action::RotateLeft, &[Scancode::Left]),
+    (Action::RotateRight, &[Scancode::Right]),
+    (Action::RotateUp, &[Scancode::Up]),
+    (Action::RotateDown, &[Scancode::Down]),
+    (Action::RollLeft, &[Scancode::Q]),
+    (Action::RollRight, &[Scancode::E]),
+    (Action::Reset, &[Scancode::R]),
+    (Action::InvertY, &[Scancode::I]),
+    (Action::NorthUp, &[Scancode::N]),
+    (Action::Orthographic, &[Scancode::O]),
+    (Action::ToggleMode, &[Scancode::F]),
+];
+
+/// Keys handled by the application itself, which can not be bound to camera actions.
+pub const RESERVED_KEYS: [Scancode; 32] = [
+    Scancode::H,
+    Scancode::G,
+    Scancode::K,
+    Scancode::Z,
+    Scancode::L,
+    Scancode::T,
+    Scancode::X,
+    Scancode::V,
+    Scancode::Y,
+    Scancode::J,
+    Scancode::C,
+    Scancode::M,
+    Scancode::P,
+    Scancode::F2,
+    Scancode::F5,
+    Scancode::F12,
+    Scancode::Tab,
+    Scancode::Backspace,
+    Scancode::Delete,
+    Scancode::Comma,
+    Scancode::Period,
+    Scancode::LeftBracket,
+    Scancode::RightBracket,
+    Scancode::Num1,
+    Scancode::Num2,
+    Scancode::Num3,
+    Scancode::Num4,
+    Scancode::Num5,
+    Scancode::Num6,
+    Scancode::Num7,
+    Scancode::Num8,
+    Scancode::Num9,
+];
+
+/// Mapping from keys to camera actions.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    actions: HashMap<Scancode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        let mut bindings = KeyBindings {
+            actions: HashMap::new(),
+        };
+        for &(action, scancodes) in DEFAULT_BINDINGS.iter() {
+            bindings.bind(action, scancodes);
+        }
+        bindings
+    }
+}
+
+impl KeyBindings {
+    /// Action bound to the key, if any.
+    pub fn action(&self, scancode: Scancode) -> Option<Action> {
+        self.actions.get(&scancode).cloned()
+    }
+
+    /// Replace keys bound to `action`. A key bound to another action is taken over.
+    pub fn bind(&mut self, action: Action, scancodes: &[Scancode]) {
+        self.actions.retain(|_, bound| *bound != action);
+        for &scancode in scancodes {
+            self.actions.insert(scancode, action);
+        }
+    }
+}
+
+/// Load key bindings from RON resource, on top of the defaults.
+pub fn load(res: &Resources, name: &str) -> Result<KeyBindings, Error> {
+    let text = res.load_string(name).map_err(|e| Error::ResourceLoad {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    parse(name, &text)
+}
+
+/// Parse map from action to SDL key names (such as `"Left Shift"`), using `name` in errors.
+///
+/// Keys in `RESERVED_KEYS` are rejected, so a binding never competes with an application key.
+pub fn parse(name: &str, text: &str) -> Result<KeyBindings, Error> {
+    let keys: HashMap<Action, Vec<String>> = ron::de::from_str(text).map_err(|e| Error::Parse {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    let mut bindings = KeyBindings::default();
+    for (action, keys) in keys {
+        let scancodes = keys
+            .into_iter()
+            .map(|key| match Scancode::from_name(&key) {
+                None => Err(Error::UnknownKey {
+                    name: name.into(),
+                    action,
+                    key,
+                }),
+                Some(scancode) if RESERVED_KEYS.contains(&scancode) => Err(Error::ReservedKey {
+                    name: name.into(),
+                    action,
+                    key,
+                }),
+                Some(scancode) => Ok(scancode),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        bindings.bind(action, &scancodes);
+    }
+
+    Ok(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_replace_defaults_of_their_action() {
+        let bindings = parse("test.ron", r#"{ Forward: ["U"] }"#).unwrap();
+        assert_eq!(bindings.action(Scancode::U), Some(Action::Forward));
+        assert_eq!(bindings.action(Scancode::W), None);
+        assert_eq!(bindings.action(Scancode::S), Some(Action::Backward));
+    }
+
+    #[test]
+    fn application_keys_can_not_be_bound() {
+        for key in &["H", "Tab", "5"] {
+            match parse("test.ron", &format!(r#"{{ Forward: ["{}"] }}"#, key)) {
+                Err(Error::ReservedKey {
+                    name,
+                    action,
+                    key: reserved,
+                }) => {
+                    assert_eq!(name, "test.ron");
+                    assert_eq!(action, Action::Forward);
+                    assert_eq!(reserved, *key);
+                }
+                other => panic!("unexpected result {:?}", other.map(|_| ())),
+            }
+        }
+
+        // defaults must not collide with application keys either
+        let defaults = KeyBindings::default();
+        assert!(RESERVED_KEYS
+            .iter()
+            .all(|&scancode| defaults.action(scancode).is_none()));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::camera::TargetCamera;
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+
+/// Label height relative to its distance from the camera, so labels keep their screen size.
+const LABEL_SCALE: f32 = 0.03;
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32_f32,
+    #[location = "1"]
+    uv: data::f32_f32,
+}
+
+/// Monospace glyph grid in a font texture, covering printable ASCII characters.
+///
+/// Glyphs are laid out row by row from the top left, starting with the space character.
+#[derive(Copy, Clone, Debug)]
+pub struct GlyphAtlas {
+    pub columns: u32,
+    pub rows: u32,
+    /// Glyph width divided by height.
+    pub glyph_aspect: f32,
+}
+
+impl GlyphAtlas {
+    const FIRST_CHAR: u32 = 32;
+
+    /// Texture coordinates of glyph cell as `(min, max)`, with V going down from the top row.
+    ///
+    /// Characters outside the atlas are shown as `?`.
+    pub fn uv_rect(&self, ch: char) -> (na::Vector2<f32>, na::Vector2<f32>) {
+        let glyph_count = self.columns * self.rows;
+        let code = ch as u32;
+        let index = if code >= Self::FIRST_CHAR && code < Self::FIRST_CHAR + glyph_count {
+            code - Self::FIRST_CHAR
+        } else {
+            '?' as u32 - Self::FIRST_CHAR
+        };
+
+        let column = (index % self.columns) as f32;
+        let row = (index / self.columns) as f32;
+        let cell = na::Vector2::new(1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let min = na::Vector2::new(column * cell.x, row * cell.y);
+
+        (min, min + cell)
+    }
+}
+
+/// Text to show next to a point in the scene.
+pub struct Label<'a> {
+    pub position: na::Point3<f32>,
+    pub text: &'a str,
+}
+
+/// Renders text labels as camera-facing quads, using glyphs from a font texture.
+pub struct Labels {
+    program: render_gl::Program,
+    program_view_location: i32,
+    program_projection_location: i32,
+    texture: render_gl::Texture,
+    atlas: GlyphAtlas,
+    vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Labels {
+    /// Load font texture containing glyphs laid out as described by `atlas`.
+    pub fn new(
+        res: &Resources,
+        gl: &gl::Gl,
+        font_name: &str,
+        atlas: GlyphAtlas,
+    ) -> Result<Labels, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/label")?;
+        let program_view_location = program.get_uniform_location("View")?;
+        let program_projection_location = program.get_uniform_location("Projection")?;
+
+        let texture = render_gl::Texture::from_srgb_image(gl, &res.load_image(font_name)?)?;
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Labels {
+            program,
+            program_view_location,
+            program_projection_location,
+            texture,
+            atlas,
+            vbo,
+            vao,
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (program_view_location, program_projection_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("View")?,
+                    program.get_uniform_location("Projection")?,
+                ))
+            })?;
+        self.program_view_location = program_view_location;
+        self.program_projection_location = program_projection_location;
+        Ok(())
+    }
+
+    /// Render labels facing the camera, after opaque geometry.
+    ///
+    /// Labels start at their position and extend to the camera right.
+    pub fn render(&self, gl: &gl::Gl, labels: &[Label], camera: &TargetCamera) {
+        let camera_pos = camera.project_pos();
+        let mut vertices = Vec::new();
+
+        for label in labels {
+            let height = na::distance(&label.position, &camera_pos) * LABEL_SCALE;
+            self.push_text(
+                &mut vertices,
+                &label.position,
+                &(camera.right() * height * self.atlas.glyph_aspect),
+                &(camera.up() * height),
+                label.text,
+            );
+        }
+
+        self.draw(
+            gl,
+            &vertices,
+            &camera.view_matrix(),
+            &camera.projection_matrix(),
+            true,
+        );
+    }
+
+    /// Width in pixels of `text` drawn with `render_screen`.
+    pub fn screen_text_width(&self, text: &str, glyph_height: f32) -> f32 {
+        text.chars().count() as f32 * glyph_height * self.atlas.glyph_aspect
+    }
+
+    /// Render text in screen space over everything else, for the HUD.
+    ///
+    /// Each text starts at its top left corner in pixels, with glyphs `glyph_height` pixels
+    /// tall. The `projection` maps pixels with Y going down, see `hud::pixel_projection`.
+    pub fn render_screen(
+        &self,
+        gl: &gl::Gl,
+        texts: &[(na::Point2<f32>, String)],
+        glyph_height: f32,
+        projection: &na::Matrix4<f32>,
+    ) {
+        let advance = na::Vector3::x() * glyph_height * self.atlas.glyph_aspect;
+        let up = -na::Vector3::y() * glyph_height;
+        let mut vertices = Vec::new();
+
+        for (position, text) in texts {
+            let origin = na::Point3::new(position.x, position.y + glyph_height, 0.0);
+            self.push_text(&mut vertices, &origin, &advance, &up, text);
+        }
+
+        self.draw(gl, &vertices, &na::Matrix4::identity(), projection, false);
+    }
+
+    /// Add glyph quads for `text` starting at `origin`, moving by `advance` for each character.
+    fn push_text(
+        &self,
+        vertices: &mut Vec<Vertex>,
+        origin: &na::Point3<f32>,
+        advance: &na::Vector3<f32>,
+        up: &na::Vector3<f32>,
+        text: &str,
+    ) {
+        for (i, ch) in text.chars().enumerate() {
+            let (uv_min, uv_max) = self.atlas.uv_rect(ch);
+            let origin = origin + advance * i as f32;
+
+            let corner = |x: f32, y: f32| Vertex {
+                pos: render_p3(origin + advance * x + up * y),
+                // texture rows go down, quad goes up
+                uv: (
+                    uv_min.x + (uv_max.x - uv_min.x) * x,
+                    uv_max.y - (uv_max.y - uv_min.y) * y,
+                )
+                    .into(),
+            };
+
+            vertices.extend_from_slice(&[
+                corner(0.0, 0.0),
+                corner(1.0, 0.0),
+                corner(1.0, 1.0),
+                corner(0.0, 0.0),
+                corner(1.0, 1.0),
+                corner(0.0, 1.0),
+            ]);
+        }
+    }
+
+    /// Draw glyph quads blended over the frame. Without `depth_test` they are drawn over
+    /// everything. Changed state is restored.
+    fn draw(
+        &self,
+        gl: &gl::Gl,
+        vertices: &[Vertex],
+        view: &na::Matrix4<f32>,
+        projection: &na::Matrix4<f32>,
+        depth_test: bool,
+    ) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        self.vbo.bind();
+        self.vbo.dynamic_draw_data(vertices);
+        self.vbo.unbind();
+
+        self.program.set_used();
+        self.program
+            .set_uniform_matrix4fv(self.program_view_location, view);
+        self.program
+            .set_uniform_matrix4fv(self.program_projection_location, projection);
+        self.vao.bind();
+
+        unsafe {
+            let cull_face_enabled = gl.IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+            let depth_test_enabled = gl.IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            if !depth_test {
+                gl.Disable(gl::DEPTH_TEST);
+            }
+            gl.Disable(gl::CULL_FACE);
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl.DepthMask(gl::FALSE);
+
+            self.texture.bind_at(0);
+            gl.DrawArrays(
+                gl::TRIANGLES,         // mode
+                0,                     // starting index in the enabled arrays
+                vertices.len() as i32, // number of indices to be rendered
+            );
+            self.texture.unbind_at(0);
+
+            gl.DepthMask(gl::TRUE);
+            if !blend_enabled {
+                gl.Disable(gl::BLEND);
+            }
+            if cull_face_enabled {
+                gl.Enable(gl::CULL_FACE);
+            }
+            if depth_test_enabled {
+                gl.Enable(gl::DEPTH_TEST);
+            }
+        }
+        crate::debug::check_gl_error(gl, "labels");
+    }
+}
+
+fn render_p3(v: na::Point3<f32>) -> data::f32_f32_f32 {
+    data::f32_f32_f32::new(v.x, v.y, v.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATLAS: GlyphAtlas = GlyphAtlas {
+        columns: 16,
+        rows: 6,
+        glyph_aspect: 0.5,
+    };
+
+    fn assert_rect(ch: char, min: (f32, f32), max: (f32, f32)) {
+        let (actual_min, actual_max) = ATLAS.uv_rect(ch);
+        assert!(
+            (actual_min - na::Vector2::new(min.0, min.1)).norm() < 1e-6,
+            "{}",
+            ch
+        );
+        assert!(
+            (actual_max - na::Vector2::new(max.0, max.1)).norm() < 1e-6,
+            "{}",
+            ch
+        );
+    }
+
+    #[test]
+    fn atlas_uv_of_ascii_characters() {
+        let (w, h) = (1.0 / 16.0, 1.0 / 6.0);
+        assert_rect(' ', (0.0, 0.0), (w, h));
+        // 'A' is code 65, index 33: row 2, column 1
+        assert_rect('A', (w, 2.0 * h), (2.0 * w, 3.0 * h));
+        assert_rect('~', (14.0 * w, 5.0 * h), (15.0 * w, 6.0 * h));
+    }
+
+    #[test]
+    fn characters_outside_atlas_use_question_mark() {
+        assert_eq!(ATLAS.uv_rect('é'), ATLAS.uv_rect('?'));
+        assert_eq!(ATLAS.uv_rect('\n'), ATLAS.uv_rect('?'));
+    }
+}
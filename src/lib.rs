@@ -0,0 +1,79 @@
+/*  ____  _____ ____ _____
+ * |  _ \|___ /|  _ \___ /
+ * | |_) | |_ \| | | ||_ \
+ * |  _ < ___) | |_| |__) |
+ * |_| \_\____/|____/____/
+ *
+ * Solar system 3D with astrology transit
+ *
+ * MIT License
+ *
+ * Copyright (c) 2019 Stéphane Bressani
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to
+ * deal in the Software without restriction, including without limitation the
+ * rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+ * sell copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in
+ * all copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ * FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+ * IN THE SOFTWARE.
+ */
+extern crate gl;
+extern crate sdl2;
+#[macro_use]
+extern crate failure;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate render_gl_derive;
+
+mod app;
+pub mod aspects;
+mod asteroids;
+mod background;
+pub mod bench;
+pub mod calendar;
+pub mod camera;
+pub mod cli;
+pub mod comet;
+mod cube;
+pub mod debug;
+pub mod ephemeris;
+pub mod frame_limit;
+mod gl_context;
+pub mod grid;
+pub mod halo;
+mod hud;
+pub mod keybindings;
+mod labels;
+pub mod orbit;
+mod planets;
+pub mod render_gl;
+pub mod resources;
+pub mod retrograde;
+mod rings;
+mod screenshot;
+pub mod settings;
+pub mod sim_clock;
+pub mod sky_circles;
+mod skybox;
+pub mod sphere;
+pub mod stars;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod texture_loader;
+pub mod trail;
+mod window_icon;
+mod zodiac;
+
+pub use crate::app::{App, Scene};
@@ -0,0 +1,417 @@
+use nalgebra as na;
+use std::f64::consts::PI;
+
+const KEPLER_TOLERANCE: f64 = 1e-12;
+const KEPLER_MAX_ITERATIONS: usize = 50;
+
+/// Scene distance at which logarithmic compression starts to flatten, one AU in scene units.
+const LOG_DISTANCE_KNEE: f64 = 10.0;
+
+/// Multiplier of logarithmic distances, so inner planets keep about their linear distance.
+const LOG_DISTANCE_SCALE: f64 = 20.0;
+
+/// Integration steps per orbit path segment used to place adaptive samples.
+const PATH_INTEGRATION_STEPS: usize = 16;
+
+/// Origin of the coordinates bodies are shown in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReferenceFrame {
+    /// Sun at the origin.
+    Heliocentric,
+    /// Earth at the origin, as in astrology.
+    Geocentric,
+}
+
+impl ReferenceFrame {
+    pub fn toggle(self) -> ReferenceFrame {
+        match self {
+            ReferenceFrame::Heliocentric => ReferenceFrame::Geocentric,
+            ReferenceFrame::Geocentric => ReferenceFrame::Heliocentric,
+        }
+    }
+}
+
+/// How distances from the scene origin are shown.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DistanceScale {
+    /// True distances.
+    Linear,
+    /// Distances compressed with `compress_distance`, so outer planets stay near.
+    Logarithmic,
+}
+
+impl DistanceScale {
+    pub fn toggle(self) -> DistanceScale {
+        match self {
+            DistanceScale::Linear => DistanceScale::Logarithmic,
+            DistanceScale::Logarithmic => DistanceScale::Linear,
+        }
+    }
+}
+
+/// Logarithmically compressed distance, growing monotonically from 0 for distance 0.
+pub fn compress_distance(distance: f64) -> f64 {
+    LOG_DISTANCE_SCALE * (1.0 + distance.max(0.0) / LOG_DISTANCE_KNEE).ln()
+}
+
+/// Mapping of heliocentric positions into the scene.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SceneTransform {
+    /// Heliocentric position shown at the scene origin.
+    pub origin: na::Point3<f64>,
+    pub distance_scale: DistanceScale,
+}
+
+impl SceneTransform {
+    /// Scene position of a heliocentric position. Directions from the origin are kept,
+    /// only distances are compressed.
+    pub fn apply(&self, heliocentric: &na::Point3<f64>) -> na::Point3<f64> {
+        let relative = relative_to(heliocentric, &self.origin);
+
+        match self.distance_scale {
+            DistanceScale::Linear => relative,
+            DistanceScale::Logarithmic => {
+                let distance = relative.coords.norm();
+                if distance > 0.0 {
+                    relative * (compress_distance(distance) / distance)
+                } else {
+                    relative
+                }
+            }
+        }
+    }
+
+    /// Scene position of a body on `orbit` at simulation time.
+    ///
+    /// Moons are placed at their drawn offset from the parent's scene position, which keeps
+    /// their orbit the same ellipse around the parent whatever the distance scale.
+    pub fn place(&self, orbit: &KeplerOrbit, sim_time_days: f64) -> na::Point3<f64> {
+        match orbit.parent {
+            Some(ref parent) => {
+                self.place(parent, sim_time_days) + orbit.display_offset_at(sim_time_days)
+            }
+            None => self.apply(&orbit.position_at(sim_time_days)),
+        }
+    }
+}
+
+/// Heliocentric position as seen from `origin`, for example Earth's heliocentric position.
+pub fn relative_to(heliocentric: &na::Point3<f64>, origin: &na::Point3<f64>) -> na::Point3<f64> {
+    na::Point3::from(heliocentric - origin)
+}
+
+/// Keplerian orbital elements of a body around the Sun, or around a parent body.
+///
+/// Angles are in radians, `semi_major` in scene units and `period` in days.
+#[derive(Clone, Debug)]
+pub struct KeplerOrbit {
+    pub semi_major: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub lon_ascending: f64,
+    pub arg_periapsis: f64,
+    /// Mean anomaly at simulation time zero.
+    pub mean_anomaly_epoch: f64,
+    pub period: f64,
+    /// Orbit of the body this one goes around, none for the Sun.
+    pub parent: Option<Box<KeplerOrbit>>,
+    /// Factor the distance to the parent is drawn with, so that moons stay outside of their
+    /// exaggerated parent; positions keep the true distance.
+    pub display_scale: f64,
+}
+
+impl KeplerOrbit {
+    /// Heliocentric position at specified simulation time, with ecliptic on the XY plane.
+    ///
+    /// Bodies with a parent are placed relative to the parent's position at the same time.
+    pub fn position_at(&self, sim_time_days: f64) -> na::Point3<f64> {
+        self.parent_position_at(sim_time_days) + self.local_position_at(sim_time_days).coords
+    }
+
+    /// Heliocentric position of the parent at simulation time, the origin without parent.
+    pub fn parent_position_at(&self, sim_time_days: f64) -> na::Point3<f64> {
+        self.parent
+            .as_ref()
+            .map_or_else(na::Point3::origin, |parent| {
+                parent.position_at(sim_time_days)
+            })
+    }
+
+    /// Position relative to the parent at simulation time, from this orbit's elements only.
+    pub fn local_position_at(&self, sim_time_days: f64) -> na::Point3<f64> {
+        let mean_motion = 2.0 * PI / self.period;
+        let mean_anomaly =
+            (self.mean_anomaly_epoch + mean_motion * sim_time_days).rem_euclid(2.0 * PI);
+        let eccentric_anomaly = solve_kepler(mean_anomaly, self.eccentricity);
+
+        // position in the orbital plane, with periapsis on the X axis
+        let x = self.semi_major * (eccentric_anomaly.cos() - self.eccentricity);
+        let y = self.semi_major
+            * (1.0 - self.eccentricity * self.eccentricity).sqrt()
+            * eccentric_anomaly.sin();
+
+        let rotation = na::Rotation3::from_axis_angle(&na::Vector3::z_axis(), self.lon_ascending)
+            * na::Rotation3::from_axis_angle(&na::Vector3::x_axis(), self.inclination)
+            * na::Rotation3::from_axis_angle(&na::Vector3::z_axis(), self.arg_periapsis);
+
+        rotation * na::Point3::new(x, y, 0.0)
+    }
+
+    /// Offset from the parent at simulation time as drawn, scaled by `display_scale`.
+    pub fn display_offset_at(&self, sim_time_days: f64) -> na::Vector3<f64> {
+        self.local_position_at(sim_time_days).coords * self.display_scale
+    }
+
+    /// Segments of a path with `base_segments` for a circle, more for eccentric orbits, whose
+    /// ends of the major axis are sharper.
+    pub fn path_segments(&self, base_segments: usize) -> usize {
+        (base_segments as f64 * (1.0 + 2.0 * self.eccentricity)).ceil() as usize
+    }
+
+    /// Eccentric anomalies of `segments` path points starting at periapsis, spaced so every
+    /// segment deviates about equally from the ellipse.
+    ///
+    /// The chord of a segment strays from the curve by its curvature times its squared length,
+    /// so segments are shortest near periapsis and apoapsis, where the ellipse bends most. A
+    /// circle gets even steps.
+    pub fn path_sample_anomalies(&self, segments: usize) -> Vec<f64> {
+        // ds/dE is proportional to sqrt(sin^2 E + (b/a)^2 cos^2 E), and equal error needs
+        // sample density proportional to its inverse square root
+        let axis_ratio = (1.0 - self.eccentricity * self.eccentricity).sqrt();
+        let density = |anomaly: f64| {
+            (anomaly.sin().powi(2) + (axis_ratio * anomaly.cos()).powi(2))
+                .sqrt()
+                .powf(-0.5)
+        };
+
+        let steps = segments.max(1) * PATH_INTEGRATION_STEPS;
+        let step = 2.0 * PI / steps as f64;
+        let mut cumulative = Vec::with_capacity(steps + 1);
+        cumulative.push(0.0);
+        for i in 0..steps {
+            let (a, b) = (i as f64 * step, (i + 1) as f64 * step);
+            let area = (density(a) + density(b)) * 0.5 * step;
+            cumulative.push(cumulative[i] + area);
+        }
+        let total = cumulative[steps];
+
+        let mut index = 0;
+        (0..segments)
+            .map(|i| {
+                let target = total * i as f64 / segments as f64;
+                while cumulative[index + 1] < target {
+                    index += 1;
+                }
+                let span = cumulative[index + 1] - cumulative[index];
+                let fraction = if span > 0.0 {
+                    (target - cumulative[index]) / span
+                } else {
+                    0.0
+                };
+                (index as f64 + fraction) * step
+            })
+            .collect()
+    }
+
+    /// Simulation times in days, within one period, at the `segments` points of
+    /// `path_sample_anomalies`.
+    ///
+    /// Even time steps would leave few points near periapsis, where an eccentric orbit is
+    /// fastest and most curved.
+    pub fn path_sample_times(&self, segments: usize) -> Vec<f64> {
+        let mean_motion = 2.0 * PI / self.period;
+
+        self.path_sample_anomalies(segments)
+            .into_iter()
+            .map(|eccentric_anomaly| {
+                let mean_anomaly = eccentric_anomaly - self.eccentricity * eccentric_anomaly.sin();
+                (mean_anomaly - self.mean_anomaly_epoch).rem_euclid(2.0 * PI) / mean_motion
+            })
+            .collect()
+    }
+
+    /// Heliocentric points of the closed orbit path, around the parent as placed at
+    /// `sim_time_days`, with the Sun or the parent at a focus.
+    ///
+    /// Points come from the same elements as `position_at`, so the line goes exactly through
+    /// the body positions.
+    pub fn sample_path(&self, segments: usize, sim_time_days: f64) -> Vec<na::Point3<f64>> {
+        let parent_position = self.parent_position_at(sim_time_days);
+
+        self.path_sample_times(segments)
+            .into_iter()
+            .map(|days| parent_position + self.local_position_at(days).coords)
+            .collect()
+    }
+
+    /// Points of the closed orbit path as drawn around the parent, relative to the parent;
+    /// see `display_offset_at`.
+    pub fn sample_local_path(&self, segments: usize) -> Vec<na::Vector3<f64>> {
+        self.path_sample_times(segments)
+            .into_iter()
+            .map(|days| self.display_offset_at(days))
+            .collect()
+    }
+}
+
+/// Solve Kepler's equation `M = E - e sin E` for the eccentric anomaly `E` using Newton iteration.
+pub fn solve_kepler(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    // starting from M converges poorly for high eccentricity, start from PI instead
+    let mut e = if eccentricity > 0.8 { PI } else { mean_anomaly };
+
+    for _ in 0..KEPLER_MAX_ITERATIONS {
+        let step = (e - eccentricity * e.sin() - mean_anomaly) / (1.0 - eccentricity * e.cos());
+        e -= step;
+        if step.abs() < KEPLER_TOLERANCE {
+            break;
+        }
+    }
+
+    e
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit(semi_major: f64, eccentricity: f64) -> KeplerOrbit {
+        KeplerOrbit {
+            semi_major,
+            eccentricity,
+            inclination: 0.1,
+            lon_ascending: 0.5,
+            arg_periapsis: 1.0,
+            mean_anomaly_epoch: 0.3,
+            period: 365.25,
+            parent: None,
+            display_scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn circular_orbit_keeps_constant_radius() {
+        let orbit = orbit(10.0, 0.0);
+        for day in (0..400).step_by(25) {
+            let radius = orbit.position_at(day as f64).coords.norm();
+            assert!((radius - 10.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn kepler_solver_converges_for_high_eccentricity() {
+        for i in 0..32 {
+            let mean_anomaly = i as f64 / 32.0 * 2.0 * PI;
+            let eccentric_anomaly = solve_kepler(mean_anomaly, 0.9);
+            let residual = eccentric_anomaly - 0.9 * eccentric_anomaly.sin() - mean_anomaly;
+            assert!(
+                residual.abs() < 1e-9,
+                "M = {}: residual {}",
+                mean_anomaly,
+                residual
+            );
+        }
+    }
+
+    #[test]
+    fn sampled_path_goes_through_body_positions() {
+        let orbit = orbit(10.0, 0.6);
+        let times = orbit.path_sample_times(48);
+        let path = orbit.sample_path(48, 0.0);
+        assert_eq!(path.len(), 48);
+
+        for (point, &days) in path.iter().zip(times.iter()) {
+            assert!((point - orbit.position_at(days)).norm() < 1e-9);
+        }
+        // the first point is periapsis
+        let periapsis = orbit.semi_major * (1.0 - orbit.eccentricity);
+        assert!((path[0].coords.norm() - periapsis).abs() < 1e-9);
+    }
+
+    #[test]
+    fn compressed_distance_grows_monotonically_from_zero() {
+        assert_eq!(compress_distance(0.0), 0.0);
+        assert_eq!(compress_distance(-1.0), 0.0);
+
+        let distances: Vec<_> = (0..200)
+            .map(|i| compress_distance(i as f64 * 25.0))
+            .collect();
+        assert!(distances.windows(2).all(|pair| pair[1] > pair[0]));
+        // far distances are squeezed much more than near ones
+        assert!(compress_distance(4000.0) / compress_distance(400.0) < 2.0);
+    }
+
+    #[test]
+    fn logarithmic_scene_keeps_directions() {
+        let transform = SceneTransform {
+            origin: na::Point3::new(1.0, 0.0, 0.0),
+            distance_scale: DistanceScale::Logarithmic,
+        };
+        let heliocentric = na::Point3::new(1.0, 300.0, 400.0);
+        let scene = transform.apply(&heliocentric);
+
+        assert!((scene.coords.norm() - compress_distance(500.0)).abs() < 1e-9);
+        assert!((scene.coords.normalize() - na::Vector3::new(0.0, 0.6, 0.8)).norm() < 1e-12);
+    }
+
+    /// Distances between the ellipse of `orbit` and the middle of each chord through
+    /// `anomalies`.
+    fn chord_errors(orbit: &KeplerOrbit, anomalies: &[f64]) -> Vec<f64> {
+        let b = orbit.semi_major * (1.0 - orbit.eccentricity * orbit.eccentricity).sqrt();
+        let point = |anomaly: f64| {
+            na::Point2::new(
+                orbit.semi_major * (anomaly.cos() - orbit.eccentricity),
+                b * anomaly.sin(),
+            )
+        };
+
+        (0..anomalies.len())
+            .map(|i| {
+                let start = anomalies[i];
+                let end = anomalies.get(i + 1).copied().unwrap_or(2.0 * PI);
+                let chord_middle = na::center(&point(start), &point(end));
+                (point((start + end) / 2.0) - chord_middle).norm()
+            })
+            .collect()
+    }
+
+    /// Largest error and ratio of largest to smallest error.
+    fn error_stats(errors: &[f64]) -> (f64, f64) {
+        let max = errors.iter().cloned().fold(0.0, f64::max);
+        let min = errors.iter().cloned().fold(f64::INFINITY, f64::min);
+        (max, max / min)
+    }
+
+    #[test]
+    fn adaptive_samples_stray_less_than_even_steps() {
+        let orbit = orbit(10.0, 0.9);
+        let segments = 64;
+        let uniform: Vec<_> = (0..segments)
+            .map(|i| i as f64 / segments as f64 * 2.0 * PI)
+            .collect();
+        let adaptive = orbit.path_sample_anomalies(segments);
+        assert_eq!(adaptive.len(), segments);
+        assert_eq!(adaptive[0], 0.0);
+
+        let (adaptive_max, adaptive_spread) = error_stats(&chord_errors(&orbit, &adaptive));
+        let (uniform_max, uniform_spread) = error_stats(&chord_errors(&orbit, &uniform));
+        assert!(
+            adaptive_max < uniform_max,
+            "{} {}",
+            adaptive_max,
+            uniform_max
+        );
+        // errors are about equal along the whole path
+        assert!(adaptive_spread < 1.5, "{}", adaptive_spread);
+        assert!(uniform_spread > 2.0, "{}", uniform_spread);
+
+        // a circle gets even steps
+        let circle = orbit_anomalies_for(0.0, segments);
+        for (sample, expected) in circle.iter().zip(uniform.iter()) {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    fn orbit_anomalies_for(eccentricity: f64, segments: usize) -> Vec<f64> {
+        orbit(10.0, eccentricity).path_sample_anomalies(segments)
+    }
+}
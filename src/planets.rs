@@ -0,0 +1,561 @@
+use crate::orbit::KeplerOrbit;
+use crate::render_gl::srgb;
+use crate::resources::{self, Resources};
+use crate::trail;
+use nalgebra as na;
+use serde::Deserialize;
+
+/// Scene units per astronomical unit.
+pub const SCENE_UNITS_PER_AU: f64 = 10.0;
+
+/// Range of the multiplier applied to body radii when rendering.
+pub const MIN_SCALE_FACTOR: f32 = 0.1;
+pub const MAX_SCALE_FACTOR: f32 = 100.0;
+
+/// Change of the radius multiplier for one key press.
+const SCALE_FACTOR_STEP: f32 = 1.25;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to load resource {}", name)]
+    ResourceLoad {
+        name: String,
+        #[cause]
+        inner: resources::Error,
+    },
+    #[fail(display = "Failed to parse {}", name)]
+    Parse {
+        name: String,
+        #[cause]
+        inner: ron::de::Error,
+    },
+    #[fail(
+        display = "Invalid field {} of planet {} in {}: {}",
+        field, planet, name, reason
+    )]
+    InvalidField {
+        name: String,
+        planet: String,
+        field: String,
+        reason: String,
+    },
+}
+
+/// Orbital elements as written in the planet table, with angles in degrees.
+#[derive(Clone, Debug, Deserialize)]
+pub struct OrbitDef {
+    /// Semi-major axis in astronomical units.
+    pub semi_major: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub lon_ascending: f64,
+    pub arg_periapsis: f64,
+    pub mean_anomaly_epoch: f64,
+    /// Orbital period in days.
+    pub period: f64,
+    /// Factor the distance to the parent is drawn with, for moons that would otherwise be
+    /// hidden by the exaggerated radius of their parent.
+    #[serde(default)]
+    pub display_scale: Option<f64>,
+}
+
+/// Ring system around a body, with radii in multiples of the body radius.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RingDef {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+}
+
+/// Tail of a comet, pointing away from the Sun.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CometDef {
+    /// Tail length in AU when the comet is 1 AU from the Sun.
+    pub tail_length: f64,
+}
+
+/// Celestial body described in the planet table.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PlanetDef {
+    pub name: String,
+    /// Bodies without orbit stay at the origin.
+    #[serde(default)]
+    pub orbit: Option<OrbitDef>,
+    /// Name of the body this one orbits, for moons; others orbit the Sun.
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Radius in scene units, before exaggeration by the scale factor.
+    pub radius: f32,
+    pub color: [f32; 3],
+    /// Emissive bodies are not lit by the Sun.
+    #[serde(default)]
+    pub emissive: bool,
+    #[serde(default)]
+    pub rings: Option<RingDef>,
+    /// Comets grow a tail near the Sun.
+    #[serde(default)]
+    pub comet: Option<CometDef>,
+    /// Image resource of an equirectangular surface map, drawn instead of the color once
+    /// loaded.
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// Samples in the orbit trail, overriding the default length.
+    #[serde(default)]
+    pub trail_length: Option<usize>,
+    /// Sidereal rotation period in days; bodies without it do not spin.
+    #[serde(default)]
+    pub rotation_period: Option<f64>,
+    /// Tilt of the spin axis from the ecliptic pole in degrees.
+    #[serde(default)]
+    pub axial_tilt: f32,
+}
+
+impl PlanetDef {
+    /// Orbit from this body's elements alone, without parent; see `kepler_orbits`.
+    pub fn kepler_orbit(&self) -> Option<KeplerOrbit> {
+        self.orbit.as_ref().map(|orbit| KeplerOrbit {
+            semi_major: orbit.semi_major * SCENE_UNITS_PER_AU,
+            eccentricity: orbit.eccentricity,
+            inclination: orbit.inclination.to_radians(),
+            lon_ascending: orbit.lon_ascending.to_radians(),
+            arg_periapsis: orbit.arg_periapsis.to_radians(),
+            mean_anomaly_epoch: orbit.mean_anomaly_epoch.to_radians(),
+            period: orbit.period,
+            parent: None,
+            display_scale: orbit.display_scale.unwrap_or(1.0),
+        })
+    }
+
+    /// Radius to draw the body with, exaggerated by `scale_factor` so it stays visible
+    /// next to orbital distances.
+    pub fn rendered_radius(&self, scale_factor: f32) -> f32 {
+        self.radius * scale_factor
+    }
+
+    /// Rotation of the body from model space at simulation time, with model Z as spin axis.
+    ///
+    /// The axis leans from the ecliptic pole towards ecliptic longitude 90°, so for Earth it
+    /// points at the north celestial pole, as in `sky_circles::equator_rotation`.
+    pub fn orientation(&self, sim_days: f64) -> na::UnitQuaternion<f32> {
+        let tilt = na::UnitQuaternion::from_axis_angle(
+            &na::Vector3::x_axis(),
+            -self.axial_tilt.to_radians(),
+        );
+        let spin = self.rotation_period.map_or(0.0, |period| {
+            ((sim_days / period).rem_euclid(1.0) * 2.0 * ::std::f64::consts::PI) as f32
+        });
+
+        tilt * na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), spin)
+    }
+
+    /// Model matrix of the body at `position`, spinning on its tilted axis, with the unit
+    /// sphere scaled to `radius`.
+    pub fn model_matrix(
+        &self,
+        position: &na::Vector3<f32>,
+        radius: f32,
+        sim_days: f64,
+    ) -> na::Matrix4<f32> {
+        na::Matrix4::new_translation(position)
+            * self.orientation(sim_days).to_homogeneous()
+            * na::Matrix4::new_scaling(radius)
+    }
+
+    /// South and north ends of the spin axis through a body at `position`, each `half_length`
+    /// from the center along the tilted axis.
+    pub fn axis_endpoints(
+        &self,
+        position: &na::Point3<f32>,
+        half_length: f32,
+        sim_days: f64,
+    ) -> (na::Point3<f32>, na::Point3<f32>) {
+        let axis = self.orientation(sim_days) * na::Vector3::z() * half_length;
+
+        (position - axis, position + axis)
+    }
+
+    /// Body color in linear space; the table lists it in sRGB.
+    pub fn color(&self) -> na::Vector3<f32> {
+        srgb::color_to_linear(&na::Vector3::new(
+            self.color[0],
+            self.color[1],
+            self.color[2],
+        ))
+    }
+}
+
+/// Next radius multiplier, one step larger or smaller, within the allowed range.
+pub fn step_scale_factor(current: f32, larger: bool) -> f32 {
+    let next = if larger {
+        current * SCALE_FACTOR_STEP
+    } else {
+        current / SCALE_FACTOR_STEP
+    };
+
+    next.clamp(MIN_SCALE_FACTOR, MAX_SCALE_FACTOR)
+}
+
+/// Index of the next (or previous) body in a list of `len` bodies, wrapping around at both ends.
+pub fn cycle_index(current: usize, len: usize, forward: bool) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    if forward {
+        (current + 1) % len
+    } else {
+        (current + len - 1) % len
+    }
+}
+
+/// Orbits of all `planets` in the same order, with moons placed on their parent's orbit.
+///
+/// Parents are expected to be checked by `parse`, so that they exist and have no cycles.
+pub fn kepler_orbits(planets: &[PlanetDef]) -> Vec<Option<KeplerOrbit>> {
+    planets
+        .iter()
+        .map(|planet| resolve_orbit(planets, planet, planets.len()))
+        .collect()
+}
+
+/// Orbit of `planet` with its parents resolved, following at most `depth` parents.
+fn resolve_orbit(planets: &[PlanetDef], planet: &PlanetDef, depth: usize) -> Option<KeplerOrbit> {
+    let mut orbit = planet.kepler_orbit()?;
+
+    if depth > 0 {
+        orbit.parent = planet
+            .parent
+            .as_ref()
+            .and_then(|parent| planets.iter().find(|p| &p.name == parent))
+            .and_then(|parent| resolve_orbit(planets, parent, depth - 1))
+            .map(Box::new);
+    }
+
+    Some(orbit)
+}
+
+/// Load planet table from RON resource.
+pub fn load(res: &Resources, name: &str) -> Result<Vec<PlanetDef>, Error> {
+    let text = res.load_string(name).map_err(|e| Error::ResourceLoad {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    parse(name, &text)
+}
+
+/// Parse and validate planet table, using `name` in errors.
+pub fn parse(name: &str, text: &str) -> Result<Vec<PlanetDef>, Error> {
+    let planets: Vec<PlanetDef> = ron::de::from_str(text).map_err(|e| Error::Parse {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    for planet in &planets {
+        validate(name, planet)?;
+        validate_parent(name, planet, &planets)?;
+    }
+
+    Ok(planets)
+}
+
+/// Check that the parent of `planet` exists and the chain of parents does not loop.
+fn validate_parent(name: &str, planet: &PlanetDef, planets: &[PlanetDef]) -> Result<(), Error> {
+    let invalid = |reason: &str| Error::InvalidField {
+        name: name.into(),
+        planet: planet.name.clone(),
+        field: "parent".into(),
+        reason: reason.into(),
+    };
+
+    if planet.parent.is_some() && planet.orbit.is_none() {
+        return Err(invalid("requires an orbit around the parent"));
+    }
+
+    // a chain longer than the table must pass through some body twice
+    let mut current = planet;
+    for _ in 0..planets.len() {
+        let parent = match current.parent {
+            Some(ref parent) => parent,
+            None => return Ok(()),
+        };
+        current = planets
+            .iter()
+            .find(|p| &p.name == parent)
+            .ok_or_else(|| invalid(&format!("unknown body {}", parent)))?;
+        if current.name == planet.name {
+            break;
+        }
+    }
+
+    Err(invalid("parents form a cycle"))
+}
+
+fn validate(name: &str, planet: &PlanetDef) -> Result<(), Error> {
+    let invalid = |field: &str, reason: &str| Error::InvalidField {
+        name: name.into(),
+        planet: planet.name.clone(),
+        field: field.into(),
+        reason: reason.into(),
+    };
+
+    if planet.radius.is_nan() || planet.radius <= 0.0 {
+        return Err(invalid("radius", "must be positive"));
+    }
+
+    if let Some(period) = planet.rotation_period {
+        if !period.is_finite() || period <= 0.0 {
+            return Err(invalid("rotation_period", "must be positive"));
+        }
+    }
+
+    if let Some(ref rings) = planet.rings {
+        if rings.inner_radius.is_nan() || rings.inner_radius <= 0.0 {
+            return Err(invalid("inner_radius", "must be positive"));
+        }
+        if rings.outer_radius.is_nan() || rings.outer_radius <= rings.inner_radius {
+            return Err(invalid("outer_radius", "must be greater than inner_radius"));
+        }
+    }
+
+    if let Some(ref comet) = planet.comet {
+        if comet.tail_length.is_nan() || comet.tail_length <= 0.0 {
+            return Err(invalid("tail_length", "must be positive"));
+        }
+    }
+
+    if let Some(length) = planet.trail_length {
+        if length == 0 || length > trail::MAX_LENGTH {
+            return Err(invalid(
+                "trail_length",
+                &format!("must be in range [1, {}]", trail::MAX_LENGTH),
+            ));
+        }
+    }
+
+    if let Some(ref orbit) = planet.orbit {
+        if orbit.semi_major.is_nan() || orbit.semi_major <= 0.0 {
+            return Err(invalid("semi_major", "must be positive"));
+        }
+        if !(0.0..1.0).contains(&orbit.eccentricity) {
+            return Err(invalid("eccentricity", "must be in range [0, 1)"));
+        }
+        if orbit.period.is_nan() || orbit.period <= 0.0 {
+            return Err(invalid("period", "must be positive"));
+        }
+        if let Some(scale) = orbit.display_scale {
+            if !scale.is_finite() || scale <= 0.0 {
+                return Err(invalid("display_scale", "must be positive"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_PLANETS: &str = r#"[
+        (name: "Sun", radius: 2.0, color: (1.0, 0.9, 0.6), emissive: true),
+        (
+            name: "Earth",
+            orbit: Some((
+                semi_major: 1.0,
+                eccentricity: ECCENTRICITY,
+                inclination: 0.0,
+                lon_ascending: 0.0,
+                arg_periapsis: 102.937,
+                mean_anomaly_epoch: 357.529,
+                period: 365.256,
+            )),
+            radius: 0.4,
+            color: (0.3, 0.5, 0.9),
+        ),
+    ]"#;
+
+    fn table(eccentricity: &str) -> String {
+        TWO_PLANETS.replace("ECCENTRICITY", eccentricity)
+    }
+
+    #[test]
+    fn parses_planet_table() {
+        let planets = parse("test.ron", &table("0.0167")).unwrap();
+        let names: Vec<_> = planets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Sun", "Earth"]);
+        assert!(planets[0].orbit.is_none());
+        assert_eq!(planets[1].orbit.as_ref().unwrap().eccentricity, 0.0167);
+    }
+
+    #[test]
+    fn bad_eccentricity_is_reported_with_file_name() {
+        match parse("test.ron", &table("\"high\"")) {
+            Err(Error::Parse { name, .. }) => assert_eq!(name, "test.ron"),
+            other => panic!("unexpected result {:?}", other.map(|p| p.len())),
+        }
+        match parse("test.ron", &table("1.5")) {
+            Err(Error::InvalidField {
+                name,
+                planet,
+                field,
+                ..
+            }) => assert_eq!(
+                (&*name, &*planet, &*field),
+                ("test.ron", "Earth", "eccentricity")
+            ),
+            other => panic!("unexpected result {:?}", other.map(|p| p.len())),
+        }
+    }
+
+    #[test]
+    fn focus_cycles_and_wraps_both_ways() {
+        assert_eq!(cycle_index(0, 3, true), 1);
+        assert_eq!(cycle_index(2, 3, true), 0);
+        assert_eq!(cycle_index(0, 3, false), 2);
+        assert_eq!(cycle_index(1, 3, false), 0);
+        assert_eq!(cycle_index(0, 0, true), 0);
+    }
+
+    #[test]
+    fn model_matrix_scales_unit_sphere_to_rendered_radius() {
+        let planets = parse("test.ron", &table("0.0167")).unwrap();
+        let earth = &planets[1];
+        let radius = earth.rendered_radius(10.0);
+        assert!((radius - 4.0).abs() < 1e-5);
+
+        let position = na::Vector3::new(3.0, -2.0, 0.5);
+        let model = earth.model_matrix(&position, radius, 12.5);
+        let center = model.transform_point(&na::Point3::origin());
+        assert!((center.coords - position).norm() < 1e-5);
+        for axis in &[na::Vector3::x(), na::Vector3::y(), na::Vector3::z()] {
+            assert!((model.transform_vector(axis).norm() - radius).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn scale_factor_steps_stay_in_range() {
+        assert!((step_scale_factor(1.0, true) - SCALE_FACTOR_STEP).abs() < 1e-6);
+        assert!((step_scale_factor(SCALE_FACTOR_STEP, false) - 1.0).abs() < 1e-6);
+        assert_eq!(step_scale_factor(MAX_SCALE_FACTOR, true), MAX_SCALE_FACTOR);
+        assert_eq!(step_scale_factor(MIN_SCALE_FACTOR, false), MIN_SCALE_FACTOR);
+    }
+
+    #[test]
+    fn earth_pole_normal_points_at_north_celestial_pole() {
+        let mut earth = parse("test.ron", &table("0.0167")).unwrap().remove(1);
+        earth.axial_tilt = 23.44;
+        earth.rotation_period = Some(0.997);
+
+        let celestial_pole = crate::sky_circles::equator_rotation() * na::Vector3::z();
+        // the spin moves the surface but not the pole
+        for &sim_days in &[0.0, 0.3, 100.7] {
+            let model = earth.model_matrix(&na::Vector3::new(10.0, 0.0, 0.0), 0.4, sim_days);
+            let normal_matrix = model
+                .fixed_slice::<na::U3, na::U3>(0, 0)
+                .try_inverse()
+                .unwrap()
+                .transpose();
+            let normal = (normal_matrix * na::Vector3::z()).normalize();
+
+            assert!((normal - celestial_pole).norm() < 1e-5, "{}", normal);
+        }
+        // north of the ecliptic, leaning towards ecliptic longitude 90°
+        assert!(celestial_pole.y > 0.39 && celestial_pole.z > 0.91);
+    }
+
+    /// Sun, Earth and Moon, with the parents of Moon and Earth given as RON options.
+    fn with_moon(moon_parent: &str, earth_parent: &str) -> String {
+        format!(
+            r#"[
+            (name: "Sun", radius: 2.0, color: (1.0, 0.9, 0.6), emissive: true),
+            (
+                name: "Earth",
+                parent: {},
+                orbit: Some((
+                    semi_major: 1.0,
+                    eccentricity: 0.0167,
+                    inclination: 0.0,
+                    lon_ascending: 0.0,
+                    arg_periapsis: 102.937,
+                    mean_anomaly_epoch: 357.529,
+                    period: 365.256,
+                )),
+                radius: 0.4,
+                color: (0.3, 0.5, 0.9),
+            ),
+            (
+                name: "Moon",
+                parent: {},
+                orbit: Some((
+                    semi_major: 0.00257,
+                    eccentricity: 0.0549,
+                    inclination: 5.145,
+                    lon_ascending: 125.08,
+                    arg_periapsis: 318.15,
+                    mean_anomaly_epoch: 135.27,
+                    period: 27.3217,
+                    display_scale: Some(39.0),
+                )),
+                radius: 0.11,
+                color: (0.7, 0.7, 0.7),
+            ),
+        ]"#,
+            earth_parent, moon_parent
+        )
+    }
+
+    #[test]
+    fn moon_is_placed_around_earth_at_true_distance() {
+        let planets = parse("test.ron", &with_moon("Some(\"Earth\")", "None")).unwrap();
+        let orbits = kepler_orbits(&planets);
+        let (earth, moon) = (orbits[1].as_ref().unwrap(), orbits[2].as_ref().unwrap());
+
+        for &sim_days in &[0.0, 10.0, 200.0] {
+            let offset = moon.position_at(sim_days) - earth.position_at(sim_days);
+            let distance_au = offset.norm() / SCENE_UNITS_PER_AU;
+            assert!(
+                (distance_au - 0.00257).abs() < 0.00257 * 0.06,
+                "{}",
+                distance_au
+            );
+
+            let scene = crate::orbit::SceneTransform {
+                origin: na::Point3::origin(),
+                distance_scale: crate::orbit::DistanceScale::Linear,
+            };
+            let drawn = scene.place(moon, sim_days) - scene.place(earth, sim_days);
+            assert!((drawn - offset * 39.0).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn parent_cycle_is_rejected() {
+        let cycle = with_moon("Some(\"Earth\")", "Some(\"Moon\")");
+        match parse("test.ron", &cycle) {
+            Err(Error::InvalidField { field, reason, .. }) => {
+                assert_eq!((&*field, &*reason), ("parent", "parents form a cycle"))
+            }
+            other => panic!("unexpected result {:?}", other.map(|p| p.len())),
+        }
+
+        match parse("test.ron", &with_moon("Some(\"Pluto\")", "None")) {
+            Err(Error::InvalidField { reason, .. }) => assert_eq!(reason, "unknown body Pluto"),
+            other => panic!("unexpected result {:?}", other.map(|p| p.len())),
+        }
+    }
+
+    #[test]
+    fn axis_endpoints_are_symmetric_along_the_spin_axis() {
+        let mut earth = parse("test.ron", &table("0.0167")).unwrap().remove(1);
+        earth.axial_tilt = 23.44;
+        earth.rotation_period = Some(0.997);
+        let position = na::Point3::new(10.0, -2.0, 1.0);
+
+        let (south, north) = earth.axis_endpoints(&position, 0.6, 42.5);
+        assert!((na::center(&south, &north) - position).norm() < 1e-5);
+        assert!(((north - south).norm() - 1.2).abs() < 1e-5);
+
+        let celestial_pole = crate::sky_circles::equator_rotation() * na::Vector3::z();
+        assert!(((north - position).normalize() - celestial_pole).norm() < 1e-5);
+    }
+}
@@ -199,3 +199,22 @@ impl Drop for VertexArray {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn buffers_and_vertex_arrays_are_deleted_on_drop() {
+        let gl = mock_gl::load();
+        {
+            let _vbo = ArrayBuffer::new(&gl);
+            let _ebo = ElementArrayBuffer::new(&gl);
+            let _vao = VertexArray::new(&gl);
+            assert_eq!(mock_gl::with_state(|s| s.deleted("buffer")), 0);
+        }
+        assert_eq!(mock_gl::with_state(|s| s.deleted("buffer")), 2);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("vertex array")), 1);
+    }
+}
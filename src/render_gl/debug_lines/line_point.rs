@@ -6,5 +6,5 @@ pub struct LinePoint {
     #[location = "0"]
     pub pos: data::f32_f32_f32,
     #[location = "1"]
-    pub color: data::u2_u10_u10_u10_rev_float,
+    pub color: data::u8_u8_u8_u8_float,
 }
@@ -15,6 +15,91 @@ mod shared_debug_lines;
 use self::line_point::LinePoint;
 use self::shared_debug_lines::SharedDebugLines;
 
+/// Number of GPU buffers the lines are rotated through, so that refilling one does not wait
+/// for the GPU to finish drawing from the buffer filled in the previous frames.
+const BUFFER_RING_SIZE: usize = 3;
+
+/// Line width supported by the driver closest to `width`, within `range` (min, max).
+pub fn clamp_line_width(width: f32, range: (f32, f32)) -> f32 {
+    width.max(range.0).min(range.1)
+}
+
+/// Index of the buffer to fill after `current`, in a ring of `len` buffers.
+pub fn next_buffer_index(current: usize, len: usize) -> usize {
+    if len == 0 {
+        0
+    } else {
+        (current + 1) % len
+    }
+}
+
+/// Consecutive vertices drawn with the same line width and translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct LineRange {
+    width: f32,
+    /// Container whose translation applies, none for lines drawn where they are.
+    translated: Option<i32>,
+    first: i32,
+    count: i32,
+}
+
+/// One GPU buffer of the ring, with the vertex array reading from it.
+struct LinesBuffer {
+    vbo: buffer::ArrayBuffer,
+    vbo_count: i32,
+    vbo_capacity: Option<usize>,
+    vao: buffer::VertexArray,
+    ranges: Vec<LineRange>,
+}
+
+impl LinesBuffer {
+    fn new(gl: &gl::Gl) -> LinesBuffer {
+        let vbo = buffer::ArrayBuffer::new(gl);
+        let vao = buffer::VertexArray::new(gl);
+        vao.bind();
+        vbo.bind();
+        LinePoint::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        LinesBuffer {
+            vbo,
+            vbo_count: 0,
+            vbo_capacity: None,
+            vao,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Replace buffer contents, growing the buffer if needed.
+    fn fill<'a, I: Iterator<Item = &'a LinePoint>>(&mut self, len: usize, items: I) {
+        self.vbo.bind();
+
+        let should_recreate_buffer = match self.vbo_capacity {
+            None => true,
+            Some(vbo_capacity) if vbo_capacity < len => true,
+            _ => false,
+        };
+
+        if should_recreate_buffer {
+            self.vbo.dynamic_draw_data_null::<LinePoint>(len);
+            self.vbo_capacity = Some(len);
+        }
+
+        if let Some(mut buffer) = unsafe {
+            self.vbo
+                .map_buffer_range_write_invalidate::<LinePoint>(0, len)
+        } {
+            for (index, item) in items.enumerate() {
+                *unsafe { buffer.get_unchecked_mut(index) } = *item;
+            }
+        }
+        self.vbo.unbind();
+
+        self.vbo_count = len as i32;
+    }
+}
+
 #[derive(Copy, Clone)]
 struct PolylineBuilderItem {
     point: na::Vector3<f32>,
@@ -43,7 +128,7 @@ impl PolylineBuilder {
         for (i, item) in self.items.into_iter().enumerate() {
             let mapped_point = LinePoint {
                 pos: (item.point.x, item.point.y, item.point.z).into(),
-                color: (item.color.x, item.color.y, item.color.z, item.color.w).into(),
+                color: render_color_vec4(item.color),
             };
             if i == 0 || i == points_len - 1 {
                 pairwise_points.push(mapped_point);
@@ -62,48 +147,68 @@ impl PolylineBuilder {
     }
 }
 
+/// Handle to a line strip; its lines are removed from `DebugLines` when dropped.
 pub struct Polyline {
     containers: Rc<RefCell<SharedDebugLines>>,
     id: i32,
 }
 
+impl Polyline {
+    /// Draw the whole line strip moved by `translation`, without rebuilding its points.
+    pub fn set_translation(&self, translation: na::Vector3<f32>) {
+        self.containers
+            .borrow_mut()
+            .set_translation(self.id, translation);
+    }
+}
+
 impl Drop for Polyline {
     fn drop(&mut self) {
         self.containers.borrow_mut().remove_container(self.id);
     }
 }
 
+/// Renders all debug lines in a single draw call.
+///
+/// Every helper returns a handle that owns its chunk of line data in shared storage.
+/// Dropping the handle frees the chunk, and the GPU buffer is refilled on next `render`
+/// (the buffer itself is only reallocated when it needs to grow). Handles hold the shared
+/// storage alive, so they can safely outlive a frame, or even the `DebugLines` itself.
 pub struct DebugLines {
     program: Program,
     program_view_projection_location: i32,
+    program_translation_location: i32,
     containers: Rc<RefCell<SharedDebugLines>>,
-    lines_vbo_count: i32,
-    lines_vbo: buffer::ArrayBuffer,
-    lines_vbo_capacity: Option<usize>,
-    lines_vao: buffer::VertexArray,
+    /// Aliased line widths supported by the driver, as (min, max).
+    line_width_range: (f32, f32),
+    lines_buffers: Vec<LinesBuffer>,
+    /// Buffer filled last, which is the one drawn.
+    current_buffer: usize,
 }
 
 impl DebugLines {
     pub fn new(gl: &gl::Gl, res: &Resources) -> Result<DebugLines, failure::Error> {
-        let lines_vbo = buffer::ArrayBuffer::new(&gl);
-        let lines_vao = buffer::VertexArray::new(gl);
-        lines_vao.bind();
-        lines_vbo.bind();
-        LinePoint::vertex_attrib_pointers(gl);
-        lines_vbo.unbind();
-        lines_vao.unbind();
+        let lines_buffers = (0..BUFFER_RING_SIZE)
+            .map(|_| LinesBuffer::new(gl))
+            .collect();
 
         let program = Program::from_res(gl, res, "shaders/render_gl/debug_lines")?;
         let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
+        let program_translation_location = program.get_uniform_location("Translation")?;
+
+        let mut line_width_range = [1.0f32; 2];
+        unsafe {
+            gl.GetFloatv(gl::ALIASED_LINE_WIDTH_RANGE, line_width_range.as_mut_ptr());
+        }
 
         Ok(DebugLines {
             program,
             program_view_projection_location,
+            program_translation_location,
             containers: Rc::new(RefCell::new(SharedDebugLines::new())),
-            lines_vbo,
-            lines_vbo_count: 0,
-            lines_vbo_capacity: None,
-            lines_vao,
+            line_width_range: (line_width_range[0], line_width_range[1]),
+            lines_buffers,
+            current_buffer: 0,
         })
     }
 
@@ -111,71 +216,149 @@ impl DebugLines {
         let mut shared_debug_lines = self.containers.borrow_mut();
 
         if shared_debug_lines.invalidated {
-            let all_data_len = shared_debug_lines
-                .containers
-                .values()
-                .flat_map(|v| v.iter())
-                .count();
-
-            self.lines_vbo.bind();
-
-            let should_recreate_buffer = match self.lines_vbo_capacity {
-                None => true,
-                Some(lines_vbo_capacity) if lines_vbo_capacity < all_data_len => true,
-                _ => false,
-            };
-
-            if should_recreate_buffer {
-                self.lines_vbo
-                    .dynamic_draw_data_null::<LinePoint>(all_data_len);
-                self.lines_vbo_capacity = Some(all_data_len);
-            }
-
-            if let Some(_) = self.lines_vbo_capacity {
-                if let Some(mut buffer) = unsafe {
-                    self.lines_vbo
-                        .map_buffer_range_write_invalidate::<LinePoint>(0, all_data_len)
-                } {
-                    for (index, item) in shared_debug_lines
-                        .containers
-                        .values()
-                        .flat_map(|v| v.iter())
-                        .enumerate()
+            // lines of the same width are kept together, so each width is one draw call,
+            // plus one for every translated container
+            let mut containers = shared_debug_lines
+                .visible_containers_with_width()
+                .collect::<Vec<_>>();
+            containers.sort_by(|a, b| {
+                a.0.partial_cmp(&b.0)
+                    .unwrap_or(::std::cmp::Ordering::Equal)
+                    .then(a.1.cmp(&b.1))
+            });
+
+            let mut ranges: Vec<LineRange> = Vec::new();
+            let mut all_data_len = 0;
+            for &(width, translated, data) in &containers {
+                match ranges.last_mut() {
+                    Some(range)
+                        if range.width == width
+                            && range.translated.is_none()
+                            && translated.is_none() =>
                     {
-                        *unsafe { buffer.get_unchecked_mut(index) } = *item;
+                        range.count += data.len() as i32
                     }
+                    _ => ranges.push(LineRange {
+                        width,
+                        translated,
+                        first: all_data_len as i32,
+                        count: data.len() as i32,
+                    }),
                 }
+                all_data_len += data.len();
             }
-            self.lines_vbo.unbind();
 
-            self.lines_vbo_count = all_data_len as i32;
+            // write into the buffer least recently drawn from
+            self.current_buffer = next_buffer_index(self.current_buffer, self.lines_buffers.len());
+            let lines_buffer = &mut self.lines_buffers[self.current_buffer];
+            lines_buffer.fill(
+                all_data_len,
+                containers.iter().flat_map(|&(_, _, data)| data.iter()),
+            );
+            lines_buffer.ranges = ranges;
 
             shared_debug_lines.invalidated = false;
         }
     }
 
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (program_view_projection_location, program_translation_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("ViewProjection")?,
+                    program.get_uniform_location("Translation")?,
+                ))
+            })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.program_translation_location = program_translation_location;
+        Ok(())
+    }
+
+    /// Number of live handles contributing lines.
+    pub fn active_count(&self) -> usize {
+        self.containers.borrow().containers.len()
+    }
+
+    /// Number of live handles in visible groups.
+    pub fn visible_count(&self) -> usize {
+        self.containers.borrow().visible_containers().count()
+    }
+
+    /// Create handles in `f` as members of a named group, which can be hidden as a whole.
+    ///
+    /// Handles created outside of any group belong to the default group `""`.
+    pub fn in_group<R, F: FnOnce(&DebugLines) -> R>(&self, name: &str, f: F) -> R {
+        let previous =
+            ::std::mem::replace(&mut self.containers.borrow_mut().current_group, name.into());
+        let result = f(self);
+        self.containers.borrow_mut().current_group = previous;
+        result
+    }
+
+    /// Show or hide all lines of a group, including handles created later.
+    pub fn set_group_visible(&mut self, name: &str, visible: bool) {
+        self.containers
+            .borrow_mut()
+            .set_group_visible(name, visible);
+    }
+
+    pub fn is_group_visible(&self, name: &str) -> bool {
+        self.containers.borrow().is_group_visible(name)
+    }
+
+    /// Set line width in pixels for all lines of a group; the default group is `""`.
+    ///
+    /// Drivers only support some widths, and core profiles may not draw wide lines at all,
+    /// so the width is clamped to `line_width_range`. Returns the width actually used.
+    pub fn set_group_line_width(&mut self, name: &str, width: f32) -> f32 {
+        let width = clamp_line_width(width, self.line_width_range);
+        self.containers
+            .borrow_mut()
+            .set_group_line_width(name, width);
+        width
+    }
+
+    /// Aliased line widths supported by the driver, as (min, max).
+    pub fn line_width_range(&self) -> (f32, f32) {
+        self.line_width_range
+    }
+
     pub fn render(&mut self, gl: &gl::Gl, target: &ColorBuffer, vp_matrix: &na::Matrix4<f32>) {
         self.check_if_invalidated_and_reinitialize();
 
-        if self.lines_vbo_count > 0 {
+        let lines_buffer = &self.lines_buffers[self.current_buffer];
+        if lines_buffer.vbo_count > 0 {
             self.program.set_used();
             self.program
                 .set_uniform_matrix4fv(self.program_view_projection_location, &vp_matrix);
 
-            self.lines_vao.bind();
+            lines_buffer.vao.bind();
 
             unsafe {
                 target.set_default_blend_func(gl);
                 target.enable_blend(gl);
 
-                gl.DrawArrays(
-                    gl::LINES,            // mode
-                    0,                    // starting index in the enabled arrays
-                    self.lines_vbo_count, // number of indices to be rendered
-                );
+                let containers = self.containers.borrow();
+                for range in &lines_buffer.ranges {
+                    let translation = range
+                        .translated
+                        .and_then(|key| containers.translation(key))
+                        .unwrap_or_else(na::zero);
+                    self.program
+                        .set_uniform_3f(self.program_translation_location, &translation);
+                    gl.LineWidth(range.width);
+                    gl.DrawArrays(
+                        gl::LINES,   // mode
+                        range.first, // starting index in the enabled arrays
+                        range.count, // number of indices to be rendered
+                    );
+                }
+                gl.LineWidth(1.0);
 
                 target.disable_blend(gl);
             }
+            crate::debug::check_gl_error(gl, "debug lines");
         }
     }
 
@@ -193,33 +376,93 @@ impl DebugLines {
         }
     }
 
+    /// White line segment.
+    pub fn line(&self, from: na::Point3<f32>, to: na::Point3<f32>) -> Polyline {
+        self.colored_line(from, to, white())
+    }
+
+    pub fn colored_line(
+        &self,
+        from: na::Point3<f32>,
+        to: na::Point3<f32>,
+        color: na::Vector4<f32>,
+    ) -> Polyline {
+        self.start_polyline(from.coords, color)
+            .with_point(to.coords, color)
+            .finish()
+    }
+
+    /// White closed ellipse around `center` on the XY plane, tilted around X axis by `inclination`.
+    pub fn ellipse(
+        &self,
+        center: na::Point3<f32>,
+        semi_major: f32,
+        semi_minor: f32,
+        inclination: f32,
+        segments: usize,
+    ) -> Polyline {
+        self.colored_ellipse(
+            center,
+            semi_major,
+            semi_minor,
+            inclination,
+            segments,
+            white(),
+        )
+    }
+
+    pub fn colored_ellipse(
+        &self,
+        center: na::Point3<f32>,
+        semi_major: f32,
+        semi_minor: f32,
+        inclination: f32,
+        segments: usize,
+        color: na::Vector4<f32>,
+    ) -> Polyline {
+        let segments = segments.max(3);
+        let tilt = na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), inclination);
+
+        let point_at = |i: usize| {
+            let angle = i as f32 / segments as f32 * 2.0 * ::std::f32::consts::PI;
+            center.coords
+                + tilt * na::Vector3::new(semi_major * angle.cos(), semi_minor * angle.sin(), 0.0)
+        };
+
+        (1..segments)
+            .fold(self.start_polyline(point_at(0), color), |builder, i| {
+                builder.with_point(point_at(i), color)
+            })
+            .close_and_finish()
+    }
+
     pub fn marker(&self, pos: na::Point3<f32>, size: f32) -> PointMarker {
         let half = size / 2.0;
 
         let new_id = self.containers.borrow_mut().new_container(vec![
             LinePoint {
                 pos: render_p3(pos + na::Vector3::x() * half),
-                color: (0.0, 1.0, 0.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(0.0, 1.0, 0.0, 1.0)),
             },
             LinePoint {
                 pos: render_p3(pos + na::Vector3::x() * -half),
-                color: (0.0, 1.0, 0.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(0.0, 1.0, 0.0, 1.0)),
             },
             LinePoint {
                 pos: render_p3(pos + na::Vector3::y() * half),
-                color: (1.0, 0.0, 0.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(1.0, 0.0, 0.0, 1.0)),
             },
             LinePoint {
                 pos: render_p3(pos + na::Vector3::y() * -half),
-                color: (1.0, 0.0, 0.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(1.0, 0.0, 0.0, 1.0)),
             },
             LinePoint {
                 pos: render_p3(pos + na::Vector3::z() * half),
-                color: (0.0, 0.0, 1.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(0.0, 0.0, 1.0, 1.0)),
             },
             LinePoint {
                 pos: render_p3(pos + na::Vector3::z() * -half),
-                color: (0.0, 0.0, 1.0, 1.0).into(),
+                color: render_color_vec4(na::Vector4::new(0.0, 0.0, 1.0, 1.0)),
             },
         ]);
 
@@ -272,6 +515,23 @@ impl DebugLines {
         }
     }
 
+    /// Empty trail of up to `capacity` points, see `TrailLine::update_points`.
+    pub fn trail(&self, capacity: usize, color: na::Vector4<f32>) -> TrailLine {
+        let capacity = capacity.max(2);
+
+        let new_id = self
+            .containers
+            .borrow_mut()
+            .new_container(vec![hidden_point(); (capacity - 1) * 2]);
+
+        TrailLine {
+            containers: self.containers.clone(),
+            id: new_id,
+            capacity,
+            color,
+        }
+    }
+
     pub fn ray_marker(
         &self,
         pos: na::Point3<f32>,
@@ -299,6 +559,82 @@ impl DebugLines {
     }
 }
 
+/// Handle to a fixed-size line strip that fades out towards its tail; its lines are removed
+/// from `DebugLines` when dropped.
+pub struct TrailLine {
+    containers: Rc<RefCell<SharedDebugLines>>,
+    id: i32,
+    capacity: usize,
+    color: na::Vector4<f32>,
+}
+
+impl TrailLine {
+    /// Change the number of trail points; new segments stay hidden until the next update.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(2);
+        self.containers.borrow_mut().resize_container(
+            self.id,
+            (self.capacity - 1) * 2,
+            hidden_point(),
+        );
+    }
+
+    /// Replace trail points, from head (opaque) to tail (transparent).
+    ///
+    /// Points beyond trail capacity are ignored.
+    pub fn update_points<I: IntoIterator<Item = na::Point3<f32>>>(&self, points: I) {
+        let points: Vec<_> = points.into_iter().take(self.capacity).collect();
+
+        if let Some(data) = self.containers.borrow_mut().get_container_mut(self.id) {
+            let faded = |i: usize| {
+                let mut color = self.color;
+                color.w *= trail_alpha(i, self.capacity);
+                render_color_vec4(color)
+            };
+
+            for (segment, pair) in data.chunks_mut(2).enumerate() {
+                match (points.get(segment), points.get(segment + 1)) {
+                    (Some(&from), Some(&to)) => {
+                        pair[0].pos = render_p3(from);
+                        pair[0].color = faded(segment);
+                        pair[1].pos = render_p3(to);
+                        pair[1].color = faded(segment + 1);
+                    }
+                    _ => {
+                        // unused segment collapses to an invisible point
+                        pair[0].color = render_color_vec4(na::Vector4::zeros());
+                        pair[1] = pair[0];
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TrailLine {
+    fn drop(&mut self) {
+        self.containers.borrow_mut().remove_container(self.id);
+    }
+}
+
+/// Invisible point at the origin, filling unused line segments.
+fn hidden_point() -> LinePoint {
+    LinePoint {
+        pos: render_p3(na::Point3::origin()),
+        color: render_color_vec4(na::Vector4::zeros()),
+    }
+}
+
+/// Opacity of trail point `i` (0 is head) in a trail of `capacity` points.
+pub fn trail_alpha(i: usize, capacity: usize) -> f32 {
+    if capacity < 2 {
+        return 1.0;
+    }
+
+    1.0 - (i as f32 / (capacity - 1) as f32).min(1.0)
+}
+
+/// Handle to a ray; its line is removed from `DebugLines` when dropped.
 pub struct RayMarker {
     containers: Rc<RefCell<SharedDebugLines>>,
     id: i32,
@@ -321,6 +657,7 @@ impl Drop for RayMarker {
     }
 }
 
+/// Handle to a 3-axis cross; its lines are removed from `DebugLines` when dropped.
 pub struct PointMarker {
     containers: Rc<RefCell<SharedDebugLines>>,
     id: i32,
@@ -342,6 +679,9 @@ impl PointMarker {
             data[5].pos = render_p3(pos + na::Vector3::z() * -half);
         }
     }
+
+    /// Remove the marker lines now, same as dropping the handle.
+    pub fn remove(self) {}
 }
 
 impl Drop for PointMarker {
@@ -350,10 +690,180 @@ impl Drop for PointMarker {
     }
 }
 
+fn white() -> na::Vector4<f32> {
+    na::Vector4::new(1.0, 1.0, 1.0, 1.0)
+}
+
 fn render_p3(v: na::Point3<f32>) -> data::f32_f32_f32 {
     data::f32_f32_f32::new(v.x, v.y, v.z)
 }
 
-fn render_color_vec4(v: na::Vector4<f32>) -> data::u2_u10_u10_u10_rev_float {
-    (v.x, v.y, v.z, v.w).into()
+/// Pack color with 8 bits per channel, so trail alpha keeps its gradual fade.
+fn render_color_vec4(v: na::Vector4<f32>) -> data::u8_u8_u8_u8_float {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    data::u8_u8_u8_u8_float::new(channel(v.x), channel(v.y), channel(v.z), channel(v.w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    fn debug_lines() -> DebugLines {
+        mock_gl::test_debug_lines().1
+    }
+
+    #[test]
+    fn ellipse_has_one_line_per_segment_and_is_closed() {
+        let debug_lines = debug_lines();
+        let ellipse = debug_lines.ellipse(na::Point3::origin(), 2.0, 1.0, 0.0, 64);
+
+        let containers = debug_lines.containers.borrow();
+        let points = &containers.containers[&ellipse.id];
+        // 65 points including the closing one, each inner point shared by two lines
+        assert_eq!(points.len(), 64 * 2);
+        let (first, last) = (points[0].pos, points[points.len() - 1].pos);
+        assert!((first.d0 - last.d0).abs() < 1e-5);
+        assert!((first.d1 - last.d1).abs() < 1e-5);
+        assert!((first.d2 - last.d2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn dropping_handles_frees_their_lines() {
+        let debug_lines = debug_lines();
+        let marker = debug_lines.marker(na::Point3::origin(), 1.0);
+        let line = debug_lines.line(na::Point3::origin(), na::Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(debug_lines.active_count(), 2);
+
+        drop(marker);
+        assert_eq!(debug_lines.active_count(), 1);
+        drop(line);
+        assert_eq!(debug_lines.active_count(), 0);
+    }
+
+    #[test]
+    fn hidden_group_lines_are_not_visible_until_shown() {
+        let mut debug_lines = debug_lines();
+        let orbit = debug_lines.in_group("orbits", |lines| {
+            lines.line(na::Point3::origin(), na::Point3::new(1.0, 0.0, 0.0))
+        });
+        let _marker = debug_lines.marker(na::Point3::origin(), 1.0);
+        assert_eq!(debug_lines.visible_count(), 2);
+
+        debug_lines.set_group_visible("orbits", false);
+        assert!(!debug_lines.is_group_visible("orbits"));
+        assert!(debug_lines.is_group_visible(""));
+        assert_eq!(debug_lines.visible_count(), 1);
+        assert_eq!(debug_lines.active_count(), 2);
+
+        // lines created later join the hidden group
+        let _later = debug_lines.in_group("orbits", |lines| {
+            lines.line(na::Point3::origin(), na::Point3::new(0.0, 1.0, 0.0))
+        });
+        assert_eq!(debug_lines.visible_count(), 1);
+
+        debug_lines.set_group_visible("orbits", true);
+        assert_eq!(debug_lines.visible_count(), 3);
+        drop(orbit);
+        assert_eq!(debug_lines.visible_count(), 2);
+    }
+
+    #[test]
+    fn translated_polyline_is_drawn_on_its_own_with_its_offset() {
+        let (gl, mut debug_lines) = mock_gl::test_debug_lines();
+        let _line = debug_lines.line(na::Point3::origin(), na::Point3::new(1.0, 0.0, 0.0));
+        let _other = debug_lines.line(na::Point3::origin(), na::Point3::new(0.0, 1.0, 0.0));
+        let moved = debug_lines.line(na::Point3::origin(), na::Point3::new(0.0, 0.0, 1.0));
+        moved.set_translation(na::Vector3::new(5.0, 0.0, 0.0));
+
+        let target = ColorBuffer::new();
+        debug_lines.render(&gl, &target, &na::Matrix4::identity());
+        mock_gl::with_state(|state| assert_eq!(state.draws, vec![4, 2]));
+
+        // moving it again does not refill the buffer
+        moved.set_translation(na::Vector3::new(6.0, 0.0, 0.0));
+        assert!(!debug_lines.containers.borrow().invalidated);
+        let buffer = &debug_lines.lines_buffers[debug_lines.current_buffer];
+        assert_eq!(buffer.ranges.len(), 2);
+        assert_eq!(buffer.ranges[1].translated, Some(moved.id));
+    }
+
+    #[test]
+    fn buffer_ring_index_wraps_around() {
+        let indices = (0..BUFFER_RING_SIZE * 2)
+            .scan(0, |current, _| {
+                *current = next_buffer_index(*current, BUFFER_RING_SIZE);
+                Some(*current)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [1, 2, 0, 1, 2, 0]);
+        assert_eq!(next_buffer_index(0, 1), 0);
+        assert_eq!(next_buffer_index(5, 0), 0);
+    }
+
+    #[test]
+    fn each_change_fills_the_next_buffer_of_the_ring() {
+        let (gl, mut debug_lines) = mock_gl::test_debug_lines();
+        let target = ColorBuffer::new();
+        let mut drawn = Vec::new();
+        for i in 0..4 {
+            let _marker = debug_lines.marker(na::Point3::new(i as f32, 0.0, 0.0), 1.0);
+            debug_lines.render(&gl, &target, &na::Matrix4::identity());
+            drawn.push(debug_lines.current_buffer);
+        }
+        assert_eq!(drawn, [1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn line_width_is_clamped_to_the_driver_range() {
+        assert_eq!(clamp_line_width(0.5, (1.0, 8.0)), 1.0);
+        assert_eq!(clamp_line_width(3.0, (1.0, 8.0)), 3.0);
+        assert_eq!(clamp_line_width(12.0, (1.0, 8.0)), 8.0);
+        // core profiles without wide lines report a range of (1, 1)
+        assert_eq!(clamp_line_width(4.0, (1.0, 1.0)), 1.0);
+
+        let mut debug_lines = debug_lines();
+        assert_eq!(debug_lines.line_width_range(), (1.0, 8.0));
+        assert_eq!(debug_lines.set_group_line_width("orbits", 20.0), 8.0);
+        assert_eq!(debug_lines.set_group_line_width("orbits", 2.5), 2.5);
+    }
+
+    #[test]
+    fn removing_a_marker_frees_only_its_lines() {
+        let mut debug_lines = debug_lines();
+        let color = na::Vector4::new(1.0, 0.6, 0.1, 1.0);
+        let mut markers: Vec<_> = (0..3)
+            .map(|i| debug_lines.colored_marker(na::Point3::new(i as f32, 0.0, 0.0), color, 0.5))
+            .collect();
+        assert_eq!(debug_lines.active_count(), 3);
+        debug_lines.check_if_invalidated_and_reinitialize();
+
+        markers.pop().unwrap().remove();
+        assert_eq!(debug_lines.active_count(), 2);
+        assert!(debug_lines.containers.borrow().invalidated);
+
+        let remaining = debug_lines.containers.borrow();
+        assert!(remaining.containers.contains_key(&markers[0].id));
+        assert!(remaining.containers.contains_key(&markers[1].id));
+    }
+
+    #[test]
+    fn colored_line_and_ellipse_store_their_packed_color() {
+        let debug_lines = debug_lines();
+        let color = na::Vector4::new(1.0, 0.6, 0.2, 0.5);
+        let line =
+            debug_lines.colored_line(na::Point3::origin(), na::Point3::new(1.0, 0.0, 0.0), color);
+        let ellipse = debug_lines.colored_ellipse(na::Point3::origin(), 2.0, 1.0, 0.3, 16, color);
+
+        let containers = debug_lines.containers.borrow();
+        for id in &[line.id, ellipse.id] {
+            for point in &containers.containers[id] {
+                let stored = point.color;
+                assert_eq!(
+                    (stored.d0, stored.d1, stored.d2, stored.d3),
+                    (255, 153, 51, 128)
+                );
+            }
+        }
+    }
 }
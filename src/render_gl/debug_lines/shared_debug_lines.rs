@@ -1,9 +1,19 @@
 use super::LinePoint;
-use std::collections::BTreeMap;
+use nalgebra as na;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct SharedDebugLines {
     pub invalidated: bool,
     pub containers: BTreeMap<i32, Vec<LinePoint>>,
+    /// Group name of each container.
+    groups: BTreeMap<i32, String>,
+    /// Group assigned to newly created containers.
+    pub current_group: String,
+    hidden_groups: BTreeSet<String>,
+    /// Line widths of groups that do not use the default width.
+    group_line_widths: BTreeMap<String, f32>,
+    /// Offsets of containers that are moved as a whole, applied when drawing.
+    translations: BTreeMap<i32, na::Vector3<f32>>,
     next_id: i32,
 }
 
@@ -12,6 +22,11 @@ impl SharedDebugLines {
         SharedDebugLines {
             invalidated: true,
             containers: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            current_group: String::new(),
+            hidden_groups: BTreeSet::new(),
+            group_line_widths: BTreeMap::new(),
+            translations: BTreeMap::new(),
             next_id: 0,
         }
     }
@@ -25,17 +40,96 @@ impl SharedDebugLines {
     pub fn new_container(&mut self, data: Vec<LinePoint>) -> i32 {
         let next_id = self.get_next_id();
         self.containers.insert(next_id, data);
+        self.groups.insert(next_id, self.current_group.clone());
         self.invalidated = true;
         next_id
     }
 
     pub fn remove_container(&mut self, key: i32) {
         self.containers.remove(&key);
+        self.groups.remove(&key);
+        self.translations.remove(&key);
         self.invalidated = true;
     }
 
+    /// Grow or shrink a container to `len` points, filling new ones with `fill`.
+    pub fn resize_container(&mut self, key: i32, len: usize, fill: LinePoint) {
+        if let Some(data) = self.containers.get_mut(&key) {
+            data.resize(len, fill);
+            self.invalidated = true;
+        }
+    }
+
     pub fn get_container_mut(&mut self, key: i32) -> Option<&mut [LinePoint]> {
         self.invalidated = true;
         self.containers.get_mut(&key).map(|v| v.as_mut_slice())
     }
+
+    pub fn set_group_visible(&mut self, group: &str, visible: bool) {
+        let changed = if visible {
+            self.hidden_groups.remove(group)
+        } else {
+            self.hidden_groups.insert(group.into())
+        };
+
+        if changed {
+            self.invalidated = true;
+        }
+    }
+
+    pub fn is_group_visible(&self, group: &str) -> bool {
+        !self.hidden_groups.contains(group)
+    }
+
+    pub fn set_group_line_width(&mut self, group: &str, width: f32) {
+        self.group_line_widths.insert(group.into(), width);
+        self.invalidated = true;
+    }
+
+    pub fn group_line_width(&self, group: &str) -> f32 {
+        self.group_line_widths.get(group).cloned().unwrap_or(1.0)
+    }
+
+    /// Move a container by `translation` without touching its points.
+    ///
+    /// Only the first translation of a container refills the buffer, because the container
+    /// is then drawn on its own.
+    pub fn set_translation(&mut self, key: i32, translation: na::Vector3<f32>) {
+        if self.containers.contains_key(&key)
+            && self.translations.insert(key, translation).is_none()
+        {
+            self.invalidated = true;
+        }
+    }
+
+    pub fn translation(&self, key: i32) -> Option<na::Vector3<f32>> {
+        self.translations.get(&key).cloned()
+    }
+
+    /// Containers that belong to visible groups.
+    pub fn visible_containers<'a>(&'a self) -> impl Iterator<Item = &'a Vec<LinePoint>> + 'a {
+        self.visible_containers_with_width()
+            .map(|(_, _, data)| data)
+    }
+
+    /// Containers that belong to visible groups, with the line width of their group and
+    /// their key if they are translated.
+    pub fn visible_containers_with_width<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (f32, Option<i32>, &'a Vec<LinePoint>)> + 'a {
+        self.containers.iter().filter_map(move |(id, data)| {
+            let group = self.groups.get(id).map(String::as_str).unwrap_or("");
+
+            if self.hidden_groups.contains(group) {
+                None
+            } else {
+                let translated = if self.translations.contains_key(id) {
+                    Some(*id)
+                } else {
+                    None
+                };
+                Some((self.group_line_width(group), translated, data))
+            }
+        })
+    }
 }
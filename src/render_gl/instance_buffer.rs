@@ -0,0 +1,124 @@
+use crate::render_gl::{buffer, data};
+use nalgebra as na;
+
+/// First vertex attribute location of the per-instance model matrix.
+///
+/// A `mat4` attribute takes four consecutive locations, one per column, so meshes drawn with
+/// instances must keep locations 3 to 6 free.
+pub const INSTANCE_FIRST_LOCATION: usize = 3;
+
+/// Model matrix of one instance, stored column by column.
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub struct ModelInstance {
+    #[location = "3"]
+    col0: data::f32_f32_f32_f32,
+    #[location = "4"]
+    col1: data::f32_f32_f32_f32,
+    #[location = "5"]
+    col2: data::f32_f32_f32_f32,
+    #[location = "6"]
+    col3: data::f32_f32_f32_f32,
+}
+
+impl ModelInstance {
+    /// Bytes between the starts of two instances in the buffer.
+    pub const STRIDE: usize = ::std::mem::size_of::<ModelInstance>();
+
+    /// Number of vertex attribute locations one instance takes.
+    pub const LOCATIONS: usize = 4;
+}
+
+impl From<&na::Matrix4<f32>> for ModelInstance {
+    fn from(model: &na::Matrix4<f32>) -> Self {
+        let column = |i: usize| (model[(0, i)], model[(1, i)], model[(2, i)], model[(3, i)]).into();
+
+        ModelInstance {
+            col0: column(0),
+            col1: column(1),
+            col2: column(2),
+            col3: column(3),
+        }
+    }
+}
+
+/// Buffer of per-instance model matrices for `glDrawElementsInstanced`.
+///
+/// The buffer is attached to a vertex array once, after which updates only replace the data.
+pub struct InstanceBuffer {
+    gl: gl::Gl,
+    vbo: buffer::ArrayBuffer,
+    count: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new(gl: &gl::Gl) -> InstanceBuffer {
+        InstanceBuffer {
+            gl: gl.clone(),
+            vbo: buffer::ArrayBuffer::new(gl),
+            count: 0,
+        }
+    }
+
+    /// Set up instance attributes on the currently bound vertex array, advancing once per
+    /// instance instead of once per vertex.
+    pub fn attach(&self) {
+        self.vbo.bind();
+        ModelInstance::vertex_attrib_pointers(&self.gl);
+        for location in INSTANCE_FIRST_LOCATION..INSTANCE_FIRST_LOCATION + ModelInstance::LOCATIONS
+        {
+            unsafe {
+                self.gl
+                    .VertexAttribDivisor(location as gl::types::GLuint, 1);
+            }
+        }
+        self.vbo.unbind();
+    }
+
+    /// Replace all instances with these model matrices.
+    pub fn update(&mut self, models: &[na::Matrix4<f32>]) {
+        let instances = models.iter().map(ModelInstance::from).collect::<Vec<_>>();
+
+        self.vbo.bind();
+        self.vbo.dynamic_draw_data(&instances);
+        self.vbo.unbind();
+        self.count = instances.len();
+    }
+
+    /// Number of instances uploaded by the last update.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn instances_are_packed_matrices() {
+        assert_eq!(ModelInstance::STRIDE, 64);
+        assert_eq!(
+            ModelInstance::STRIDE,
+            ModelInstance::LOCATIONS * ::std::mem::size_of::<data::f32_f32_f32_f32>()
+        );
+
+        let model = na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0));
+        let instance = ModelInstance::from(&model);
+        let col3 = instance.col3;
+        assert_eq!((col3.d0, col3.d1, col3.d2, col3.d3), (1.0, 2.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn update_counts_uploaded_instances() {
+        let gl = mock_gl::load();
+        let mut instances = InstanceBuffer::new(&gl);
+        assert_eq!(instances.count(), 0);
+
+        instances.update(&[na::Matrix4::identity(); 5]);
+        assert_eq!(instances.count(), 5);
+        instances.update(&[na::Matrix4::identity(); 2]);
+        assert_eq!(instances.count(), 2);
+    }
+}
@@ -0,0 +1,481 @@
+//! Stand-in OpenGL functions for unit tests, so GL wrappers can run without a context.
+//!
+//! Each test thread has its own state: generated objects get increasing names, deleted
+//! objects are counted by kind, and queries answer from values the test sets up.
+
+use super::DebugLines;
+use crate::resources::Resources;
+use gl::types::*;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::os::raw::c_void;
+use std::path::Path;
+
+pub struct State {
+    next_name: GLuint,
+    /// Deleted objects by kind: "buffer", "vertex array", "texture", "framebuffer",
+    /// "renderbuffer", "shader" and "program".
+    pub deleted: BTreeMap<&'static str, usize>,
+    /// Errors returned by `glGetError`, oldest first.
+    pub errors: VecDeque<GLenum>,
+    /// Values returned by `glGetIntegerv` and `glGetFramebufferAttachmentParameteriv`.
+    pub integers: BTreeMap<GLenum, GLint>,
+    /// Info log of failing shader compiles; compiling succeeds when `None`.
+    pub compile_log: Option<String>,
+    /// Uniform names `glGetUniformLocation` does not find.
+    pub missing_uniforms: BTreeSet<String>,
+    /// Capabilities currently enabled with `glEnable`.
+    pub enabled: BTreeSet<GLenum>,
+    pub depth_mask: bool,
+    /// Source RGB, destination RGB, source alpha and destination alpha factors.
+    pub blend_func: [GLenum; 4],
+    /// Internal format of the last `glTexImage2D` upload.
+    pub tex_image_format: Option<GLint>,
+    /// Vertex counts of draw calls, in order.
+    pub draws: Vec<GLsizei>,
+    /// Matrices uploaded with `glUniformMatrix4fv`, in order.
+    pub matrices: Vec<[GLfloat; 16]>,
+    /// Contents of the last buffer mapped with `glMapBufferRange`.
+    pub mapped: Vec<u8>,
+}
+
+impl State {
+    fn new() -> State {
+        let mut integers = BTreeMap::new();
+        integers.insert(gl::MAX_TEXTURE_SIZE, 16384);
+        integers.insert(gl::UNPACK_ALIGNMENT, 4);
+        State {
+            next_name: 1,
+            deleted: BTreeMap::new(),
+            errors: VecDeque::new(),
+            integers,
+            compile_log: None,
+            missing_uniforms: BTreeSet::new(),
+            enabled: BTreeSet::new(),
+            depth_mask: true,
+            blend_func: [gl::ONE, gl::ZERO, gl::ONE, gl::ZERO],
+            tex_image_format: None,
+            draws: Vec::new(),
+            matrices: Vec::new(),
+            mapped: Vec::new(),
+        }
+    }
+
+    /// Number of deleted objects of a kind.
+    pub fn deleted(&self, kind: &str) -> usize {
+        self.deleted.get(kind).cloned().unwrap_or(0)
+    }
+
+    fn name(&mut self) -> GLuint {
+        let name = self.next_name;
+        self.next_name += 1;
+        name
+    }
+
+    fn delete(&mut self, kind: &'static str, n: usize) {
+        *self.deleted.entry(kind).or_insert(0) += n;
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::new());
+}
+
+/// Load mock GL functions, resetting the state of the current thread.
+pub fn load() -> gl::Gl {
+    STATE.with(|state| *state.borrow_mut() = State::new());
+    gl::Gl::load_with(function)
+}
+
+/// Resources from the `assets` directory of the repository.
+pub fn test_resources() -> Resources {
+    Resources::from_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")).unwrap()
+}
+
+/// Load mock GL functions and debug lines with shaders from the repository assets.
+pub fn test_debug_lines() -> (gl::Gl, DebugLines) {
+    let gl = load();
+    let debug_lines = DebugLines::new(&gl, &test_resources()).unwrap();
+    (gl, debug_lines)
+}
+
+/// Inspect or set up the mock state of the current thread.
+pub fn with_state<R, F: FnOnce(&mut State) -> R>(f: F) -> R {
+    STATE.with(|state| f(&mut state.borrow_mut()))
+}
+
+fn function(name: &str) -> *const c_void {
+    match name {
+        "glGenBuffers" | "glGenVertexArrays" | "glGenTextures" | "glGenFramebuffers"
+        | "glGenRenderbuffers" => gen_names as *const c_void,
+        "glDeleteBuffers" => delete_buffers as *const c_void,
+        "glDeleteVertexArrays" => delete_vertex_arrays as *const c_void,
+        "glDeleteTextures" => delete_textures as *const c_void,
+        "glDeleteFramebuffers" => delete_framebuffers as *const c_void,
+        "glDeleteRenderbuffers" => delete_renderbuffers as *const c_void,
+        "glCreateShader" => create_shader as *const c_void,
+        "glCreateProgram" => create_program as *const c_void,
+        "glDeleteShader" => delete_shader as *const c_void,
+        "glDeleteProgram" => delete_program as *const c_void,
+        "glShaderSource" => shader_source as *const c_void,
+        "glCompileShader"
+        | "glLinkProgram"
+        | "glUseProgram"
+        | "glBindVertexArray"
+        | "glEnableVertexAttribArray"
+        | "glActiveTexture"
+        | "glGenerateMipmap"
+        | "glClear" => ignore_1 as *const c_void,
+        "glAttachShader"
+        | "glDetachShader"
+        | "glBindBuffer"
+        | "glBindTexture"
+        | "glBindFramebuffer"
+        | "glBindRenderbuffer"
+        | "glVertexAttribDivisor"
+        | "glPolygonMode"
+        | "glUniform1i" => ignore_2 as *const c_void,
+        "glGetShaderiv" | "glGetProgramiv" => get_object_iv as *const c_void,
+        "glGetShaderInfoLog" | "glGetProgramInfoLog" => get_info_log as *const c_void,
+        "glGetUniformLocation" => get_uniform_location as *const c_void,
+        "glUniform1f" => uniform_1f as *const c_void,
+        "glUniform3f" => uniform_3f as *const c_void,
+        "glUniformMatrix4fv" => uniform_matrix_4fv as *const c_void,
+        "glBufferData" => buffer_data as *const c_void,
+        "glMapBufferRange" => map_buffer_range as *const c_void,
+        "glUnmapBuffer" => unmap_buffer as *const c_void,
+        "glVertexAttribPointer" => vertex_attrib_pointer as *const c_void,
+        "glVertexAttribIPointer" | "glVertexAttribLPointer" => {
+            vertex_attrib_i_pointer as *const c_void
+        }
+        "glEnable" => enable as *const c_void,
+        "glDisable" => disable as *const c_void,
+        "glIsEnabled" => is_enabled as *const c_void,
+        "glDepthMask" => depth_mask as *const c_void,
+        "glBlendFunc" => blend_func as *const c_void,
+        "glBlendFuncSeparate" => blend_func_separate as *const c_void,
+        "glLineWidth" => line_width as *const c_void,
+        "glViewport" => viewport as *const c_void,
+        "glClearColor" => clear_color as *const c_void,
+        "glPixelStorei" => pixel_store_i as *const c_void,
+        "glGetIntegerv" => get_integer_v as *const c_void,
+        "glGetFloatv" => get_float_v as *const c_void,
+        "glGetBooleanv" => get_boolean_v as *const c_void,
+        "glGetError" => get_error as *const c_void,
+        "glGetFramebufferAttachmentParameteriv" => {
+            get_framebuffer_attachment_parameter_iv as *const c_void
+        }
+        "glTexImage2D" => tex_image_2d as *const c_void,
+        "glTexParameteri" => tex_parameter_i as *const c_void,
+        "glDrawArrays" => draw_arrays as *const c_void,
+        "glDrawElements" => draw_elements as *const c_void,
+        "glDrawElementsInstanced" => draw_elements_instanced as *const c_void,
+        "glRenderbufferStorage" => renderbuffer_storage as *const c_void,
+        "glFramebufferRenderbuffer" => framebuffer_renderbuffer as *const c_void,
+        "glCheckFramebufferStatus" => check_framebuffer_status as *const c_void,
+        "glReadPixels" => read_pixels as *const c_void,
+        _ => std::ptr::null(),
+    }
+}
+
+extern "system" fn gen_names(n: GLsizei, names: *mut GLuint) {
+    with_state(|state| {
+        for i in 0..n as usize {
+            unsafe { *names.add(i) = state.name() };
+        }
+    });
+}
+
+macro_rules! delete_names {
+    ($name:ident, $kind:expr) => {
+        extern "system" fn $name(n: GLsizei, _names: *const GLuint) {
+            with_state(|state| state.delete($kind, n as usize));
+        }
+    };
+}
+
+delete_names!(delete_buffers, "buffer");
+delete_names!(delete_vertex_arrays, "vertex array");
+delete_names!(delete_textures, "texture");
+delete_names!(delete_framebuffers, "framebuffer");
+delete_names!(delete_renderbuffers, "renderbuffer");
+
+extern "system" fn create_shader(_kind: GLenum) -> GLuint {
+    with_state(|state| state.name())
+}
+
+extern "system" fn create_program() -> GLuint {
+    with_state(|state| state.name())
+}
+
+extern "system" fn delete_shader(_shader: GLuint) {
+    with_state(|state| state.delete("shader", 1));
+}
+
+extern "system" fn delete_program(_program: GLuint) {
+    with_state(|state| state.delete("program", 1));
+}
+
+extern "system" fn shader_source(
+    _shader: GLuint,
+    _count: GLsizei,
+    _source: *const *const GLchar,
+    _length: *const GLint,
+) {
+}
+
+extern "system" fn ignore_1(_a: GLuint) {}
+
+extern "system" fn ignore_2(_a: GLuint, _b: GLuint) {}
+
+/// Linking always succeeds; compiling fails with `compile_log` if it is set.
+extern "system" fn get_object_iv(_object: GLuint, pname: GLenum, params: *mut GLint) {
+    let value = with_state(|state| match (pname, &state.compile_log) {
+        (gl::COMPILE_STATUS, Some(_)) => 0,
+        (gl::COMPILE_STATUS, None) | (gl::LINK_STATUS, _) => 1,
+        (gl::INFO_LOG_LENGTH, Some(log)) => log.len() as GLint + 1,
+        _ => 0,
+    });
+    unsafe { *params = value };
+}
+
+extern "system" fn get_info_log(
+    _object: GLuint,
+    buf_size: GLsizei,
+    length: *mut GLsizei,
+    info_log: *mut GLchar,
+) {
+    let log = with_state(|state| state.compile_log.clone()).unwrap_or_default();
+    let len = log.len().min((buf_size.max(1) - 1) as usize);
+    unsafe {
+        std::ptr::copy_nonoverlapping(log.as_ptr() as *const GLchar, info_log, len);
+        *info_log.add(len) = 0;
+        if !length.is_null() {
+            *length = len as GLsizei;
+        }
+    }
+}
+
+extern "system" fn get_uniform_location(_program: GLuint, name: *const GLchar) -> GLint {
+    let name = unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy();
+    with_state(|state| {
+        if state.missing_uniforms.contains(name.as_ref()) {
+            -1
+        } else {
+            0
+        }
+    })
+}
+
+extern "system" fn uniform_1f(_location: GLint, _v0: GLfloat) {}
+
+extern "system" fn uniform_3f(_location: GLint, _v0: GLfloat, _v1: GLfloat, _v2: GLfloat) {}
+
+extern "system" fn uniform_matrix_4fv(
+    _location: GLint,
+    _count: GLsizei,
+    _transpose: GLboolean,
+    value: *const GLfloat,
+) {
+    let mut matrix = [0.0; 16];
+    unsafe { matrix.copy_from_slice(std::slice::from_raw_parts(value, 16)) };
+    with_state(|state| state.matrices.push(matrix));
+}
+
+extern "system" fn buffer_data(
+    _target: GLenum,
+    _size: GLsizeiptr,
+    _data: *const c_void,
+    _usage: GLenum,
+) {
+}
+
+extern "system" fn map_buffer_range(
+    _target: GLenum,
+    _offset: GLintptr,
+    length: GLsizeiptr,
+    _access: GLbitfield,
+) -> *mut c_void {
+    with_state(|state| {
+        state.mapped = vec![0; length as usize];
+        state.mapped.as_mut_ptr() as *mut c_void
+    })
+}
+
+extern "system" fn unmap_buffer(_target: GLenum) -> GLboolean {
+    gl::TRUE
+}
+
+extern "system" fn vertex_attrib_pointer(
+    _index: GLuint,
+    _size: GLint,
+    _kind: GLenum,
+    _normalized: GLboolean,
+    _stride: GLsizei,
+    _pointer: *const c_void,
+) {
+}
+
+extern "system" fn vertex_attrib_i_pointer(
+    _index: GLuint,
+    _size: GLint,
+    _kind: GLenum,
+    _stride: GLsizei,
+    _pointer: *const c_void,
+) {
+}
+
+extern "system" fn enable(cap: GLenum) {
+    with_state(|state| state.enabled.insert(cap));
+}
+
+extern "system" fn disable(cap: GLenum) {
+    with_state(|state| state.enabled.remove(&cap));
+}
+
+extern "system" fn is_enabled(cap: GLenum) -> GLboolean {
+    if with_state(|state| state.enabled.contains(&cap)) {
+        gl::TRUE
+    } else {
+        gl::FALSE
+    }
+}
+
+extern "system" fn depth_mask(flag: GLboolean) {
+    with_state(|state| state.depth_mask = flag == gl::TRUE);
+}
+
+extern "system" fn blend_func(sfactor: GLenum, dfactor: GLenum) {
+    with_state(|state| state.blend_func = [sfactor, dfactor, sfactor, dfactor]);
+}
+
+extern "system" fn blend_func_separate(
+    src_rgb: GLenum,
+    dst_rgb: GLenum,
+    src_alpha: GLenum,
+    dst_alpha: GLenum,
+) {
+    with_state(|state| state.blend_func = [src_rgb, dst_rgb, src_alpha, dst_alpha]);
+}
+
+extern "system" fn line_width(_width: GLfloat) {}
+
+extern "system" fn viewport(_x: GLint, _y: GLint, _width: GLsizei, _height: GLsizei) {}
+
+extern "system" fn clear_color(_r: GLfloat, _g: GLfloat, _b: GLfloat, _a: GLfloat) {}
+
+extern "system" fn pixel_store_i(pname: GLenum, param: GLint) {
+    with_state(|state| state.integers.insert(pname, param));
+}
+
+extern "system" fn get_integer_v(pname: GLenum, data: *mut GLint) {
+    let value = with_state(|state| match pname {
+        gl::BLEND_SRC_RGB => state.blend_func[0] as GLint,
+        gl::BLEND_DST_RGB => state.blend_func[1] as GLint,
+        gl::BLEND_SRC_ALPHA => state.blend_func[2] as GLint,
+        gl::BLEND_DST_ALPHA => state.blend_func[3] as GLint,
+        _ => state.integers.get(&pname).cloned().unwrap_or(0),
+    });
+    unsafe { *data = value };
+}
+
+/// Answers the aliased line width range; other queries yield zero.
+extern "system" fn get_float_v(pname: GLenum, data: *mut GLfloat) {
+    unsafe {
+        if pname == gl::ALIASED_LINE_WIDTH_RANGE {
+            *data = 1.0;
+            *data.add(1) = 8.0;
+        } else {
+            *data = 0.0;
+        }
+    }
+}
+
+extern "system" fn get_boolean_v(pname: GLenum, data: *mut GLboolean) {
+    let value = with_state(|state| pname == gl::DEPTH_WRITEMASK && state.depth_mask);
+    unsafe { *data = if value { gl::TRUE } else { gl::FALSE } };
+}
+
+extern "system" fn get_error() -> GLenum {
+    with_state(|state| state.errors.pop_front().unwrap_or(gl::NO_ERROR))
+}
+
+extern "system" fn get_framebuffer_attachment_parameter_iv(
+    _target: GLenum,
+    _attachment: GLenum,
+    pname: GLenum,
+    params: *mut GLint,
+) {
+    let value = with_state(|state| state.integers.get(&pname).cloned().unwrap_or(0));
+    unsafe { *params = value };
+}
+
+extern "system" fn tex_image_2d(
+    _target: GLenum,
+    _level: GLint,
+    internal_format: GLint,
+    _width: GLsizei,
+    _height: GLsizei,
+    _border: GLint,
+    _format: GLenum,
+    _kind: GLenum,
+    _pixels: *const c_void,
+) {
+    with_state(|state| state.tex_image_format = Some(internal_format));
+}
+
+extern "system" fn tex_parameter_i(_target: GLenum, _pname: GLenum, _param: GLint) {}
+
+extern "system" fn draw_arrays(_mode: GLenum, _first: GLint, count: GLsizei) {
+    with_state(|state| state.draws.push(count));
+}
+
+extern "system" fn draw_elements(
+    _mode: GLenum,
+    count: GLsizei,
+    _kind: GLenum,
+    _indices: *const c_void,
+) {
+    with_state(|state| state.draws.push(count));
+}
+
+extern "system" fn draw_elements_instanced(
+    _mode: GLenum,
+    count: GLsizei,
+    _kind: GLenum,
+    _indices: *const c_void,
+    _instance_count: GLsizei,
+) {
+    with_state(|state| state.draws.push(count));
+}
+
+extern "system" fn renderbuffer_storage(
+    _target: GLenum,
+    _internal_format: GLenum,
+    _width: GLsizei,
+    _height: GLsizei,
+) {
+}
+
+extern "system" fn framebuffer_renderbuffer(
+    _target: GLenum,
+    _attachment: GLenum,
+    _renderbuffer_target: GLenum,
+    _renderbuffer: GLuint,
+) {
+}
+
+extern "system" fn check_framebuffer_status(_target: GLenum) -> GLenum {
+    gl::FRAMEBUFFER_COMPLETE
+}
+
+extern "system" fn read_pixels(
+    _x: GLint,
+    _y: GLint,
+    _width: GLsizei,
+    _height: GLsizei,
+    _format: GLenum,
+    _kind: GLenum,
+    _pixels: *mut c_void,
+) {
+}
@@ -2,10 +2,20 @@ pub mod buffer;
 mod color_buffer;
 pub mod data;
 mod debug_lines;
+mod instance_buffer;
+#[cfg(test)]
+pub mod mock_gl;
 mod shader;
+pub mod srgb;
+mod texture;
 mod viewport;
 
 pub use self::color_buffer::ColorBuffer;
-pub use self::debug_lines::{DebugLines, RayMarker};
+pub use self::debug_lines::{
+    clamp_line_width, trail_alpha, DebugLines, PointMarker, Polyline, PolylineBuilder, RayMarker,
+    TrailLine,
+};
+pub use self::instance_buffer::{InstanceBuffer, ModelInstance, INSTANCE_FIRST_LOCATION};
 pub use self::shader::{Error, Program, Shader};
-pub use self::viewport::Viewport;
+pub use self::texture::{Error as TextureError, Texture};
+pub use self::viewport::{scale_factor, Viewport};
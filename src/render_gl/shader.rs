@@ -1,4 +1,4 @@
-use crate::resources::{self, Resources};
+use crate::resources::{self, Resources, SourceFile};
 use gl;
 use nalgebra as na;
 use std;
@@ -51,6 +51,21 @@ impl Program {
         })
     }
 
+    /// Recompile and relink program from the same resources it was loaded from.
+    ///
+    /// Uniform locations may change after reload, so `locations` resolves them from the new
+    /// program before it replaces the current one. If compiling, linking or resolving fails,
+    /// the current program is kept.
+    pub fn reload<T, F>(&mut self, res: &Resources, locations: F) -> Result<T, Error>
+    where
+        F: FnOnce(&Program) -> Result<T, Error>,
+    {
+        let program = Program::from_res(&self.gl, res, &self.name)?;
+        let locations = locations(&program)?;
+        *self = program;
+        Ok(locations)
+    }
+
     pub fn from_shaders(name: &str, gl: &gl::Gl, shaders: &[Shader]) -> Result<Program, String> {
         let program_id = unsafe { gl.CreateProgram() };
 
@@ -146,6 +161,18 @@ impl Program {
             self.gl.Uniform3f(location, value.x, value.y, value.z);
         }
     }
+
+    pub fn set_uniform_1i(&self, location: i32, value: i32) {
+        unsafe {
+            self.gl.Uniform1i(location, value);
+        }
+    }
+
+    pub fn set_uniform_1f(&self, location: i32, value: f32) {
+        unsafe {
+            self.gl.Uniform1f(location, value);
+        }
+    }
 }
 
 impl Drop for Program {
@@ -172,14 +199,18 @@ impl Shader {
             .map(|&(_, kind)| kind)
             .ok_or_else(|| Error::CanNotDetermineShaderTypeForResource { name: name.into() })?;
 
-        let source = res.load_cstring(name).map_err(|e| Error::ResourceLoad {
-            name: name.into(),
-            inner: e,
-        })?;
-
-        Shader::from_source(gl, &source, shader_kind).map_err(|message| Error::CompileError {
-            name: name.into(),
-            message,
+        let source = res
+            .load_shader_source(name)
+            .map_err(|e| Error::ResourceLoad {
+                name: name.into(),
+                inner: e,
+            })?;
+
+        Shader::from_source(gl, &source.source, shader_kind).map_err(|message| {
+            Error::CompileError {
+                name: name.into(),
+                message: annotate_info_log(&message, &source.files),
+            }
         })
     }
 
@@ -252,6 +283,60 @@ fn shader_from_source(
     Ok(id)
 }
 
+/// Prefix info log lines with the file name and line number reported by the driver,
+/// and follow each of them with the offending source line.
+///
+/// The source string number in driver messages is an index into `files`.
+fn annotate_info_log(log: &str, files: &[SourceFile]) -> String {
+    let main_name = files.first().map(|f| f.name.as_str()).unwrap_or("");
+    let mut result = String::new();
+
+    for log_line in log.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        result.push('\n');
+
+        match parse_info_log_location(log_line) {
+            Some((file_index, line_number)) => {
+                let file = files.get(file_index);
+                let name = file.map(|f| f.name.as_str()).unwrap_or(main_name);
+                result.push_str(&format!("{}:{}: {}", name, line_number, log_line));
+
+                if let Some(text) = file.and_then(|f| {
+                    line_number
+                        .checked_sub(1)
+                        .and_then(|index| f.text.lines().nth(index))
+                }) {
+                    result.push_str(&format!("\n    | {}", text.trim_end()));
+                }
+            }
+            None => result.push_str(&format!("{}: {}", main_name, log_line)),
+        }
+    }
+
+    result
+}
+
+/// Find source string number and line number in driver messages such as
+/// `0:12(5): error`, `0(12) : error` or `ERROR: 0:12: error`.
+fn parse_info_log_location(log_line: &str) -> Option<(usize, usize)> {
+    let mut rest = log_line;
+    for prefix in &["ERROR:", "WARNING:"] {
+        if rest.to_uppercase().starts_with(prefix) {
+            rest = rest[prefix.len()..].trim_start();
+        }
+    }
+
+    let index_len = rest.find(|c: char| !c.is_ascii_digit())?;
+    let file_index = rest[..index_len].parse().ok()?;
+    let rest = &rest[index_len..];
+
+    let rest = rest.strip_prefix(':').or_else(|| rest.strip_prefix('('))?;
+    let line_len = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+
+    Some((file_index, rest[..line_len].parse().ok()?))
+}
+
 fn create_whitespace_cstring_with_len(len: usize) -> CString {
     // allocate buffer of correct size
     let mut buffer: Vec<u8> = Vec::with_capacity(len + 1);
@@ -260,3 +345,74 @@ fn create_whitespace_cstring_with_len(len: usize) -> CString {
     // convert buffer to CString
     unsafe { CString::from_vec_unchecked(buffer) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn programs_and_shaders_are_deleted_on_drop() {
+        let gl = mock_gl::load();
+        let source = CString::new("void main() {}").unwrap();
+        let shaders = vec![
+            Shader::from_vert_source(&gl, &source).unwrap(),
+            Shader::from_frag_source(&gl, &source).unwrap(),
+        ];
+        let program = Program::from_shaders("test", &gl, &shaders).unwrap();
+        drop(shaders);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("shader")), 2);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("program")), 0);
+        drop(program);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("program")), 1);
+    }
+
+    #[test]
+    fn failed_uniform_lookup_keeps_current_program() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let mut program = Program::from_res(&gl, &res, "shaders/render_gl/debug_lines").unwrap();
+        let id = program.id();
+        mock_gl::with_state(|s| s.missing_uniforms.insert("ViewProjection".into()));
+
+        let result = program.reload(&res, |p| p.get_uniform_location("ViewProjection"));
+        assert!(result.is_err());
+        assert_eq!(program.id(), id);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("program")), 1);
+
+        mock_gl::with_state(|s| s.missing_uniforms.clear());
+        program
+            .reload(&res, |p| p.get_uniform_location("ViewProjection"))
+            .unwrap();
+        assert_ne!(program.id(), id);
+    }
+
+    #[test]
+    fn compile_error_names_resource_and_quotes_line() {
+        let root = std::env::temp_dir().join(format!("r3d3-shader-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("shaders")).unwrap();
+        std::fs::write(
+            root.join("shaders/broken.frag"),
+            "#version 330 core\nvoid main() {\n    oops;\n}\n",
+        )
+        .unwrap();
+        let res = Resources::from_dir(&root).unwrap();
+        let gl = mock_gl::load();
+        mock_gl::with_state(|s| s.compile_log = Some("0:3(5): error: `oops' undeclared".into()));
+
+        let message = match Shader::from_res(&gl, &res, "shaders/broken.frag") {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("broken shader compiled"),
+        };
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(message.contains("shaders/broken.frag:3:"), "{}", message);
+        assert!(
+            message
+                .lines()
+                .any(|l| l.trim_start().starts_with('|') && l.contains("oops;")),
+            "{}",
+            message
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use nalgebra as na;
+
+/// Convert one sRGB encoded channel in range [0, 1] to linear intensity.
+///
+/// This is what the GPU does when sampling an sRGB texture.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert linear intensity in range [0, 1] to sRGB encoding.
+///
+/// This is what the GPU does when writing to the framebuffer with `FRAMEBUFFER_SRGB` enabled.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert color picked in sRGB (as in most color pickers) to linear, for lighting and blending.
+pub fn color_to_linear(color: &na::Vector3<f32>) -> na::Vector3<f32> {
+    color.map(srgb_to_linear)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_to_srgb_matches_known_values_and_inverts() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+        // middle gray in linear light is encoded much brighter
+        assert!((linear_to_srgb(0.5) - 0.7354).abs() < 1e-4);
+        assert!((linear_to_srgb(0.002) - 0.002 * 12.92).abs() < 1e-7);
+
+        for i in 0..=100 {
+            let value = i as f32 / 100.0;
+            assert!((srgb_to_linear(linear_to_srgb(value)) - value).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use gl;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Unsupported texture size {}x{}", width, height)]
+    UnsupportedSize { width: u32, height: u32 },
+}
+
+/// 2D texture with mipmaps and trilinear filtering.
+pub struct Texture {
+    gl: gl::Gl,
+    id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    /// Upload RGBA image and generate mipmaps. Sizes do not need to be powers of two.
+    pub fn from_image(gl: &gl::Gl, img: &image::RgbaImage) -> Result<Texture, Error> {
+        Texture::upload(gl, img, gl::RGBA)
+    }
+
+    /// Upload RGBA image with sRGB encoded colors, which are sampled as linear values.
+    pub fn from_srgb_image(gl: &gl::Gl, img: &image::RgbaImage) -> Result<Texture, Error> {
+        Texture::upload(gl, img, gl::SRGB8_ALPHA8)
+    }
+
+    fn upload(
+        gl: &gl::Gl,
+        img: &image::RgbaImage,
+        internal_format: gl::types::GLenum,
+    ) -> Result<Texture, Error> {
+        let (width, height) = img.dimensions();
+
+        let mut max_size: gl::types::GLint = 0;
+        unsafe {
+            gl.GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+        }
+        if width == 0 || height == 0 || width > max_size as u32 || height > max_size as u32 {
+            return Err(Error::UnsupportedSize { width, height });
+        }
+
+        let mut id: gl::types::GLuint = 0;
+        unsafe {
+            gl.GenTextures(1, &mut id);
+            gl.BindTexture(gl::TEXTURE_2D, id);
+
+            // rows of non-power-of-two images are not necessarily aligned
+            let mut unpack_alignment: gl::types::GLint = 4;
+            gl.GetIntegerv(gl::UNPACK_ALIGNMENT, &mut unpack_alignment);
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl.TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as gl::types::GLint,
+                width as gl::types::GLsizei,
+                height as gl::types::GLsizei,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                img.as_ptr() as *const gl::types::GLvoid,
+            );
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment);
+            gl.GenerateMipmap(gl::TEXTURE_2D);
+
+            let params = [
+                (gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::REPEAT),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+            ];
+            for &(name, value) in params.iter() {
+                gl.TexParameteri(gl::TEXTURE_2D, name, value as gl::types::GLint);
+            }
+            gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+        crate::debug::check_gl_error(gl, "texture upload");
+
+        Ok(Texture {
+            gl: gl.clone(),
+            id,
+            width,
+            height,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Bind texture to specified texture unit.
+    pub fn bind_at(&self, unit: u32) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn unbind_at(&self, unit: u32) {
+        unsafe {
+            self.gl.ActiveTexture(gl::TEXTURE0 + unit);
+            self.gl.BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn texture_is_deleted_on_drop() {
+        let gl = mock_gl::load();
+        let texture = Texture::from_image(&gl, &image::RgbaImage::new(2, 2)).unwrap();
+        assert_eq!(mock_gl::with_state(|s| s.deleted("texture")), 0);
+        drop(texture);
+        assert_eq!(mock_gl::with_state(|s| s.deleted("texture")), 1);
+    }
+
+    #[test]
+    fn upload_restores_unpack_alignment() {
+        let gl = mock_gl::load();
+        let _texture = Texture::from_image(&gl, &image::RgbaImage::new(3, 5)).unwrap();
+        assert_eq!(
+            mock_gl::with_state(|s| s.integers[&gl::UNPACK_ALIGNMENT]),
+            4
+        );
+    }
+}
@@ -1,5 +1,16 @@
 use gl;
 
+/// Pixels per point of a window, 1.0 unless the display is high-DPI.
+///
+/// Mouse events are in points, so their positions are multiplied by this to get pixels.
+pub fn scale_factor(window_size: (u32, u32), drawable_size: (u32, u32)) -> f32 {
+    if window_size.0 == 0 {
+        1.0
+    } else {
+        drawable_size.0 as f32 / window_size.0 as f32
+    }
+}
+
 pub struct Viewport {
     pub x: i32,
     pub y: i32,
@@ -17,9 +28,35 @@ impl Viewport {
         self.h = h;
     }
 
+    /// Current width and height in pixels.
+    pub fn size(&self) -> (i32, i32) {
+        (self.w, self.h)
+    }
+
     pub fn set_used(&self, gl: &gl::Gl) {
         unsafe {
             gl.Viewport(self.x, self.y, self.w, self.h);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_follows_resize() {
+        let mut viewport = Viewport::for_window(800, 600);
+        assert_eq!(viewport.size(), (800, 600));
+
+        viewport.update_size(1024, 768);
+        assert_eq!(viewport.size(), (1024, 768));
+    }
+
+    #[test]
+    fn high_dpi_window_has_more_pixels_than_points() {
+        assert_eq!(scale_factor((800, 600), (1600, 1200)), 2.0);
+        assert_eq!(scale_factor((800, 600), (800, 600)), 1.0);
+        assert_eq!(scale_factor((0, 0), (0, 0)), 1.0);
+    }
+}
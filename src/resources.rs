@@ -1,3 +1,4 @@
+use image;
 use std::ffi;
 use std::fs;
 use std::io::{self, Read};
@@ -7,10 +8,40 @@ use std::path::{Path, PathBuf};
 pub enum Error {
     #[fail(display = "I/O error")]
     Io(#[cause] io::Error),
+    #[fail(display = "Failed to read {}", name)]
+    Read {
+        name: String,
+        #[cause]
+        inner: io::Error,
+    },
+    #[fail(display = "{} is not valid UTF-8 after byte {}", name, valid_up_to)]
+    InvalidUtf8 {
+        name: String,
+        valid_up_to: usize,
+        #[cause]
+        inner: std::string::FromUtf8Error,
+    },
     #[fail(display = "Failed to read CString from file that contains 0")]
     FileContainsNil,
     #[fail(display = "Failed get executable path")]
     FailedToGetExePath,
+    #[fail(display = "Assets directory not found at {}", path)]
+    AssetsDirNotFound { path: String },
+    #[fail(display = "Failed to decode image")]
+    Image(#[cause] image::ImageError),
+    #[fail(display = "Unsupported image format of {}: {}", name, reason)]
+    UnsupportedImageFormat { name: String, reason: String },
+    #[fail(display = "Failed to include {} from {}", name, included_from)]
+    Include {
+        name: String,
+        included_from: String,
+        #[cause]
+        inner: io::Error,
+    },
+    #[fail(display = "Malformed #include directive in {} at line {}", name, line)]
+    MalformedInclude { name: String, line: usize },
+    #[fail(display = "Circular #include: {}", chain)]
+    IncludeCycle { chain: String },
 }
 
 impl From<io::Error> for Error {
@@ -19,6 +50,26 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<image::ImageError> for Error {
+    fn from(other: image::ImageError) -> Self {
+        Error::Image(other)
+    }
+}
+
+/// Shader source with `#include` directives resolved.
+pub struct ShaderSource {
+    pub source: ffi::CString,
+    /// Files that make up the source, indexed by the GLSL source string number
+    /// used in emitted `#line` directives. The loaded file itself is at index 0.
+    pub files: Vec<SourceFile>,
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+#[derive(Clone)]
 pub struct Resources {
     root_path: PathBuf,
 }
@@ -29,8 +80,21 @@ impl Resources {
 
         let exe_path = exe_file_name.parent().ok_or(Error::FailedToGetExePath)?;
 
+        Resources::from_dir(&exe_path.join(rel_path))
+    }
+
+    /// Use assets from directory `root_path`, which must exist.
+    pub fn from_dir(root_path: &Path) -> Result<Resources, Error> {
+        if !root_path.is_dir() {
+            return Err(Error::AssetsDirNotFound {
+                path: root_path.to_string_lossy().into_owned(),
+            });
+        }
+
+        info!("Using assets from {}", root_path.display());
+
         Ok(Resources {
-            root_path: exe_path.join(rel_path),
+            root_path: root_path.into(),
         })
     }
 
@@ -39,11 +103,18 @@ impl Resources {
     }
 
     pub fn load_cstring(&self, resource_name: &str) -> Result<ffi::CString, Error> {
-        let mut file = fs::File::open(resource_name_to_path(&self.root_path, resource_name))?;
+        debug!("Loading {}", resource_name);
+        let read_error = |inner| Error::Read {
+            name: resource_name.into(),
+            inner,
+        };
+        let mut file = fs::File::open(resource_name_to_path(&self.root_path, resource_name))
+            .map_err(read_error)?;
 
         // allocate buffer of the same size as file
-        let mut buffer: Vec<u8> = Vec::with_capacity(file.metadata()?.len() as usize + 1);
-        file.read_to_end(&mut buffer)?;
+        let mut buffer: Vec<u8> =
+            Vec::with_capacity(file.metadata().map_err(read_error)?.len() as usize + 1);
+        file.read_to_end(&mut buffer).map_err(read_error)?;
 
         // check for nul byte
         if buffer.iter().find(|i| **i == 0).is_some() {
@@ -52,6 +123,171 @@ impl Resources {
 
         Ok(unsafe { ffi::CString::from_vec_unchecked(buffer) })
     }
+
+    pub fn load_string(&self, resource_name: &str) -> Result<String, Error> {
+        debug!("Loading {}", resource_name);
+        self.read_text(resource_name)
+    }
+
+    /// Read a text file, telling apart files that can not be read (missing, no permission)
+    /// from files that are not UTF-8.
+    fn read_text(&self, resource_name: &str) -> Result<String, Error> {
+        let bytes =
+            fs::read(resource_name_to_path(&self.root_path, resource_name)).map_err(|inner| {
+                Error::Read {
+                    name: resource_name.into(),
+                    inner,
+                }
+            })?;
+
+        String::from_utf8(bytes).map_err(|inner| Error::InvalidUtf8 {
+            name: resource_name.into(),
+            valid_up_to: inner.utf8_error().valid_up_to(),
+            inner,
+        })
+    }
+
+    /// Load shader source, recursively inlining `#include "file"` directives.
+    ///
+    /// Included file names are relative to the including file.
+    pub fn load_shader_source(&self, resource_name: &str) -> Result<ShaderSource, Error> {
+        let mut files = Vec::new();
+        let mut output = String::new();
+
+        self.inline_includes(resource_name, &mut Vec::new(), &mut files, &mut output)?;
+
+        Ok(ShaderSource {
+            source: ffi::CString::new(output).map_err(|_| Error::FileContainsNil)?,
+            files,
+        })
+    }
+
+    fn inline_includes(
+        &self,
+        resource_name: &str,
+        include_stack: &mut Vec<String>,
+        files: &mut Vec<SourceFile>,
+        output: &mut String,
+    ) -> Result<(), Error> {
+        if include_stack.iter().any(|name| name == resource_name) {
+            let mut chain = include_stack.clone();
+            chain.push(resource_name.into());
+            return Err(Error::IncludeCycle {
+                chain: chain.join(" -> "),
+            });
+        }
+
+        debug!("Loading shader source {}", resource_name);
+        let text = self.read_text(resource_name)?;
+
+        let file_index = files.len();
+        files.push(SourceFile {
+            name: resource_name.into(),
+            text: text.clone(),
+        });
+        include_stack.push(resource_name.into());
+
+        if file_index > 0 {
+            output.push_str(&format!("#line 1 {}\n", file_index));
+        }
+
+        for (i, line) in text.lines().enumerate() {
+            match parse_include_directive(line) {
+                None => {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                Some(None) => {
+                    return Err(Error::MalformedInclude {
+                        name: resource_name.into(),
+                        line: i + 1,
+                    });
+                }
+                Some(Some(include_name)) => {
+                    let include_name = relative_resource_name(resource_name, include_name);
+                    self.inline_includes(&include_name, include_stack, files, output)
+                        .map_err(|e| match e {
+                            Error::Read { inner, .. } => Error::Include {
+                                name: include_name.clone(),
+                                included_from: resource_name.into(),
+                                inner,
+                            },
+                            e => e,
+                        })?;
+                    // continue numbering from the next line of this file
+                    output.push_str(&format!("#line {} {}\n", i + 2, file_index));
+                }
+            }
+        }
+
+        include_stack.pop();
+
+        Ok(())
+    }
+
+    /// Load and decode image file into RGBA pixels.
+    pub fn load_image(&self, resource_name: &str) -> Result<image::RgbaImage, Error> {
+        debug!("Loading image {}", resource_name);
+        let bytes =
+            fs::read(resource_name_to_path(&self.root_path, resource_name)).map_err(|inner| {
+                Error::Read {
+                    name: resource_name.into(),
+                    inner,
+                }
+            })?;
+
+        decode_image(resource_name, &bytes)
+    }
+}
+
+/// Decode image file contents into RGBA pixels, using `name` in errors.
+///
+/// The format is detected from the contents; formats and color types the decoder does not
+/// handle are reported as `UnsupportedImageFormat`.
+pub fn decode_image(name: &str, bytes: &[u8]) -> Result<image::RgbaImage, Error> {
+    let unsupported = |reason: String| Error::UnsupportedImageFormat {
+        name: name.into(),
+        reason,
+    };
+
+    match image::load_from_memory(bytes) {
+        Ok(img) => Ok(img.to_rgba()),
+        Err(image::ImageError::UnsupportedError(reason)) => Err(unsupported(reason)),
+        Err(image::ImageError::UnsupportedColor(color)) => {
+            Err(unsupported(format!("color type {:?}", color)))
+        }
+        Err(e) => Err(Error::Image(e)),
+    }
+}
+
+/// Returns `None` if line is not an include directive, and `Some(None)` if it is malformed.
+fn parse_include_directive(line: &str) -> Option<Option<&str>> {
+    let rest = line.trim().strip_prefix("#include")?;
+    let rest = rest.trim();
+
+    if rest.len() < 2 || !rest.starts_with('"') || !rest.ends_with('"') {
+        return Some(None);
+    }
+
+    Some(Some(&rest[1..rest.len() - 1]))
+}
+
+/// Resolve resource name relative to the directory of another resource.
+fn relative_resource_name(base: &str, name: &str) -> String {
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop();
+
+    for part in name.split('/') {
+        match part {
+            "." | "" => (),
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    parts.join("/")
 }
 
 fn resource_name_to_path(root_dir: &Path, location: &str) -> PathBuf {
@@ -63,3 +299,126 @@ fn resource_name_to_path(root_dir: &Path, location: &str) -> PathBuf {
 
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assets directory in the system temp dir with the given files, removed on drop.
+    struct TempAssets(PathBuf);
+
+    impl TempAssets {
+        fn new(name: &str, files: &[(&str, &[u8])]) -> TempAssets {
+            let root = std::env::temp_dir().join(format!("r3d3-{}-{}", name, std::process::id()));
+            for &(file_name, contents) in files {
+                let path = resource_name_to_path(&root, file_name);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, contents).unwrap();
+            }
+            TempAssets(root)
+        }
+
+        fn resources(&self) -> Resources {
+            Resources::from_dir(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempAssets {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn missing_assets_dir_is_reported_with_path() {
+        match Resources::from_relative_exe_path(Path::new("no-such-assets")) {
+            Err(Error::AssetsDirNotFound { path }) => assert!(path.ends_with("no-such-assets")),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("found assets in a directory that does not exist"),
+        }
+    }
+
+    #[test]
+    fn include_is_inlined_with_line_directives() {
+        let assets = TempAssets::new(
+            "include",
+            &[
+                (
+                    "shaders/main.frag",
+                    b"#version 330 core\n#include \"lib/common.glsl\"\nvoid main() {}\n",
+                ),
+                (
+                    "shaders/lib/common.glsl",
+                    b"float halve(float x) { return x / 2.0; }\n",
+                ),
+            ],
+        );
+        let source = assets
+            .resources()
+            .load_shader_source("shaders/main.frag")
+            .unwrap();
+
+        assert_eq!(
+            source.source.to_str().unwrap(),
+            "#version 330 core\n#line 1 1\nfloat halve(float x) { return x / 2.0; }\n#line 3 0\nvoid main() {}\n"
+        );
+        let names: Vec<_> = source.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["shaders/main.frag", "shaders/lib/common.glsl"]);
+    }
+
+    #[test]
+    fn circular_include_is_an_error() {
+        let assets = TempAssets::new(
+            "include-cycle",
+            &[
+                ("shaders/a.glsl", b"#include \"b.glsl\"\n"),
+                ("shaders/b.glsl", b"#include \"a.glsl\"\n"),
+            ],
+        );
+        match assets.resources().load_shader_source("shaders/a.glsl") {
+            Err(Error::IncludeCycle { chain }) => {
+                assert_eq!(chain, "shaders/a.glsl -> shaders/b.glsl -> shaders/a.glsl")
+            }
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("circular include was accepted"),
+        }
+    }
+
+    /// 2x1 RGBA PNG with an opaque red and a half transparent blue pixel.
+    const TINY_PNG: [u8; 71] = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0xf4,
+        0x22, 0x7f, 0x8a, 0x00, 0x00, 0x00, 0x0e, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8,
+        0xcf, 0xc0, 0x00, 0x42, 0x0d, 0x00, 0x0f, 0x7a, 0x03, 0x7e, 0x77, 0xe9, 0x7f, 0x97, 0x00,
+        0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    #[test]
+    fn decodes_embedded_png() {
+        let img = decode_image("tiny.png", &TINY_PNG).unwrap();
+        assert_eq!(img.dimensions(), (2, 1));
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn unknown_image_format_is_unsupported() {
+        match decode_image("notes.txt", b"not an image") {
+            Err(Error::UnsupportedImageFormat { name, .. }) => assert_eq!(name, "notes.txt"),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("decoded text as an image"),
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported_with_file_name() {
+        let assets = TempAssets::new("utf8", &[("planets.ron", b"[(name: \"Caf\xe9\")]")]);
+        match assets.resources().load_string("planets.ron") {
+            Err(Error::InvalidUtf8 {
+                name, valid_up_to, ..
+            }) => assert_eq!((&*name, valid_up_to), ("planets.ron", 12)),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(text) => panic!("invalid UTF-8 was accepted as {:?}", text),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::orbit::KeplerOrbit;
+use nalgebra as na;
+
+/// Time between the two longitude samples used to detect retrograde motion, in days.
+const SAMPLE_INTERVAL_DAYS: f64 = 1.0;
+
+/// Ecliptic longitude of a direction in degrees, in range [0, 360).
+pub fn ecliptic_longitude(direction: &na::Vector3<f64>) -> f64 {
+    direction
+        .y
+        .atan2(direction.x)
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// Apparent ecliptic longitude of `body` as seen from `earth`, both heliocentric, in degrees.
+pub fn geocentric_longitude(body: &na::Point3<f64>, earth: &na::Point3<f64>) -> f64 {
+    ecliptic_longitude(&(body - earth))
+}
+
+/// Longitude decreased from `prev_lon` to `cur_lon` (degrees), taking the shorter way around.
+///
+/// Samples must be close enough that the body moved less than 180 degrees between them.
+pub fn is_retrograde(prev_lon: f64, cur_lon: f64) -> bool {
+    let change = (cur_lon - prev_lon + 180.0).rem_euclid(360.0) - 180.0;
+    change < 0.0
+}
+
+/// Body on `orbit` appears to move backwards when seen from Earth on `earth_orbit`.
+pub fn is_retrograde_at(orbit: &KeplerOrbit, earth_orbit: &KeplerOrbit, sim_days: f64) -> bool {
+    let longitude_at =
+        |days| geocentric_longitude(&orbit.position_at(days), &earth_orbit.position_at(days));
+
+    is_retrograde(
+        longitude_at(sim_days - SAMPLE_INTERVAL_DAYS),
+        longitude_at(sim_days),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decreasing_longitude_is_retrograde_across_the_wrap() {
+        let sequence = [10.0, 4.0, 359.0, 350.0];
+        for pair in sequence.windows(2) {
+            assert!(is_retrograde(pair[0], pair[1]));
+        }
+
+        assert!(!is_retrograde(355.0, 2.0));
+        assert!(!is_retrograde(120.0, 121.0));
+        assert!(!is_retrograde(120.0, 120.0));
+    }
+
+    #[test]
+    fn geocentric_longitude_is_measured_from_earth() {
+        let earth = na::Point3::new(1.0, 0.0, 0.0);
+        // the Sun seen from Earth is opposite to Earth seen from the Sun
+        assert!((geocentric_longitude(&na::Point3::origin(), &earth) - 180.0).abs() < 1e-9);
+        let body = na::Point3::new(1.0, -1.0, 0.3);
+        assert!((geocentric_longitude(&body, &earth) - 270.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+use std::f32::consts::PI;
+
+const SEGMENTS: u32 = 128;
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32_f32,
+    /// Distance across the ring from inner (0) to outer (1) edge, and angle fraction.
+    #[location = "1"]
+    uv: data::f32_f32,
+}
+
+/// Flat transparent annulus on the XY plane, centered at origin.
+pub struct Rings {
+    program: render_gl::Program,
+    program_view_projection_location: i32,
+    program_model_location: i32,
+    color_location: i32,
+    /// Ring color in linear space.
+    pub color: na::Vector3<f32>,
+    _vbo: buffer::ArrayBuffer,
+    _ebo: buffer::ElementArrayBuffer,
+    index_count: i32,
+    outer_radius: f32,
+    vao: buffer::VertexArray,
+}
+
+impl Rings {
+    pub fn new(
+        res: &Resources,
+        gl: &gl::Gl,
+        inner_radius: f32,
+        outer_radius: f32,
+    ) -> Result<Rings, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/rings")?;
+        let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
+        let program_model_location = program.get_uniform_location("Model")?;
+        let color_location = program.get_uniform_location("RingColor")?;
+
+        let (vbo_data, ebo_data) = generate_annulus(inner_radius, outer_radius, SEGMENTS);
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let ebo = buffer::ElementArrayBuffer::new(gl);
+        ebo.bind();
+        ebo.static_draw_data::<u32>(&ebo_data);
+        ebo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        ebo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vao.unbind();
+
+        ebo.unbind(); // do not unbind ebo until we finish with vao
+
+        Ok(Rings {
+            program,
+            program_view_projection_location,
+            program_model_location,
+            color_location,
+            color: render_gl::srgb::color_to_linear(&na::Vector3::new(0.85, 0.78, 0.6)),
+            _vbo: vbo,
+            _ebo: ebo,
+            index_count: ebo_data.len() as i32,
+            outer_radius: outer_radius.max(inner_radius.max(0.0)),
+            vao,
+        })
+    }
+
+    /// Center and radius of a sphere enclosing the rings, in model space.
+    pub fn bounding_sphere(&self) -> (na::Point3<f32>, f32) {
+        (na::Point3::origin(), self.outer_radius)
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (program_view_projection_location, program_model_location, color_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("ViewProjection")?,
+                    program.get_uniform_location("Model")?,
+                    program.get_uniform_location("RingColor")?,
+                ))
+            })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.program_model_location = program_model_location;
+        self.color_location = color_location;
+        Ok(())
+    }
+
+    /// Render blended rings, after all opaque geometry.
+    ///
+    /// Rings are tested against depth but do not write it. When several ringed bodies are visible,
+    /// render them back to front from the camera. Blend function and enable, depth mask and
+    /// culling state are restored.
+    pub fn render(&self, gl: &gl::Gl, vp_matrix: &na::Matrix4<f32>, model: &na::Matrix4<f32>) {
+        self.program.set_used();
+        self.program
+            .set_uniform_matrix4fv(self.program_view_projection_location, vp_matrix);
+        self.program
+            .set_uniform_matrix4fv(self.program_model_location, model);
+        self.program
+            .set_uniform_3f(self.color_location, &self.color);
+        self.vao.bind();
+
+        unsafe {
+            // rings are visible from both sides
+            let cull_face_enabled = gl.IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+            let mut blend_func: [gl::types::GLint; 4] = [0; 4];
+            let blend_params = [
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ];
+            for (value, &name) in blend_func.iter_mut().zip(blend_params.iter()) {
+                gl.GetIntegerv(name, value);
+            }
+            let mut depth_mask: gl::types::GLboolean = gl::TRUE;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+
+            gl.Disable(gl::CULL_FACE);
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl.DepthMask(gl::FALSE);
+
+            gl.DrawElements(
+                gl::TRIANGLES,      // mode
+                self.index_count,   // index vertex count
+                gl::UNSIGNED_INT,   // index type
+                ::std::ptr::null(), // pointer to indices (we are using ebo configured at vao creation)
+            );
+
+            gl.BlendFuncSeparate(
+                blend_func[0] as gl::types::GLenum,
+                blend_func[1] as gl::types::GLenum,
+                blend_func[2] as gl::types::GLenum,
+                blend_func[3] as gl::types::GLenum,
+            );
+            gl.DepthMask(depth_mask);
+            if !blend_enabled {
+                gl.Disable(gl::BLEND);
+            }
+            if cull_face_enabled {
+                gl.Enable(gl::CULL_FACE);
+            }
+        }
+    }
+}
+
+/// Generate vertices and triangle indices for annulus, with inner and outer vertex per segment.
+///
+/// The seam is duplicated so that angle fraction goes from 0 to 1.
+fn generate_annulus(
+    inner_radius: f32,
+    outer_radius: f32,
+    segments: u32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let inner_radius = inner_radius.max(0.0);
+    let outer_radius = outer_radius.max(inner_radius);
+
+    let mut vertices = Vec::with_capacity(((segments + 1) * 2) as usize);
+
+    for i in 0..=segments {
+        let t = i as f32 / segments as f32;
+        let (sin, cos) = (t * 2.0 * PI).sin_cos();
+
+        vertices.push(Vertex {
+            pos: (inner_radius * cos, inner_radius * sin, 0.0).into(),
+            uv: (0.0, t).into(),
+        });
+        vertices.push(Vertex {
+            pos: (outer_radius * cos, outer_radius * sin, 0.0).into(),
+            uv: (1.0, t).into(),
+        });
+    }
+
+    let mut indices = Vec::with_capacity((segments * 6) as usize);
+
+    for i in 0..segments {
+        let inner = i * 2;
+        let outer = inner + 1;
+        let next_inner = inner + 2;
+        let next_outer = inner + 3;
+
+        indices.extend_from_slice(&[inner, outer, next_outer, inner, next_outer, next_inner]);
+    }
+
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn annulus_vertices_lie_between_radii() {
+        let (vertices, indices) = generate_annulus(1.5, 2.5, 32);
+        assert_eq!(vertices.len(), 33 * 2);
+        assert_eq!(indices.len(), 32 * 6);
+
+        for pair in vertices.chunks(2) {
+            let (inner, outer) = (pair[0].pos, pair[1].pos);
+            let inner_radius = (inner.d0 * inner.d0 + inner.d1 * inner.d1).sqrt();
+            let outer_radius = (outer.d0 * outer.d0 + outer.d1 * outer.d1).sqrt();
+            assert!((inner_radius - 1.5).abs() < 1e-5);
+            assert!((outer_radius - 2.5).abs() < 1e-5);
+        }
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+    }
+
+    #[test]
+    fn render_restores_depth_mask_and_blend_func() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let rings = Rings::new(&res, &gl, 1.5, 2.5).unwrap();
+
+        unsafe {
+            gl.DepthMask(gl::FALSE);
+            gl.BlendFuncSeparate(gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        rings.render(&gl, &na::Matrix4::identity(), &na::Matrix4::identity());
+
+        mock_gl::with_state(|state| {
+            assert!(!state.depth_mask);
+            assert_eq!(
+                state.blend_func,
+                [gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA]
+            );
+            assert!(!state.enabled.contains(&gl::BLEND));
+            assert_eq!(state.draws, vec![(SEGMENTS * 6) as i32]);
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use crate::render_gl::Viewport;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to create screenshot directory {}", path)]
+    CreateDir {
+        path: String,
+        #[cause]
+        inner: io::Error,
+    },
+    #[fail(display = "Failed to write screenshot {}", path)]
+    Write {
+        path: String,
+        #[cause]
+        inner: io::Error,
+    },
+}
+
+/// Read current framebuffer and save it as timestamped PNG in specified directory.
+///
+/// Returns path of the written file.
+pub fn capture(gl: &gl::Gl, viewport: &Viewport, dir: &Path) -> Result<PathBuf, Error> {
+    let (width, height) = viewport.size();
+    let width = width.max(0) as usize;
+    let height = height.max(0) as usize;
+    let mut pixels = vec![0u8; width * height * 4];
+
+    unsafe {
+        gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl.ReadPixels(
+            viewport.x,
+            viewport.y,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut gl::types::GLvoid,
+        );
+    }
+
+    // OpenGL rows start at the bottom, PNG rows at the top
+    flip_vertically(&mut pixels, width, height, 4);
+
+    fs::create_dir_all(dir).map_err(|e| Error::CreateDir {
+        path: dir.to_string_lossy().into_owned(),
+        inner: e,
+    })?;
+
+    let path = dir.join(timestamped_file_name());
+    image::save_buffer(
+        &path,
+        &pixels,
+        width as u32,
+        height as u32,
+        image::ColorType::RGBA(8),
+    )
+    .map_err(|e| Error::Write {
+        path: path.to_string_lossy().into_owned(),
+        inner: e,
+    })?;
+
+    Ok(path)
+}
+
+/// Reverse order of pixel rows in place.
+pub fn flip_vertically(pixels: &mut [u8], width: usize, height: usize, bytes_per_pixel: usize) {
+    let row_len = width * bytes_per_pixel;
+
+    for y in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - 1 - y) * row_len);
+        top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+    }
+}
+
+fn timestamped_file_name() -> String {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!(
+        "screenshot-{}-{:03}.png",
+        since_epoch.as_secs(),
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_reverses_rows() {
+        // 2x3 image with one byte per pixel, odd height keeps the middle row
+        let mut pixels = vec![1, 2, 3, 4, 5, 6];
+        flip_vertically(&mut pixels, 2, 3, 1);
+        assert_eq!(pixels, [5, 6, 3, 4, 1, 2]);
+
+        let mut pixels: Vec<u8> = (0..16).collect();
+        flip_vertically(&mut pixels, 1, 4, 4);
+        assert_eq!(
+            pixels,
+            [12, 13, 14, 15, 8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]
+        );
+    }
+}
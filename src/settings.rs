@@ -0,0 +1,128 @@
+use crate::camera;
+use crate::cli;
+use crate::orbit::{DistanceScale, ReferenceFrame};
+use crate::sim_clock;
+use crate::trail;
+use std::f32::consts::PI;
+
+/// Runtime configuration of the viewer, set up from the command line and changed by key presses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Window title, followed by the simulation date.
+    pub title: String,
+    /// Vertical field of view in radians.
+    pub fov: f32,
+    /// Camera movement speed in scene units per second.
+    pub movement_speed: f32,
+    /// Multiplier of mouse rotation.
+    pub rotation_sensitivity: f32,
+    /// Simulated seconds per real second at start.
+    pub time_scale: f64,
+    /// Multiplier applied to body radii when rendering.
+    pub planet_scale: f32,
+    /// Orbit paths and trails are drawn.
+    pub show_orbits: bool,
+    /// Samples in orbit trails of bodies without their own length.
+    pub trail_length: usize,
+    pub show_aspect_grid: bool,
+    /// Crosshair is drawn at the center of the window.
+    pub show_reticle: bool,
+    pub wireframe: bool,
+    /// Frame rate is shown in the window title.
+    pub show_fps: bool,
+    /// Synchronize buffer swaps with display refresh.
+    pub vsync: bool,
+    /// Frame rate limit used when vsync is off.
+    pub max_fps: Option<u32>,
+    pub frame: ReferenceFrame,
+    pub distance_scale: DistanceScale,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            title: "R3D3".into(),
+            fov: PI / 2.0,
+            movement_speed: camera::DEFAULT_MOVEMENT_SPEED,
+            rotation_sensitivity: 1.0,
+            time_scale: sim_clock::DEFAULT_TIME_SCALE,
+            planet_scale: 1.0,
+            show_orbits: true,
+            trail_length: trail::DEFAULT_LENGTH,
+            show_aspect_grid: true,
+            show_reticle: false,
+            wireframe: false,
+            show_fps: false,
+            vsync: true,
+            max_fps: None,
+            frame: ReferenceFrame::Heliocentric,
+            distance_scale: DistanceScale::Linear,
+        }
+    }
+}
+
+impl Settings {
+    /// Default settings overridden by the command line options that were given.
+    ///
+    /// Benchmarks run without vsync or frame rate limit.
+    pub fn from_options(options: &cli::Options) -> Settings {
+        let defaults = Settings::default();
+        let bench = options.bench_frames.is_some();
+
+        Settings {
+            title: options
+                .title
+                .clone()
+                .unwrap_or_else(|| defaults.title.clone()),
+            movement_speed: options.movement_speed.unwrap_or(defaults.movement_speed),
+            rotation_sensitivity: options
+                .rotation_sensitivity
+                .unwrap_or(defaults.rotation_sensitivity),
+            trail_length: options.trail_length.unwrap_or(defaults.trail_length),
+            show_fps: options.show_fps,
+            vsync: options.vsync && !bench,
+            max_fps: options.max_fps.filter(|_| !bench),
+            ..defaults
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> cli::Options {
+        cli::Options::parse(args.iter().map(|&arg| String::from(arg))).unwrap()
+    }
+
+    #[test]
+    fn options_override_only_given_settings() {
+        assert_eq!(Settings::from_options(&options(&[])), Settings::default());
+
+        let settings = Settings::from_options(&options(&[
+            "--title",
+            "Sky",
+            "--speed",
+            "2.5",
+            "--trail",
+            "128",
+            "--fps",
+            "--max-fps",
+            "30",
+        ]));
+        assert_eq!(settings.title, "Sky");
+        assert_eq!(settings.movement_speed, 2.5);
+        assert_eq!(settings.trail_length, 128);
+        assert!(settings.show_fps);
+        assert_eq!(settings.max_fps, Some(30));
+        assert_eq!(settings.rotation_sensitivity, 1.0);
+        assert_eq!(settings.fov, PI / 2.0);
+    }
+
+    #[test]
+    fn benchmark_runs_without_frame_limits() {
+        let settings = Settings::from_options(&options(&["--bench", "100", "--max-fps", "30"]));
+        assert!(!settings.vsync);
+        assert_eq!(settings.max_fps, None);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::calendar::{self, CalendarDate};
+
+/// Simulation time, independent from the render frame delta.
+///
+/// Elapsed simulation seconds advance by real seconds multiplied by `time_scale`,
+/// unless the clock is paused. A negative `time_scale` runs the clock backwards.
+/// They are counted from the epoch, which is J2000 by default.
+pub struct SimClock {
+    elapsed: f64,
+    epoch_julian_day: f64,
+    time_scale: f64,
+    paused: bool,
+    /// Simulation seconds moved by one `step` while paused.
+    step: f64,
+}
+
+/// One day of simulation time per real second.
+pub const DEFAULT_TIME_SCALE: f64 = 86_400.0;
+
+/// One hour of simulation time per step while paused.
+pub const DEFAULT_STEP_SECONDS: f64 = 3600.0;
+
+/// Range of the time scale magnitude, in either direction.
+const MIN_TIME_SCALE: f64 = 1.0;
+const MAX_TIME_SCALE: f64 = 86_400.0 * 365.25 * 10.0;
+
+/// Clamp magnitude of `time_scale` to the allowed range, keeping its direction.
+fn clamp_scale(time_scale: f64) -> f64 {
+    time_scale.signum() * time_scale.abs().clamp(MIN_TIME_SCALE, MAX_TIME_SCALE)
+}
+
+impl SimClock {
+    pub fn new(time_scale: f64) -> SimClock {
+        SimClock {
+            elapsed: 0.0,
+            epoch_julian_day: calendar::J2000,
+            time_scale: clamp_scale(time_scale),
+            paused: false,
+            step: DEFAULT_STEP_SECONDS,
+        }
+    }
+
+    /// Advance simulation by real elapsed seconds.
+    pub fn advance(&mut self, real_delta: f64) {
+        if !self.paused {
+            self.elapsed += real_delta * self.time_scale;
+        }
+    }
+
+    /// Elapsed simulation seconds.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Start counting simulation time from specified Julian day.
+    pub fn set_epoch(&mut self, julian_day: f64) {
+        self.epoch_julian_day = julian_day;
+    }
+
+    /// Current simulation time as Julian day.
+    pub fn julian_day(&self) -> f64 {
+        self.epoch_julian_day + self.elapsed / 86_400.0
+    }
+
+    /// Current simulation time in days since J2000, the epoch of orbital elements.
+    pub fn days_since_j2000(&self) -> f64 {
+        self.julian_day() - calendar::J2000
+    }
+
+    /// Jump to midnight of specified calendar date.
+    pub fn set_date(&mut self, year: i32, month: u32, day: u32) -> Result<(), calendar::Error> {
+        let date = CalendarDate::from_ymd(year, month, day)?;
+        self.elapsed = (date.to_julian_day() - self.epoch_julian_day) * 86_400.0;
+        Ok(())
+    }
+
+    /// Current simulation time as calendar date.
+    pub fn date(&self) -> CalendarDate {
+        CalendarDate::from_julian_day(self.julian_day())
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Set simulation seconds per real second, clamped to a sane range.
+    ///
+    /// Negative values run the clock backwards.
+    pub fn set_scale(&mut self, time_scale: f64) {
+        self.time_scale = clamp_scale(time_scale);
+    }
+
+    /// Run the clock in the other direction at the same speed.
+    pub fn reverse(&mut self) {
+        self.time_scale = -self.time_scale;
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.time_scale < 0.0
+    }
+
+    pub fn speed_up(&mut self) {
+        self.set_scale(self.time_scale * 2.0);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.set_scale(self.time_scale / 2.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn step_seconds(&self) -> f64 {
+        self.step
+    }
+
+    /// Set simulation seconds moved by one step; negative values are taken as positive.
+    pub fn set_step_seconds(&mut self, step: f64) {
+        self.step = step.abs();
+    }
+
+    /// Move paused simulation one step forward or backward.
+    ///
+    /// Returns false and does nothing if the clock is running.
+    pub fn step(&mut self, forward: bool) -> bool {
+        if !self.paused {
+            return false;
+        }
+
+        if forward {
+            self.elapsed += self.step;
+        } else {
+            self.elapsed -= self.step;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paused_clock_does_not_advance() {
+        let mut clock = SimClock::new(DEFAULT_TIME_SCALE);
+        clock.advance(1.0);
+        assert_eq!(clock.elapsed(), DEFAULT_TIME_SCALE);
+
+        clock.toggle_pause();
+        clock.advance(1.0);
+        assert_eq!(clock.elapsed(), DEFAULT_TIME_SCALE);
+
+        clock.toggle_pause();
+        clock.advance(0.5);
+        assert_eq!(clock.elapsed(), DEFAULT_TIME_SCALE * 1.5);
+    }
+
+    #[test]
+    fn paused_clock_moves_by_whole_steps() {
+        let mut clock = SimClock::new(DEFAULT_TIME_SCALE);
+        assert!(!clock.step(true));
+        assert_eq!(clock.elapsed(), 0.0);
+
+        clock.toggle_pause();
+        assert!(clock.step(true));
+        assert_eq!(clock.elapsed(), DEFAULT_STEP_SECONDS);
+        assert!(clock.step(true));
+        assert_eq!(clock.elapsed(), 2.0 * DEFAULT_STEP_SECONDS);
+
+        clock.set_step_seconds(-60.0);
+        assert!(clock.step(false));
+        assert_eq!(clock.elapsed(), 2.0 * DEFAULT_STEP_SECONDS - 60.0);
+    }
+
+    #[test]
+    fn negative_scale_brings_orbits_back_to_start() {
+        let earth = crate::orbit::KeplerOrbit {
+            semi_major: 1.0,
+            eccentricity: 0.0167,
+            inclination: 0.0,
+            lon_ascending: 0.0,
+            arg_periapsis: 1.796,
+            mean_anomaly_epoch: 6.240,
+            period: 365.256,
+            parent: None,
+            display_scale: 1.0,
+        };
+        let mut clock = SimClock::new(DEFAULT_TIME_SCALE);
+        clock.set_date(2024, 3, 20).unwrap();
+        let start_days = clock.days_since_j2000();
+        let start = earth.position_at(start_days);
+
+        for _ in 0..60 {
+            clock.advance(0.5);
+        }
+        assert!((clock.days_since_j2000() - start_days - 30.0).abs() < 1e-9);
+        assert!((earth.position_at(clock.days_since_j2000()) - start).norm() > 0.4);
+
+        clock.set_scale(-DEFAULT_TIME_SCALE);
+        assert!(clock.is_reversed());
+        for _ in 0..60 {
+            clock.advance(0.5);
+        }
+        assert!((clock.days_since_j2000() - start_days).abs() < 1e-9);
+        assert!((earth.position_at(clock.days_since_j2000()) - start).norm() < 1e-9);
+    }
+}
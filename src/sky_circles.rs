@@ -0,0 +1,98 @@
+use crate::render_gl::{DebugLines, Polyline};
+use nalgebra as na;
+
+/// Tilt of Earth's equator to the ecliptic (obliquity of the ecliptic), in degrees.
+pub const OBLIQUITY_DEGREES: f32 = 23.44;
+
+const CIRCLE_SEGMENTS: usize = 120;
+
+/// Rotation taking equatorial coordinates to ecliptic (world) coordinates.
+///
+/// Both planes cross at the equinoxes, on the X axis, and the celestial north pole leans
+/// towards ecliptic longitude 90 degrees.
+pub fn equator_rotation() -> na::UnitQuaternion<f32> {
+    na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), -OBLIQUITY_DEGREES.to_radians())
+}
+
+/// Ecliptic and celestial equator drawn as great circles around the origin.
+pub struct SkyCircles {
+    _ecliptic: Polyline,
+    _equator: Polyline,
+}
+
+impl SkyCircles {
+    pub fn new(debug_lines: &DebugLines, radius: f32) -> SkyCircles {
+        let ecliptic = debug_lines.colored_ellipse(
+            na::Point3::origin(),
+            radius,
+            radius,
+            0.0,
+            CIRCLE_SEGMENTS,
+            na::Vector4::new(0.9, 0.8, 0.3, 0.5),
+        );
+        // the equator is the ecliptic turned about the X axis, where both circles cross
+        let equator_tilt = equator_rotation().scaled_axis().x;
+        let equator = debug_lines.colored_ellipse(
+            na::Point3::origin(),
+            radius,
+            radius,
+            equator_tilt,
+            CIRCLE_SEGMENTS,
+            na::Vector4::new(0.3, 0.7, 0.9, 0.5),
+        );
+
+        SkyCircles {
+            _ecliptic: ecliptic,
+            _equator: equator,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::{mock_gl, ColorBuffer};
+
+    #[test]
+    fn june_solstice_sun_is_north_of_the_equator() {
+        // the Sun is at ecliptic longitude 90 degrees at the June solstice
+        let sun = na::Vector3::y();
+        let equatorial = equator_rotation().inverse() * sun;
+        let declination = equatorial.z.asin().to_degrees();
+        assert!((declination - OBLIQUITY_DEGREES).abs() < 1e-3);
+
+        let north_pole = equator_rotation() * na::Vector3::z();
+        assert!(north_pole.y > 0.0);
+        assert!((north_pole.z.acos().to_degrees() - OBLIQUITY_DEGREES).abs() < 1e-3);
+    }
+
+    #[test]
+    fn equator_points_are_ecliptic_points_rotated_about_x() {
+        let (gl, mut debug_lines) = mock_gl::test_debug_lines();
+        let _circles = SkyCircles::new(&debug_lines, 10.0);
+        debug_lines.render(&gl, &ColorBuffer::new(), &na::Matrix4::identity());
+
+        // 16 byte line points starting with three f32 coordinates, ecliptic first
+        let points: Vec<na::Vector3<f32>> = mock_gl::with_state(|state| {
+            state
+                .mapped
+                .chunks(16)
+                .map(|p| {
+                    let coord = |i: usize| {
+                        f32::from_ne_bytes([p[i * 4], p[i * 4 + 1], p[i * 4 + 2], p[i * 4 + 3]])
+                    };
+                    na::Vector3::new(coord(0), coord(1), coord(2))
+                })
+                .collect()
+        });
+        assert_eq!(points.len(), CIRCLE_SEGMENTS * 4);
+        let (ecliptic, equator) = points.split_at(CIRCLE_SEGMENTS * 2);
+
+        for (ecliptic_point, equator_point) in ecliptic.iter().zip(equator) {
+            assert!(ecliptic_point.z.abs() < 1e-5);
+            assert!((equator_rotation() * ecliptic_point - equator_point).norm() < 1e-4);
+        }
+        // half of the equator lies north of the ecliptic
+        assert!(equator.iter().any(|p| p.z > 3.9));
+    }
+}
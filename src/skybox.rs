@@ -0,0 +1,357 @@
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use nalgebra as na;
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32_f32,
+}
+
+/// Face file names, in the order of `TEXTURE_CUBE_MAP_POSITIVE_X + i` targets.
+const FACES: [&str; 6] = [
+    "right.png",
+    "left.png",
+    "top.png",
+    "bottom.png",
+    "front.png",
+    "back.png",
+];
+
+/// Color used for a face that could not be loaded.
+const FALLBACK_COLOR: [u8; 4] = [5, 5, 15, 255];
+
+/// Star field drawn as a cube map around the camera, behind everything else.
+pub struct Skybox {
+    gl: gl::Gl,
+    program: render_gl::Program,
+    program_view_location: i32,
+    program_projection_location: i32,
+    texture: gl::types::GLuint,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+}
+
+impl Skybox {
+    /// Load six cube map faces from `path` (for example `"skybox/"`).
+    ///
+    /// A face that is missing, can not be decoded or differs in size from the first loaded face
+    /// is replaced with a solid dark color. Returns `None` when no face loads at all, so the
+    /// clear color shows instead.
+    pub fn new(res: &Resources, gl: &gl::Gl, path: &str) -> Result<Option<Skybox>, failure::Error> {
+        let faces = match load_faces(res, path) {
+            Some(faces) => faces,
+            None => {
+                warn!(
+                    "No skybox face could be loaded from {}, skipping skybox",
+                    path
+                );
+                return Ok(None);
+            }
+        };
+
+        let program = render_gl::Program::from_res(gl, res, "shaders/skybox")?;
+        let program_view_location = program.get_uniform_location("View")?;
+        let program_projection_location = program.get_uniform_location("Projection")?;
+
+        let mut texture: gl::types::GLuint = 0;
+        unsafe {
+            gl.GenTextures(1, &mut texture);
+            gl.BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+        }
+
+        for (i, img) in faces.iter().enumerate() {
+            let (w, h) = img.dimensions();
+
+            unsafe {
+                gl.TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as gl::types::GLenum,
+                    0,
+                    gl::SRGB8_ALPHA8 as gl::types::GLint,
+                    w as gl::types::GLsizei,
+                    h as gl::types::GLsizei,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    img.as_ptr() as *const gl::types::GLvoid,
+                );
+            }
+        }
+
+        unsafe {
+            let params = [
+                (gl::TEXTURE_MIN_FILTER, gl::LINEAR),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE),
+            ];
+            for &(name, value) in params.iter() {
+                gl.TexParameteri(gl::TEXTURE_CUBE_MAP, name, value as gl::types::GLint);
+            }
+            gl.BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        let vbo_data = cube_vertices();
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Some(Skybox {
+            gl: gl.clone(),
+            program,
+            program_view_location,
+            program_projection_location,
+            texture,
+            _vbo: vbo,
+            vao,
+        }))
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (program_view_location, program_projection_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("View")?,
+                    program.get_uniform_location("Projection")?,
+                ))
+            })?;
+        self.program_view_location = program_view_location;
+        self.program_projection_location = program_projection_location;
+        Ok(())
+    }
+
+    /// Render skybox; should be done first, because it does not write depth.
+    ///
+    /// Stars are added on top of whatever is already in the color buffer, such as the background
+    /// gradient.
+    /// The `view` matrix should only contain camera rotation, so that stars stay at infinity.
+    pub fn render(&self, gl: &gl::Gl, view: &na::Matrix4<f32>, projection: &na::Matrix4<f32>) {
+        self.program.set_used();
+        self.program
+            .set_uniform_matrix4fv(self.program_view_location, view);
+        self.program
+            .set_uniform_matrix4fv(self.program_projection_location, projection);
+        self.vao.bind();
+
+        unsafe {
+            // we are looking at the cube from inside
+            let cull_face_enabled = gl.IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+            let mut blend_func: [gl::types::GLint; 4] = [0; 4];
+            let blend_params = [
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ];
+            for (value, &name) in blend_func.iter_mut().zip(blend_params.iter()) {
+                gl.GetIntegerv(name, value);
+            }
+            let mut depth_mask: gl::types::GLboolean = gl::TRUE;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+
+            gl.Disable(gl::CULL_FACE);
+            gl.DepthMask(gl::FALSE);
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::ONE, gl::ONE);
+
+            gl.BindTexture(gl::TEXTURE_CUBE_MAP, self.texture);
+            gl.DrawArrays(
+                gl::TRIANGLES, // mode
+                0,             // starting index in the enabled arrays
+                36,            // number of indices to be rendered
+            );
+            gl.BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+
+            gl.BlendFuncSeparate(
+                blend_func[0] as gl::types::GLenum,
+                blend_func[1] as gl::types::GLenum,
+                blend_func[2] as gl::types::GLenum,
+                blend_func[3] as gl::types::GLenum,
+            );
+            if !blend_enabled {
+                gl.Disable(gl::BLEND);
+            }
+            gl.DepthMask(depth_mask);
+            if cull_face_enabled {
+                gl.Enable(gl::CULL_FACE);
+            }
+        }
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteTextures(1, &self.texture);
+        }
+    }
+}
+
+/// Load the faces in `FACES` order, all sized like the first face that loads.
+///
+/// Cube map faces must share one size to make the texture complete, so failed or differently
+/// sized faces become `FALLBACK_COLOR` images of that size. Returns `None` if no face loads.
+fn load_faces(res: &Resources, path: &str) -> Option<Vec<image::RgbaImage>> {
+    let loaded: Vec<Option<image::RgbaImage>> = FACES
+        .iter()
+        .map(|face| {
+            let name = format!("{}{}", path, face);
+            match res.load_image(&name) {
+                Ok(img) => Some(img),
+                Err(e) => {
+                    warn!(
+                        "Failed to load skybox face {}, using solid color: {}",
+                        name,
+                        failure::Error::from(e)
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let (w, h) = loaded.iter().flatten().next()?.dimensions();
+
+    Some(
+        loaded
+            .into_iter()
+            .zip(FACES.iter())
+            .map(|(img, face)| {
+                if let Some(img) = img {
+                    if img.dimensions() == (w, h) {
+                        return img;
+                    }
+                    warn!(
+                        "Skybox face {}{} is {}x{} instead of {}x{}, using solid color",
+                        path,
+                        face,
+                        img.width(),
+                        img.height(),
+                        w,
+                        h
+                    );
+                }
+                image::RgbaImage::from_pixel(w, h, image::Rgba(FALLBACK_COLOR))
+            })
+            .collect(),
+    )
+}
+
+fn cube_vertices() -> Vec<Vertex> {
+    let corners = [
+        (-1.0, -1.0, -1.0),
+        (1.0, -1.0, -1.0),
+        (-1.0, 1.0, -1.0),
+        (1.0, 1.0, -1.0),
+        (-1.0, -1.0, 1.0),
+        (1.0, -1.0, 1.0),
+        (-1.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0),
+    ];
+
+    let faces: [usize; 36] = [
+        0, 2, 1, 1, 2, 3, // -z
+        4, 5, 6, 6, 5, 7, // +z
+        0, 1, 4, 4, 1, 5, // -y
+        2, 6, 3, 3, 6, 7, // +y
+        0, 4, 2, 2, 4, 6, // -x
+        1, 3, 5, 5, 3, 7, // +x
+    ];
+
+    faces
+        .iter()
+        .map(|&i| Vertex {
+            pos: corners[i].into(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn render_restores_blend_func_and_depth_mask() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let skybox = Skybox::new(&res, &gl, "skybox/").unwrap().unwrap();
+
+        unsafe {
+            gl.Enable(gl::BLEND);
+            gl.BlendFuncSeparate(gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+            gl.DepthMask(gl::FALSE);
+        }
+        skybox.render(&gl, &na::Matrix4::identity(), &na::Matrix4::identity());
+
+        mock_gl::with_state(|state| {
+            assert_eq!(
+                state.blend_func,
+                [gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA]
+            );
+            assert!(state.enabled.contains(&gl::BLEND));
+            assert!(!state.depth_mask);
+            assert_eq!(state.draws, vec![36]);
+        });
+    }
+
+    /// Assets directory in the system temp dir with 4x2 faces of the given names.
+    struct TempFaces(std::path::PathBuf);
+
+    impl TempFaces {
+        fn new(name: &str, faces: &[&str]) -> TempFaces {
+            let root = std::env::temp_dir().join(format!("r3d3-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(root.join("skybox")).unwrap();
+            for face in faces {
+                image::RgbaImage::from_pixel(4, 2, image::Rgba([200, 200, 200, 255]))
+                    .save(root.join("skybox").join(face))
+                    .unwrap();
+            }
+            TempFaces(root)
+        }
+    }
+
+    impl Drop for TempFaces {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn missing_face_gets_the_size_of_loaded_faces() {
+        let faces = TempFaces::new("skybox-missing-face", &FACES[..5]);
+        let res = Resources::from_dir(&faces.0).unwrap();
+
+        let loaded = load_faces(&res, "skybox/").unwrap();
+
+        assert_eq!(loaded.len(), 6);
+        for img in &loaded {
+            assert_eq!(img.dimensions(), (4, 2));
+        }
+        assert_eq!(loaded[0].get_pixel(0, 0).0, [200, 200, 200, 255]);
+        assert_eq!(loaded[5].get_pixel(3, 1).0, FALLBACK_COLOR);
+    }
+
+    #[test]
+    fn skybox_is_skipped_when_no_face_loads() {
+        let gl = mock_gl::load();
+        let faces = TempFaces::new("skybox-no-faces", &[]);
+        let res = Resources::from_dir(&faces.0).unwrap();
+
+        assert!(Skybox::new(&res, &gl, "skybox/").unwrap().is_none());
+    }
+}
@@ -0,0 +1,321 @@
+use crate::camera::TargetCamera;
+use crate::render_gl::{self, buffer, data};
+use crate::resources::Resources;
+use failure;
+use gl;
+use nalgebra as na;
+use std::f32::consts::PI;
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub(crate) struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32_f32,
+    #[location = "1"]
+    normal: data::f32_f32_f32,
+    #[location = "2"]
+    uv: data::f32_f32,
+}
+
+/// Number of meshes of decreasing detail, each with half the sectors and stacks of the previous.
+const LOD_LEVELS: usize = 3;
+
+/// Smallest on-screen diameter in pixels drawn with the most detailed mesh. Each following level
+/// is used down to half the size of the previous one.
+const FULL_DETAIL_PIXELS: f32 = 64.0;
+
+/// Level of detail to draw a sphere `pixel_size` pixels across with, 0 being the most detailed.
+pub fn lod_level(pixel_size: f32, levels: usize) -> usize {
+    let mut level = 0;
+    let mut threshold = FULL_DETAIL_PIXELS;
+    while level + 1 < levels && pixel_size < threshold {
+        level += 1;
+        threshold /= 2.0;
+    }
+    level
+}
+
+/// Sphere mesh at one level of detail.
+struct LodMesh {
+    _vbo: buffer::ArrayBuffer,
+    _ebo: buffer::ElementArrayBuffer,
+    index_count: i32,
+    vao: buffer::VertexArray,
+}
+
+impl LodMesh {
+    fn new(gl: &gl::Gl, sectors: u32, stacks: u32) -> LodMesh {
+        let (vbo_data, ebo_data) = generate_mesh(sectors, stacks);
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let ebo = buffer::ElementArrayBuffer::new(gl);
+        ebo.bind();
+        ebo.static_draw_data::<u32>(&ebo_data);
+        ebo.unbind();
+
+        // set up vertex array object
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        ebo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vao.unbind();
+
+        ebo.unbind(); // do not unbind ebo until we finish with vao
+
+        LodMesh {
+            _vbo: vbo,
+            _ebo: ebo,
+            index_count: ebo_data.len() as i32,
+            vao,
+        }
+    }
+}
+
+/// How a rendered sphere looks.
+pub struct Surface<'a> {
+    pub color: na::Vector3<f32>,
+    /// Equirectangular map replacing the color, with north at the top.
+    pub texture: Option<&'a render_gl::Texture>,
+    /// Emissive spheres (like the Sun) ignore lighting.
+    pub emissive: bool,
+}
+
+/// Unit UV sphere centered at origin, with Z axis going through the poles.
+///
+/// Meshes with fewer triangles are used when the sphere is small on screen.
+pub struct Sphere {
+    program: render_gl::Program,
+    program_view_projection_location: i32,
+    program_model_location: i32,
+    camera_pos_location: i32,
+    light_dir_location: i32,
+    light_color_location: i32,
+    surface_color_location: i32,
+    emissive_location: i32,
+    textured_location: i32,
+    /// Color of the directional light.
+    pub light_color: na::Vector3<f32>,
+    /// From the most to the least detailed.
+    lods: Vec<LodMesh>,
+}
+
+impl Sphere {
+    /// Create sphere with `sectors` and `stacks` at the most detailed level.
+    pub fn new(
+        res: &Resources,
+        gl: &gl::Gl,
+        sectors: u32,
+        stacks: u32,
+    ) -> Result<Sphere, failure::Error> {
+        // set up shader program
+
+        let program = render_gl::Program::from_res(gl, res, "shaders/sphere")?;
+        let program_view_projection_location = program.get_uniform_location("ViewProjection")?;
+        let program_model_location = program.get_uniform_location("Model")?;
+        let camera_pos_location = program.get_uniform_location("CameraPos")?;
+        let light_dir_location = program.get_uniform_location("LightDir")?;
+        let light_color_location = program.get_uniform_location("LightColor")?;
+        let surface_color_location = program.get_uniform_location("SurfaceColor")?;
+        let emissive_location = program.get_uniform_location("Emissive")?;
+        let textured_location = program.get_uniform_location("Textured")?;
+
+        let lods = (0..LOD_LEVELS)
+            .map(|level| LodMesh::new(gl, sectors >> level, stacks >> level))
+            .collect();
+
+        Ok(Sphere {
+            program,
+            program_view_projection_location,
+            program_model_location,
+            camera_pos_location,
+            light_dir_location,
+            light_color_location,
+            surface_color_location,
+            emissive_location,
+            textured_location,
+            light_color: na::Vector3::new(1.0, 1.0, 1.0),
+            lods,
+        })
+    }
+
+    /// Center and radius of a sphere enclosing the mesh, in model space.
+    pub fn bounding_sphere(&self) -> (na::Point3<f32>, f32) {
+        (na::Point3::origin(), 1.0)
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (
+            program_view_projection_location,
+            program_model_location,
+            camera_pos_location,
+            light_dir_location,
+            light_color_location,
+            surface_color_location,
+            emissive_location,
+            textured_location,
+        ) = self.program.reload(res, |program| {
+            Ok((
+                program.get_uniform_location("ViewProjection")?,
+                program.get_uniform_location("Model")?,
+                program.get_uniform_location("CameraPos")?,
+                program.get_uniform_location("LightDir")?,
+                program.get_uniform_location("LightColor")?,
+                program.get_uniform_location("SurfaceColor")?,
+                program.get_uniform_location("Emissive")?,
+                program.get_uniform_location("Textured")?,
+            ))
+        })?;
+        self.program_view_projection_location = program_view_projection_location;
+        self.program_model_location = program_model_location;
+        self.camera_pos_location = camera_pos_location;
+        self.light_dir_location = light_dir_location;
+        self.light_color_location = light_color_location;
+        self.surface_color_location = surface_color_location;
+        self.emissive_location = emissive_location;
+        self.textured_location = textured_location;
+        Ok(())
+    }
+
+    /// Render sphere lit by directional light, where `light_dir` points towards the light.
+    ///
+    /// The level of detail is picked from the size of the sphere on a viewport
+    /// `viewport_height` pixels tall. The model matrix should scale uniformly.
+    pub fn render(
+        &self,
+        gl: &gl::Gl,
+        camera: &TargetCamera,
+        viewport_height: f32,
+        model_matrix: &na::Matrix4<f32>,
+        light_dir: &na::Vector3<f32>,
+        surface: &Surface,
+    ) {
+        let center = model_matrix.transform_point(&na::Point3::origin());
+        let radius = model_matrix.transform_vector(&na::Vector3::x()).norm();
+        let pixel_size = camera.projected_size(&center, radius, viewport_height);
+        let lod = &self.lods[lod_level(pixel_size, self.lods.len())];
+
+        self.program.set_used();
+        self.program.set_uniform_matrix4fv(
+            self.program_view_projection_location,
+            &camera.get_vp_matrix(),
+        );
+        self.program
+            .set_uniform_matrix4fv(self.program_model_location, model_matrix);
+        self.program
+            .set_uniform_3f(self.camera_pos_location, &camera.project_pos().coords);
+        self.program
+            .set_uniform_3f(self.light_dir_location, light_dir);
+        self.program
+            .set_uniform_3f(self.light_color_location, &self.light_color);
+        self.program
+            .set_uniform_3f(self.surface_color_location, &surface.color);
+        self.program
+            .set_uniform_1i(self.emissive_location, surface.emissive as i32);
+        self.program
+            .set_uniform_1i(self.textured_location, surface.texture.is_some() as i32);
+        if let Some(texture) = surface.texture {
+            texture.bind_at(0);
+        }
+        lod.vao.bind();
+
+        unsafe {
+            gl.DrawElements(
+                gl::TRIANGLES,      // mode
+                lod.index_count,    // index vertex count
+                gl::UNSIGNED_INT,   // index type
+                ::std::ptr::null(), // pointer to indices (we are using ebo configured at vao creation)
+            );
+        }
+
+        if let Some(texture) = surface.texture {
+            texture.unbind_at(0);
+        }
+    }
+}
+
+/// Generate vertices and triangle indices for a UV sphere of radius 1.
+///
+/// There are `(sectors + 1) * (stacks + 1)` vertices: the seam and the poles are duplicated
+/// so that every vertex can have its own texture coordinate.
+pub(crate) fn generate_mesh(sectors: u32, stacks: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let sectors = sectors.max(3);
+    let stacks = stacks.max(2);
+
+    let mut vertices = Vec::with_capacity(((sectors + 1) * (stacks + 1)) as usize);
+
+    for i in 0..=stacks {
+        // from north to south pole
+        let stack_angle = PI / 2.0 - i as f32 * PI / stacks as f32;
+        let xy = stack_angle.cos();
+        let z = stack_angle.sin();
+
+        for j in 0..=sectors {
+            let sector_angle = j as f32 * 2.0 * PI / sectors as f32;
+            let x = xy * sector_angle.cos();
+            let y = xy * sector_angle.sin();
+
+            vertices.push(Vertex {
+                pos: (x, y, z).into(),
+                normal: (x, y, z).into(),
+                uv: (j as f32 / sectors as f32, i as f32 / stacks as f32).into(),
+            });
+        }
+    }
+
+    let mut indices = Vec::with_capacity((sectors * (stacks - 1) * 6) as usize);
+
+    for i in 0..stacks {
+        let k1 = i * (sectors + 1);
+        let k2 = k1 + sectors + 1;
+
+        for j in 0..sectors {
+            // poles have a single triangle per sector
+            if i != 0 {
+                indices.extend_from_slice(&[k1 + j, k2 + j, k1 + j + 1]);
+            }
+            if i != stacks - 1 {
+                indices.extend_from_slice(&[k1 + j + 1, k2 + j, k2 + j + 1]);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesh_has_expected_vertex_and_index_counts() {
+        let (vertices, indices) = generate_mesh(16, 8);
+        assert_eq!(vertices.len(), 17 * 9);
+        // two triangles per quad, except one at each pole
+        assert_eq!(indices.len(), 16 * (8 - 1) * 6);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+    }
+
+    #[test]
+    fn smaller_spheres_use_coarser_levels() {
+        assert_eq!(lod_level(200.0, 4), 0);
+        assert_eq!(lod_level(FULL_DETAIL_PIXELS, 4), 0);
+        assert_eq!(lod_level(FULL_DETAIL_PIXELS - 1.0, 4), 1);
+        assert_eq!(lod_level(FULL_DETAIL_PIXELS / 2.0 - 1.0, 4), 2);
+        // never past the last level
+        assert_eq!(lod_level(0.5, 4), 3);
+        assert_eq!(lod_level(0.5, 1), 0);
+
+        let levels = (0..200)
+            .map(|pixels| lod_level(pixels as f32, 4))
+            .collect::<Vec<_>>();
+        assert!(levels.windows(2).all(|pair| pair[1] <= pair[0]));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::render_gl::{self, buffer, data};
+use crate::resources::{self, Resources};
+use crate::sky_circles;
+use nalgebra as na;
+use serde::Deserialize;
+
+/// Radius of the celestial sphere the stars are placed on, around the camera.
+const STAR_SPHERE_RADIUS: f32 = 500.0;
+
+/// Point size in pixels of a magnitude 0 star.
+const MAGNITUDE_ZERO_POINT_SIZE: f32 = 5.0;
+
+/// Range of star point sizes in pixels, so faint stars stay visible and bright ones small.
+const MIN_POINT_SIZE: f32 = 1.0;
+const MAX_POINT_SIZE: f32 = 10.0;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to load resource {}", name)]
+    ResourceLoad {
+        name: String,
+        #[cause]
+        inner: resources::Error,
+    },
+    #[fail(display = "Failed to parse {}", name)]
+    Parse {
+        name: String,
+        #[cause]
+        inner: ron::de::Error,
+    },
+    #[fail(
+        display = "Invalid field {} of star {} in {}: {}",
+        field, star, name, reason
+    )]
+    InvalidField {
+        name: String,
+        star: String,
+        field: String,
+        reason: String,
+    },
+}
+
+/// Star as written in the catalog, with equatorial coordinates for epoch J2000.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StarDef {
+    pub name: String,
+    /// Right ascension in hours.
+    pub ra: f64,
+    /// Declination in degrees.
+    pub dec: f64,
+    /// Apparent visual magnitude, lower is brighter.
+    pub magnitude: f32,
+}
+
+impl StarDef {
+    /// Direction to the star in the scene, where the ecliptic is the XY plane.
+    pub fn direction(&self) -> na::Vector3<f32> {
+        let equatorial: na::Vector3<f32> = na::convert(equatorial_direction(self.ra, self.dec));
+        sky_circles::equator_rotation() * equatorial
+    }
+}
+
+/// Unit vector for right ascension (hours) and declination (degrees), with X towards the
+/// vernal equinox and Z towards the north celestial pole.
+pub fn equatorial_direction(ra_hours: f64, dec_degrees: f64) -> na::Vector3<f64> {
+    let ra = (ra_hours * 15.0).to_radians();
+    let dec = dec_degrees.to_radians();
+
+    na::Vector3::new(dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin())
+}
+
+/// Point size in pixels for a star of apparent `magnitude`.
+///
+/// Each magnitude is 2.5 times less light, so diameter shrinks by the square root of that.
+pub fn point_size(magnitude: f32) -> f32 {
+    (MAGNITUDE_ZERO_POINT_SIZE * 10f32.powf(-0.2 * magnitude)).clamp(MIN_POINT_SIZE, MAX_POINT_SIZE)
+}
+
+/// Load star catalog from RON resource.
+pub fn load(res: &Resources, name: &str) -> Result<Vec<StarDef>, Error> {
+    let text = res.load_string(name).map_err(|e| Error::ResourceLoad {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    parse(name, &text)
+}
+
+/// Parse and validate star catalog, using `name` in errors.
+pub fn parse(name: &str, text: &str) -> Result<Vec<StarDef>, Error> {
+    let stars: Vec<StarDef> = ron::de::from_str(text).map_err(|e| Error::Parse {
+        name: name.into(),
+        inner: e,
+    })?;
+
+    for star in &stars {
+        validate(name, star)?;
+    }
+
+    Ok(stars)
+}
+
+fn validate(name: &str, star: &StarDef) -> Result<(), Error> {
+    let invalid = |field: &str, reason: &str| Error::InvalidField {
+        name: name.into(),
+        star: star.name.clone(),
+        field: field.into(),
+        reason: reason.into(),
+    };
+
+    if !(0.0..24.0).contains(&star.ra) {
+        return Err(invalid("ra", "must be in range [0, 24) hours"));
+    }
+    if !(-90.0..=90.0).contains(&star.dec) {
+        return Err(invalid("dec", "must be in range [-90, 90] degrees"));
+    }
+    if !star.magnitude.is_finite() {
+        return Err(invalid("magnitude", "must be a number"));
+    }
+
+    Ok(())
+}
+
+#[derive(VertexAttribPointers, Copy, Clone, Debug)]
+#[repr(C, packed)]
+struct Vertex {
+    #[location = "0"]
+    pos: data::f32_f32_f32,
+    #[location = "1"]
+    size: data::f32_,
+}
+
+/// Catalog stars drawn as points around the camera, behind everything else.
+pub struct Stars {
+    program: render_gl::Program,
+    program_view_location: i32,
+    program_projection_location: i32,
+    _vbo: buffer::ArrayBuffer,
+    vao: buffer::VertexArray,
+    count: usize,
+}
+
+impl Stars {
+    pub fn new(res: &Resources, gl: &gl::Gl, stars: &[StarDef]) -> Result<Stars, failure::Error> {
+        let program = render_gl::Program::from_res(gl, res, "shaders/stars")?;
+        let program_view_location = program.get_uniform_location("View")?;
+        let program_projection_location = program.get_uniform_location("Projection")?;
+
+        let vbo_data: Vec<Vertex> = stars
+            .iter()
+            .map(|star| {
+                let pos = star.direction() * STAR_SPHERE_RADIUS;
+                Vertex {
+                    pos: (pos.x, pos.y, pos.z).into(),
+                    size: point_size(star.magnitude).into(),
+                }
+            })
+            .collect();
+
+        let vbo = buffer::ArrayBuffer::new(gl);
+        vbo.bind();
+        vbo.static_draw_data(&vbo_data);
+        vbo.unbind();
+
+        let vao = buffer::VertexArray::new(gl);
+
+        vao.bind();
+        vbo.bind();
+        Vertex::vertex_attrib_pointers(gl);
+        vbo.unbind();
+        vao.unbind();
+
+        Ok(Stars {
+            program,
+            program_view_location,
+            program_projection_location,
+            _vbo: vbo,
+            vao,
+            count: vbo_data.len(),
+        })
+    }
+
+    /// Reload shader program from resources and refresh uniform locations.
+    pub fn reload_shaders(&mut self, res: &Resources) -> Result<(), failure::Error> {
+        let (program_view_location, program_projection_location) =
+            self.program.reload(res, |program| {
+                Ok((
+                    program.get_uniform_location("View")?,
+                    program.get_uniform_location("Projection")?,
+                ))
+            })?;
+        self.program_view_location = program_view_location;
+        self.program_projection_location = program_projection_location;
+        Ok(())
+    }
+
+    /// Render stars blended additively, using a view matrix without translation so they stay
+    /// around the camera. Changed state is restored.
+    pub fn render(&self, gl: &gl::Gl, view: &na::Matrix4<f32>, projection: &na::Matrix4<f32>) {
+        self.program.set_used();
+        self.program
+            .set_uniform_matrix4fv(self.program_view_location, view);
+        self.program
+            .set_uniform_matrix4fv(self.program_projection_location, projection);
+        self.vao.bind();
+
+        unsafe {
+            let point_size_enabled = gl.IsEnabled(gl::PROGRAM_POINT_SIZE) == gl::TRUE;
+            let blend_enabled = gl.IsEnabled(gl::BLEND) == gl::TRUE;
+            let mut blend_func: [gl::types::GLint; 4] = [0; 4];
+            let blend_params = [
+                gl::BLEND_SRC_RGB,
+                gl::BLEND_DST_RGB,
+                gl::BLEND_SRC_ALPHA,
+                gl::BLEND_DST_ALPHA,
+            ];
+            for (value, &name) in blend_func.iter_mut().zip(blend_params.iter()) {
+                gl.GetIntegerv(name, value);
+            }
+            let mut depth_mask: gl::types::GLboolean = gl::TRUE;
+            gl.GetBooleanv(gl::DEPTH_WRITEMASK, &mut depth_mask);
+
+            gl.Enable(gl::PROGRAM_POINT_SIZE);
+            gl.DepthMask(gl::FALSE);
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::ONE, gl::ONE);
+
+            gl.DrawArrays(
+                gl::POINTS,                       // mode
+                0,                                // starting index in the enabled arrays
+                self.count as gl::types::GLsizei, // number of points to be rendered
+            );
+
+            gl.BlendFuncSeparate(
+                blend_func[0] as gl::types::GLenum,
+                blend_func[1] as gl::types::GLenum,
+                blend_func[2] as gl::types::GLenum,
+                blend_func[3] as gl::types::GLenum,
+            );
+            if !blend_enabled {
+                gl.Disable(gl::BLEND);
+            }
+            gl.DepthMask(depth_mask);
+            if !point_size_enabled {
+                gl.Disable(gl::PROGRAM_POINT_SIZE);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    fn star(name: &str, ra: f64, dec: f64) -> StarDef {
+        StarDef {
+            name: name.to_string(),
+            ra,
+            dec,
+            magnitude: 2.0,
+        }
+    }
+
+    #[test]
+    fn polaris_lies_near_ecliptic_longitude_90() {
+        let direction = star("Polaris", 2.5303, 89.2641).direction();
+        let longitude = direction.y.atan2(direction.x).to_degrees();
+        let latitude = direction.z.asin().to_degrees();
+
+        assert!((longitude - 88.6).abs() < 1.0, "longitude {}", longitude);
+        assert!((latitude - 66.1).abs() < 1.0, "latitude {}", latitude);
+    }
+
+    #[test]
+    fn render_restores_depth_mask_and_blend_func() {
+        let gl = mock_gl::load();
+        let res = mock_gl::test_resources();
+        let catalog = vec![
+            star("Polaris", 2.5303, 89.2641),
+            star("Vega", 18.6156, 38.7837),
+        ];
+        let stars = Stars::new(&res, &gl, &catalog).unwrap();
+
+        unsafe {
+            gl.DepthMask(gl::FALSE);
+            gl.BlendFuncSeparate(gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+        stars.render(&gl, &na::Matrix4::identity(), &na::Matrix4::identity());
+
+        mock_gl::with_state(|state| {
+            assert!(!state.depth_mask);
+            assert_eq!(
+                state.blend_func,
+                [gl::ONE, gl::ZERO, gl::ONE, gl::ONE_MINUS_SRC_ALPHA]
+            );
+            assert!(!state.enabled.contains(&gl::BLEND));
+            assert!(!state.enabled.contains(&gl::PROGRAM_POINT_SIZE));
+            assert_eq!(state.draws, vec![2]);
+        });
+    }
+}
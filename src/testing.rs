@@ -0,0 +1,169 @@
+//! Offscreen rendering helpers for checking rendering output without a visible window.
+
+use failure::err_msg;
+
+/// Render into an offscreen framebuffer of specified size and return its RGBA pixels.
+///
+/// A hidden window provides the OpenGL context. Rows are returned bottom-up, as read by
+/// `glReadPixels`.
+pub fn render_offscreen<F>(width: u32, height: u32, draw: F) -> Result<Vec<u8>, failure::Error>
+where
+    F: FnOnce(&gl::Gl),
+{
+    let sdl = sdl2::init().map_err(err_msg)?;
+    let video_subsystem = sdl.video().map_err(err_msg)?;
+
+    let window = video_subsystem
+        .window("R3D3 offscreen", width, height)
+        .opengl()
+        .hidden()
+        .build()?;
+
+    let (_gl_context, _) = crate::gl_context::create(&video_subsystem, &window, (4, 1))?;
+    let gl = gl::Gl::load_with(|s| {
+        video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void
+    });
+
+    let framebuffer = OffscreenFramebuffer::new(&gl, width as i32, height as i32)?;
+    framebuffer.bind();
+
+    unsafe {
+        gl.Viewport(0, 0, width as i32, height as i32);
+    }
+
+    draw(&gl);
+
+    let pixels = framebuffer.read_pixels();
+    framebuffer.unbind();
+
+    Ok(pixels)
+}
+
+/// Framebuffer object with color and depth renderbuffers.
+pub struct OffscreenFramebuffer {
+    gl: gl::Gl,
+    fbo: gl::types::GLuint,
+    color: gl::types::GLuint,
+    depth: gl::types::GLuint,
+    width: i32,
+    height: i32,
+}
+
+impl OffscreenFramebuffer {
+    pub fn new(
+        gl: &gl::Gl,
+        width: i32,
+        height: i32,
+    ) -> Result<OffscreenFramebuffer, failure::Error> {
+        let mut fbo: gl::types::GLuint = 0;
+        let mut renderbuffers: [gl::types::GLuint; 2] = [0; 2];
+
+        let status = unsafe {
+            gl.GenFramebuffers(1, &mut fbo);
+            gl.GenRenderbuffers(2, renderbuffers.as_mut_ptr());
+
+            gl.BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffers[0]);
+            gl.RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, width, height);
+            gl.FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                renderbuffers[0],
+            );
+
+            gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffers[1]);
+            gl.RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            gl.FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                renderbuffers[1],
+            );
+
+            gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+            let status = gl.CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        };
+
+        let framebuffer = OffscreenFramebuffer {
+            gl: gl.clone(),
+            fbo,
+            color: renderbuffers[0],
+            depth: renderbuffers[1],
+            width,
+            height,
+        };
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format_err!(
+                "Offscreen framebuffer is incomplete: 0x{:x}",
+                status
+            ));
+        }
+
+        Ok(framebuffer)
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+    }
+
+    pub fn unbind(&self) {
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Read RGBA pixels of bound framebuffer, bottom row first.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let mut pixels = vec![0u8; (self.width * self.height * 4) as usize];
+
+        unsafe {
+            self.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            self.gl.ReadPixels(
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut gl::types::GLvoid,
+            );
+        }
+
+        pixels
+    }
+}
+
+impl Drop for OffscreenFramebuffer {
+    fn drop(&mut self) {
+        let renderbuffers = [self.color, self.depth];
+        unsafe {
+            self.gl.DeleteRenderbuffers(2, renderbuffers.as_ptr());
+            self.gl.DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a display and an OpenGL driver, like the rest of this module.
+    #[test]
+    fn clear_to_red_reads_back_red() {
+        let pixels = render_offscreen(4, 4, |gl| unsafe {
+            gl.ClearColor(1.0, 0.0, 0.0, 1.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT);
+        })
+        .unwrap();
+
+        assert_eq!(pixels.len(), 4 * 4 * 4);
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::resources::{self, Resources};
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to start texture loader thread")]
+    Spawn(#[cause] io::Error),
+}
+
+/// Image decoded on the loader thread, waiting to be uploaded to GL.
+pub struct Decoded<K> {
+    /// Key the image was requested with.
+    pub key: K,
+    pub name: String,
+    pub image: Result<image::RgbaImage, resources::Error>,
+}
+
+/// Decodes image resources on a background thread, so large textures do not stall the window.
+///
+/// GL objects can only be created on the thread owning the context, so decoded images are
+/// handed back through `ready` for that thread to upload. Images come back in request order.
+pub struct TextureLoader<K> {
+    requests: mpsc::Sender<(K, String)>,
+    decoded: mpsc::Receiver<Decoded<K>>,
+    pending: usize,
+}
+
+impl<K: Send + 'static> TextureLoader<K> {
+    /// Start the loader thread, reading images from `res`.
+    ///
+    /// The thread stops once the loader is dropped and the image it works on is decoded.
+    pub fn new(res: &Resources) -> Result<TextureLoader<K>, Error> {
+        let (requests, requested) = mpsc::channel::<(K, String)>();
+        let (finished, decoded) = mpsc::channel();
+        let res = res.clone();
+
+        thread::Builder::new()
+            .name("texture loader".into())
+            .spawn(move || {
+                for (key, name) in requested {
+                    let image = res.load_image(&name);
+                    if finished.send(Decoded { key, name, image }).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(Error::Spawn)?;
+
+        Ok(TextureLoader {
+            requests,
+            decoded,
+            pending: 0,
+        })
+    }
+
+    /// Queue image resource `name` for decoding, to be returned with `key`.
+    pub fn request(&mut self, key: K, name: &str) {
+        match self.requests.send((key, name.into())) {
+            Ok(()) => self.pending += 1,
+            Err(_) => warn!("Texture loader stopped, not loading {}", name),
+        }
+    }
+
+    /// Images decoded since the last call, without waiting for the rest.
+    pub fn ready(&mut self) -> Vec<Decoded<K>> {
+        let ready = self.decoded.try_iter().collect::<Vec<_>>();
+        self.pending = self.pending.saturating_sub(ready.len());
+        ready
+    }
+
+    /// Number of requested images not returned by `ready` yet.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn decoded_images_are_handed_back_in_request_order() {
+        let res = mock_gl::test_resources();
+        let mut loader = TextureLoader::new(&res).unwrap();
+        loader.request(4, "textures/mars.png");
+        loader.request(7, "textures/missing.png");
+        assert_eq!(loader.pending(), 2);
+
+        let started = Instant::now();
+        let mut decoded = Vec::new();
+        while loader.pending() > 0 && started.elapsed() < Duration::from_secs(10) {
+            decoded.extend(loader.ready());
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(loader.pending(), 0);
+        let keys: Vec<_> = decoded.iter().map(|d| d.key).collect();
+        assert_eq!(keys, [4, 7]);
+        assert_eq!(decoded[0].image.as_ref().unwrap().dimensions(), (256, 128));
+        assert_eq!(decoded[1].name, "textures/missing.png");
+        assert!(decoded[1].image.is_err());
+        assert!(loader.ready().is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::render_gl::{DebugLines, TrailLine};
+use nalgebra as na;
+
+/// Samples kept by a trail when neither the body nor the command line sets a length.
+pub const DEFAULT_LENGTH: usize = 64;
+
+/// Upper bound of samples in any trail, keeping memory use of long trails bounded.
+pub const MAX_LENGTH: usize = 4096;
+
+/// Ring buffer of recent positions, holding at most `MAX_LENGTH` points.
+pub struct PositionHistory {
+    points: Vec<na::Point3<f32>>,
+    capacity: usize,
+    /// Index of the oldest point once the buffer is full.
+    next: usize,
+}
+
+impl PositionHistory {
+    pub fn new(capacity: usize) -> PositionHistory {
+        let capacity = capacity.clamp(1, MAX_LENGTH);
+
+        PositionHistory {
+            points: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+
+    /// Add a point, overwriting the oldest one if the buffer is full.
+    pub fn push(&mut self, point: na::Point3<f32>) {
+        if self.points.len() < self.capacity {
+            self.points.push(point);
+        } else {
+            self.points[self.next] = point;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change capacity, keeping the newest points that still fit and discarding the oldest.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let capacity = capacity.clamp(1, MAX_LENGTH);
+        if capacity == self.capacity {
+            return;
+        }
+
+        let mut points = Vec::with_capacity(capacity);
+        points.extend(self.iter_newest_first().take(capacity));
+        // stored from the oldest, so the ring starts over at index 0
+        points.reverse();
+
+        self.next = points.len() % capacity;
+        self.points = points;
+        self.capacity = capacity;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.next = 0;
+    }
+
+    /// Points from the newest to the oldest.
+    pub fn iter_newest_first<'a>(&'a self) -> impl Iterator<Item = na::Point3<f32>> + 'a {
+        let len = self.points.len();
+        let newest = (self.next + self.capacity - 1) % self.capacity;
+
+        (0..len).map(move |i| self.points[(newest + len - i) % len])
+    }
+}
+
+/// Fading line behind a moving body, sampled at fixed simulation time intervals.
+pub struct Trail {
+    history: PositionHistory,
+    line: TrailLine,
+    interval_days: f64,
+    last_sample_days: Option<f64>,
+}
+
+impl Trail {
+    pub fn new(
+        debug_lines: &DebugLines,
+        capacity: usize,
+        interval_days: f64,
+        color: na::Vector4<f32>,
+    ) -> Trail {
+        Trail {
+            history: PositionHistory::new(capacity),
+            line: debug_lines.trail(capacity.clamp(1, MAX_LENGTH) + 1, color),
+            interval_days,
+            last_sample_days: None,
+        }
+    }
+
+    /// Number of samples the trail keeps.
+    pub fn trail_length(&self) -> usize {
+        self.history.capacity()
+    }
+
+    /// Keep `length` samples from now on, capped at `MAX_LENGTH`; a shorter trail drops its
+    /// oldest samples.
+    pub fn set_trail_length(&mut self, length: usize) {
+        self.history.set_capacity(length);
+        self.line.set_capacity(self.history.capacity() + 1);
+    }
+
+    /// Forget recorded positions, for example when they are no longer in the same coordinates.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.last_sample_days = None;
+    }
+
+    /// Record body position for current simulation time, keeping the trail attached to the body.
+    pub fn update(&mut self, sim_days: f64, position: na::Point3<f32>) {
+        // time may run backwards, which keeps extending the trail along the way back
+        let due = match self.last_sample_days {
+            Some(last) => (sim_days - last).abs() >= self.interval_days,
+            None => true,
+        };
+
+        if due {
+            self.history.push(position);
+            self.last_sample_days = Some(sim_days);
+        }
+
+        self.line
+            .update_points(std::iter::once(position).chain(self.history.iter_newest_first()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::{mock_gl, trail_alpha, ColorBuffer};
+
+    fn point(x: f32) -> na::Point3<f32> {
+        na::Point3::new(x, 0.0, 0.0)
+    }
+
+    fn xs(history: &PositionHistory) -> Vec<f32> {
+        history.iter_newest_first().map(|p| p.x).collect()
+    }
+
+    #[test]
+    fn history_wraps_around_keeping_newest() {
+        let mut history = PositionHistory::new(3);
+        for x in 1..=5 {
+            history.push(point(x as f32));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(xs(&history), [5.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn alpha_fades_from_head_to_tail() {
+        let alphas: Vec<_> = (0..5).map(|i| trail_alpha(i, 5)).collect();
+        assert_eq!(alphas[0], 1.0);
+        assert_eq!(alphas[4], 0.0);
+        assert!(alphas.windows(2).all(|pair| pair[1] < pair[0]));
+
+        let (gl, mut debug_lines) = mock_gl::test_debug_lines();
+        let line = debug_lines.trail(DEFAULT_LENGTH, na::Vector4::new(1.0, 1.0, 1.0, 1.0));
+        line.update_points((0..DEFAULT_LENGTH).map(|x| point(x as f32)));
+        debug_lines.render(&gl, &ColorBuffer::new(), &na::Matrix4::identity());
+
+        // 16 byte line points with alpha in the last byte, two points per segment
+        let stored: Vec<u8> =
+            mock_gl::with_state(|state| state.mapped.chunks(16).map(|p| p[15]).collect());
+        assert_eq!(stored.len(), (DEFAULT_LENGTH - 1) * 2);
+        let segment_heads: Vec<u8> = stored.iter().step_by(2).cloned().collect();
+        assert_eq!(segment_heads[0], 255);
+        assert!(segment_heads.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn trail_keeps_sampling_when_time_runs_backwards() {
+        let debug_lines = mock_gl::test_debug_lines().1;
+        let mut trail = Trail::new(&debug_lines, 64, 1.0, na::Vector4::new(1.0, 1.0, 1.0, 1.0));
+
+        for day in 0..=10 {
+            trail.update(day as f64, point(day as f32));
+        }
+        assert_eq!(trail.history.len(), 11);
+
+        // reversed clock, moving back by half the interval each frame
+        for half_days in (0..20).rev() {
+            let day = half_days as f64 / 2.0;
+            trail.update(day, point(day as f32));
+        }
+        assert_eq!(trail.history.len(), 21);
+        assert_eq!(xs(&trail.history)[..3], [0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn resizing_a_partly_filled_history_keeps_newest_points() {
+        let mut history = PositionHistory::new(8);
+        for x in 1..=5 {
+            history.push(point(x as f32));
+        }
+
+        history.set_capacity(3);
+        assert_eq!((history.len(), history.capacity()), (3, 3));
+        assert_eq!(xs(&history), [5.0, 4.0, 3.0]);
+
+        history.set_capacity(6);
+        assert_eq!((history.len(), history.capacity()), (3, 6));
+        for x in 6..=9 {
+            history.push(point(x as f32));
+        }
+        assert_eq!(xs(&history), [9.0, 8.0, 7.0, 6.0, 5.0, 4.0]);
+    }
+}
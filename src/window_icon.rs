@@ -0,0 +1,52 @@
+use crate::debug;
+use crate::resources::Resources;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+
+/// Surface sharing the pixels of a decoded RGBA image, for use as window icon.
+pub fn surface(image: &mut image::RgbaImage) -> Result<Surface<'_>, String> {
+    let (width, height) = image.dimensions();
+
+    // RGBA32 is the format with bytes in R, G, B, A order on either endianness
+    Surface::from_data(image, width, height, width * 4, PixelFormatEnum::RGBA32)
+}
+
+/// Set the window icon from an image resource, warning and keeping no icon if that fails.
+pub fn set(window: &mut sdl2::video::Window, res: &Resources, name: &str) {
+    let mut image = match res.load_image(name) {
+        Ok(image) => image,
+        Err(e) => {
+            warn!(
+                "Failed to load window icon {}: {}",
+                name,
+                debug::failure_to_string(e.into())
+            );
+            return;
+        }
+    };
+
+    // the surface borrows the image pixels, SDL copies them when setting the icon
+    let icon = surface(&mut image);
+    match icon {
+        Ok(icon) => window.set_icon(icon),
+        Err(e) => warn!("Failed to create window icon {}: {}", name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn icon_surface_keeps_image_size_and_byte_order() {
+        let res = mock_gl::test_resources();
+        let mut image = res.load_image("icon.png").unwrap();
+        let (width, height) = image.dimensions();
+
+        let icon = surface(&mut image).unwrap();
+        assert_eq!((icon.width(), icon.height()), (width, height));
+        assert_eq!(icon.pitch(), width * 4);
+        assert_eq!(icon.pixel_format_enum(), PixelFormatEnum::RGBA32);
+    }
+}
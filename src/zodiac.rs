@@ -0,0 +1,83 @@
+use crate::render_gl::{DebugLines, Polyline};
+use nalgebra as na;
+use std::f32::consts::PI;
+
+/// Number of zodiac signs, each spanning 30 degrees of ecliptic longitude.
+pub const SIGN_COUNT: usize = 12;
+
+const CIRCLE_SEGMENTS: usize = 120;
+
+/// Ring of zodiac divisions drawn on the ecliptic plane around the Sun.
+///
+/// Longitude 0 (start of Aries, the vernal equinox) lies on the X axis.
+pub struct ZodiacWheel {
+    _circle: Polyline,
+    _ticks: Vec<Polyline>,
+}
+
+impl ZodiacWheel {
+    /// Create ring of specified radius on the plane perpendicular to `ecliptic_normal`.
+    pub fn new(
+        debug_lines: &DebugLines,
+        radius: f32,
+        ecliptic_normal: &na::Vector3<f32>,
+    ) -> ZodiacWheel {
+        let color = na::Vector4::new(0.8, 0.7, 0.3, 0.6);
+        let orientation = na::UnitQuaternion::rotation_between(&na::Vector3::z(), ecliptic_normal)
+            .unwrap_or_else(|| na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), PI));
+
+        let point_at = |longitude: f32, radius: f32| {
+            orientation * na::Vector3::new(radius * longitude.cos(), radius * longitude.sin(), 0.0)
+        };
+
+        let circle = (1..CIRCLE_SEGMENTS)
+            .fold(
+                debug_lines.start_polyline(point_at(0.0, radius), color),
+                |builder, i| {
+                    let longitude = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * PI;
+                    builder.with_point(point_at(longitude, radius), color)
+                },
+            )
+            .close_and_finish();
+
+        let ticks = tick_longitudes()
+            .map(|longitude| {
+                debug_lines
+                    .start_polyline(point_at(longitude, radius * 0.9), color)
+                    .with_point(point_at(longitude, radius * 1.1), color)
+                    .finish()
+            })
+            .collect();
+
+        ZodiacWheel {
+            _circle: circle,
+            _ticks: ticks,
+        }
+    }
+}
+
+/// Ecliptic longitudes of sign boundaries, in radians.
+pub fn tick_longitudes() -> impl Iterator<Item = f32> {
+    (0..SIGN_COUNT).map(|i| i as f32 * 2.0 * PI / SIGN_COUNT as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_gl::mock_gl;
+
+    #[test]
+    fn wheel_has_twelve_ticks_thirty_degrees_apart() {
+        let longitudes: Vec<_> = tick_longitudes().collect();
+        assert_eq!(longitudes.len(), 12);
+        for pair in longitudes.windows(2) {
+            assert!((pair[1] - pair[0] - 30f32.to_radians()).abs() < 1e-5);
+        }
+
+        let debug_lines = mock_gl::test_debug_lines().1;
+        let wheel = ZodiacWheel::new(&debug_lines, 5.0, &na::Vector3::z());
+        assert_eq!(wheel._ticks.len(), 12);
+        // the circle and one line per tick
+        assert_eq!(debug_lines.active_count(), 13);
+    }
+}